@@ -0,0 +1,8 @@
+-- Card priority and estimates
+CREATE TYPE card_priority AS ENUM ('low', 'medium', 'high', 'urgent');
+CREATE TYPE estimate_unit AS ENUM ('points', 'hours');
+
+ALTER TABLE card
+    ADD COLUMN priority card_priority,
+    ADD COLUMN estimate DOUBLE PRECISION CHECK (estimate >= 0),
+    ADD COLUMN estimate_unit estimate_unit;
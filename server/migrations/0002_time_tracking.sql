@@ -0,0 +1,19 @@
+-- Time tracking on cards
+CREATE TABLE time_entry (
+    id SERIAL PRIMARY KEY,
+    card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    ended_at TIMESTAMPTZ,
+    duration_seconds BIGINT CHECK (duration_seconds >= 0),
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX time_entry_card_id_idx ON time_entry (card_id);
+CREATE INDEX time_entry_started_at_idx ON time_entry (started_at);
+
+-- At most one running timer per user
+CREATE UNIQUE INDEX time_entry_one_running_per_user_idx
+    ON time_entry (user_id)
+    WHERE ended_at IS NULL;
@@ -0,0 +1,48 @@
+-- Human-readable card keys per board (e.g. OPS-42)
+ALTER TABLE board
+    ADD COLUMN key_prefix TEXT,
+    ADD COLUMN next_card_number INTEGER NOT NULL DEFAULT 1;
+
+UPDATE board SET key_prefix = 'B' || id;
+
+ALTER TABLE board ALTER COLUMN key_prefix SET NOT NULL;
+CREATE UNIQUE INDEX board_key_prefix_idx ON board (key_prefix);
+
+ALTER TABLE card
+    ADD COLUMN card_number INTEGER,
+    ADD COLUMN card_key TEXT;
+
+-- Number existing cards per board in creation order
+WITH numbered AS (
+    SELECT c.id,
+           bc.board_id,
+           ROW_NUMBER() OVER (PARTITION BY bc.board_id ORDER BY c.created_at ASC, c.id ASC) AS n
+    FROM card c
+    JOIN board_column bc ON bc.id = c.list_id
+)
+UPDATE card
+SET card_number = numbered.n,
+    card_key = b.key_prefix || '-' || numbered.n
+FROM numbered
+JOIN board b ON b.id = numbered.board_id
+WHERE card.id = numbered.id;
+
+UPDATE board
+SET next_card_number = COALESCE((
+    SELECT MAX(c.card_number)
+    FROM card c
+    JOIN board_column bc ON bc.id = c.list_id
+    WHERE bc.board_id = board.id
+), 0) + 1;
+
+ALTER TABLE card
+    ALTER COLUMN card_number SET NOT NULL,
+    ALTER COLUMN card_key SET NOT NULL;
+CREATE UNIQUE INDEX card_card_key_idx ON card (card_key);
+
+-- Keys a card used on boards it has since moved away from
+CREATE TABLE card_key_alias (
+    key TEXT PRIMARY KEY,
+    card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
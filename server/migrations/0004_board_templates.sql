@@ -0,0 +1,11 @@
+-- Board templates (built-in templates are synchronised at startup by slug)
+CREATE TABLE board_template (
+    id SERIAL PRIMARY KEY,
+    slug TEXT UNIQUE,
+    name TEXT NOT NULL,
+    description TEXT,
+    built_in BOOLEAN NOT NULL DEFAULT FALSE,
+    definition JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,6 @@
+-- Work-in-progress limits per column
+CREATE TYPE wip_mode AS ENUM ('advisory', 'strict');
+
+ALTER TABLE board_column
+    ADD COLUMN wip_limit INTEGER CHECK (wip_limit > 0),
+    ADD COLUMN wip_mode wip_mode NOT NULL DEFAULT 'advisory';
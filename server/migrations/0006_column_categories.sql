@@ -0,0 +1,9 @@
+-- Column categories and card lifecycle timestamps
+CREATE TYPE column_category AS ENUM ('backlog', 'todo', 'in_progress', 'done');
+
+ALTER TABLE board_column
+    ADD COLUMN category column_category NOT NULL DEFAULT 'todo';
+
+ALTER TABLE card
+    ADD COLUMN started_at TIMESTAMPTZ,
+    ADD COLUMN completed_at TIMESTAMPTZ;
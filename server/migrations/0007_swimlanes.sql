@@ -0,0 +1,31 @@
+-- Swimlanes: ordered rows spanning all columns of a board
+CREATE TABLE swimlane (
+    id SERIAL PRIMARY KEY,
+    board_id INTEGER NOT NULL REFERENCES board(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Every board has exactly one default lane
+CREATE UNIQUE INDEX swimlane_default_idx ON swimlane (board_id) WHERE is_default;
+
+INSERT INTO swimlane (board_id, title, position, is_default)
+SELECT id, 'Default', 0, TRUE FROM board;
+
+-- Existing cards start in their board's default lane; card positions are
+-- now scoped to a (list, lane) cell
+ALTER TABLE card ADD COLUMN lane_id INTEGER;
+
+UPDATE card
+SET lane_id = s.id
+FROM board_column bc
+JOIN swimlane s ON s.board_id = bc.board_id AND s.is_default
+WHERE bc.id = card.list_id;
+
+ALTER TABLE card
+    ALTER COLUMN lane_id SET NOT NULL,
+    ADD CONSTRAINT card_lane_id_fkey FOREIGN KEY (lane_id) REFERENCES swimlane(id);
+CREATE INDEX card_list_lane_idx ON card (list_id, lane_id, position);
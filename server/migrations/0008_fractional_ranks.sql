@@ -0,0 +1,62 @@
+-- Fractional ranks replace contiguous integer positions for cards and columns.
+-- A rank is a base-62 fraction compared bytewise; the API derives `position`
+-- from it. See handlers/ranks.rs.
+
+-- Evenly spaced rank `n` of `total`, matching ranks::spread_ranks
+CREATE FUNCTION pg_temp.spread_rank(n BIGINT, total BIGINT) RETURNS TEXT AS $$
+DECLARE
+    digits CONSTANT TEXT := '0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz';
+    width INTEGER := 1;
+    value NUMERIC;
+    result TEXT := '';
+BEGIN
+    WHILE 62::NUMERIC ^ width <= total + 1 LOOP
+        width := width + 1;
+    END LOOP;
+    value := FLOOR(n * 62::NUMERIC ^ width / (total + 1));
+    FOR i IN 1..width LOOP
+        result := SUBSTR(digits, (MOD(value, 62))::INTEGER + 1, 1) || result;
+        value := FLOOR(value / 62);
+    END LOOP;
+    RETURN RTRIM(result, '0');
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+ALTER TABLE card ADD COLUMN rank TEXT COLLATE "C";
+
+WITH ordered AS (
+    SELECT id,
+           ROW_NUMBER() OVER (PARTITION BY list_id, lane_id ORDER BY position ASC, id ASC) AS n,
+           COUNT(*) OVER (PARTITION BY list_id, lane_id) AS total
+    FROM card
+)
+UPDATE card
+SET rank = pg_temp.spread_rank(ordered.n, ordered.total)
+FROM ordered
+WHERE card.id = ordered.id;
+
+ALTER TABLE board_column ADD COLUMN rank TEXT COLLATE "C";
+
+WITH ordered AS (
+    SELECT id,
+           ROW_NUMBER() OVER (PARTITION BY board_id ORDER BY position ASC, id ASC) AS n,
+           COUNT(*) OVER (PARTITION BY board_id) AS total
+    FROM board_column
+)
+UPDATE board_column
+SET rank = pg_temp.spread_rank(ordered.n, ordered.total)
+FROM ordered
+WHERE board_column.id = ordered.id;
+
+DROP INDEX IF EXISTS card_list_lane_idx;
+
+ALTER TABLE card
+    ALTER COLUMN rank SET NOT NULL,
+    DROP COLUMN position,
+    -- Deferred so a whole cell can be re-ranked in one statement
+    ADD CONSTRAINT card_cell_rank_key UNIQUE (list_id, lane_id, rank) DEFERRABLE INITIALLY DEFERRED;
+
+ALTER TABLE board_column
+    ALTER COLUMN rank SET NOT NULL,
+    DROP COLUMN position,
+    ADD CONSTRAINT board_column_rank_key UNIQUE (board_id, rank) DEFERRABLE INITIALLY DEFERRED;
@@ -0,0 +1,26 @@
+-- Per-board automation rules and their execution log
+CREATE TABLE automation_rule (
+    id SERIAL PRIMARY KEY,
+    board_id INTEGER NOT NULL REFERENCES board(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    trigger JSONB NOT NULL,
+    conditions JSONB NOT NULL DEFAULT '[]',
+    actions JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX automation_rule_board_idx ON automation_rule (board_id) WHERE enabled;
+
+CREATE TABLE automation_run (
+    id SERIAL PRIMARY KEY,
+    rule_id INTEGER NOT NULL REFERENCES automation_rule(id) ON DELETE CASCADE,
+    card_id INTEGER REFERENCES card(id) ON DELETE SET NULL,
+    event JSONB NOT NULL,
+    actions JSONB NOT NULL,
+    depth INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX automation_run_rule_idx ON automation_run (rule_id, created_at DESC);
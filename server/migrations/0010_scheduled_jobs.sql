@@ -0,0 +1,16 @@
+-- Periodic background jobs. Rows are registered by the server at startup;
+-- an instance leases a due job by setting locked_by/locked_until.
+CREATE TABLE scheduled_job (
+    name TEXT PRIMARY KEY,
+    schedule TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    locked_by TEXT,
+    locked_until TIMESTAMPTZ,
+    last_started_at TIMESTAMPTZ,
+    last_finished_at TIMESTAMPTZ,
+    last_error TEXT
+);
+
+CREATE INDEX scheduled_job_due_idx ON scheduled_job (next_run_at) WHERE enabled;
@@ -0,0 +1,20 @@
+-- Recurring series that copy a template card into a list on a schedule
+CREATE TYPE recurrence_status AS ENUM ('active', 'paused', 'ended');
+
+CREATE TABLE card_recurrence (
+    id SERIAL PRIMARY KEY,
+    template_card_id INTEGER NOT NULL UNIQUE REFERENCES card(id) ON DELETE CASCADE,
+    list_id INTEGER NOT NULL REFERENCES board_column(id) ON DELETE CASCADE,
+    rule JSONB NOT NULL,
+    status recurrence_status NOT NULL DEFAULT 'active',
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ,
+    lead_days INTEGER NOT NULL DEFAULT 1,
+    next_due_at TIMESTAMPTZ,
+    last_card_id INTEGER REFERENCES card(id) ON DELETE SET NULL,
+    occurrences INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX card_recurrence_due_idx ON card_recurrence (next_due_at) WHERE status = 'active';
@@ -0,0 +1,45 @@
+-- Card comments and watchers
+CREATE TABLE card_comment (
+    id SERIAL PRIMARY KEY,
+    card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+    author_id TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX card_comment_card_idx ON card_comment (card_id, created_at);
+
+CREATE TABLE card_watcher (
+    card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (card_id, user_id)
+);
+
+-- Per-user notification inbox. dedup_key identifies the card change a
+-- notification is about, so the same change never notifies a user twice.
+CREATE TYPE notification_kind AS ENUM ('assigned', 'mentioned', 'commented', 'due_soon', 'overdue');
+
+CREATE TABLE notification (
+    id SERIAL PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    kind notification_kind NOT NULL,
+    card_id INTEGER REFERENCES card(id) ON DELETE CASCADE,
+    actor_id TEXT,
+    message TEXT NOT NULL,
+    dedup_key TEXT NOT NULL,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, dedup_key)
+);
+
+CREATE INDEX notification_inbox_idx ON notification (user_id, created_at DESC);
+
+-- Kinds a user opted out of; a missing row means enabled
+CREATE TABLE notification_preference (
+    user_id TEXT NOT NULL,
+    kind notification_kind NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    PRIMARY KEY (user_id, kind)
+);
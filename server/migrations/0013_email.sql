@@ -0,0 +1,39 @@
+-- Email delivery of notifications. A user with an email_setting row gets
+-- their notifications by email, one message each or in a daily digest.
+CREATE TYPE email_mode AS ENUM ('immediate', 'digest', 'off');
+
+CREATE TABLE email_setting (
+    user_id TEXT PRIMARY KEY,
+    address TEXT NOT NULL,
+    mode email_mode NOT NULL DEFAULT 'immediate',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Set once a notification has been queued for email (or skipped), so each
+-- notification is sent at most once
+ALTER TABLE notification ADD COLUMN emailed_at TIMESTAMPTZ;
+
+CREATE INDEX notification_unemailed_idx ON notification (user_id, created_at) WHERE emailed_at IS NULL;
+
+-- Durable outbox: messages are written in the same transaction that marks
+-- their notifications emailed and are sent by a scheduled job, retrying
+-- with backoff until they are delivered or give up.
+CREATE TYPE email_status AS ENUM ('pending', 'sent', 'failed');
+
+CREATE TABLE email_outbox (
+    id SERIAL PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    to_address TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    text_body TEXT NOT NULL,
+    html_body TEXT NOT NULL,
+    status email_status NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX email_outbox_due_idx ON email_outbox (next_attempt_at) WHERE status = 'pending';
@@ -0,0 +1,44 @@
+-- Outgoing webhooks. A board's subscriptions receive a signed JSON payload
+-- for each card, column and board event they filter on (all when `events`
+-- is empty).
+CREATE TABLE webhook (
+    id SERIAL PRIMARY KEY,
+    board_id INTEGER NOT NULL REFERENCES board(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    events JSONB NOT NULL DEFAULT '[]',
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Failed attempts since the last successful delivery; the webhook is
+    -- disabled when this reaches a limit
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    disabled_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX webhook_board_idx ON webhook (board_id);
+
+-- Deliveries are written in the transaction of the change they report and
+-- sent by a scheduled job, retrying with backoff
+CREATE TYPE webhook_delivery_status AS ENUM ('pending', 'delivered', 'failed');
+
+CREATE TABLE webhook_delivery (
+    id SERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES webhook(id) ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status webhook_delivery_status NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    response_status INTEGER,
+    response_body TEXT,
+    error TEXT,
+    duration_ms INTEGER,
+    delivered_at TIMESTAMPTZ,
+    -- Set on deliveries created by redelivering another one
+    redelivery_of INTEGER REFERENCES webhook_delivery(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX webhook_delivery_due_idx ON webhook_delivery (next_attempt_at) WHERE status = 'pending';
+CREATE INDEX webhook_delivery_history_idx ON webhook_delivery (webhook_id, created_at DESC);
@@ -0,0 +1,19 @@
+-- Git commits that reference a card, recorded from push webhooks. A commit
+-- is linked to each card once, however often its push is delivered.
+CREATE TABLE card_commit (
+    card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+    sha TEXT NOT NULL,
+    repository TEXT NOT NULL,
+    branch TEXT,
+    message TEXT NOT NULL,
+    url TEXT,
+    author_name TEXT,
+    author_username TEXT,
+    committed_at TIMESTAMPTZ,
+    -- Whether the message closes the card ("fixes OPS-42")
+    closes BOOLEAN NOT NULL DEFAULT FALSE,
+    -- When a closing commit closed the card, once pushed to the default branch
+    closed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (card_id, sha)
+);
@@ -0,0 +1,23 @@
+-- Display names shown for users, who are otherwise only known by their ID
+CREATE TABLE user_profile (
+    user_id TEXT PRIMARY KEY,
+    display_name TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- @mentions of board members in card descriptions and comments. Spans are
+-- character offsets into the text, end exclusive. Rewritten whenever the
+-- text changes.
+CREATE TABLE mention (
+    id SERIAL PRIMARY KEY,
+    card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+    -- NULL for mentions in the card's description
+    comment_id INTEGER REFERENCES card_comment(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    span_start INTEGER NOT NULL,
+    span_end INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX mention_card_idx ON mention (card_id, comment_id);
+CREATE INDEX mention_user_idx ON mention (user_id, created_at DESC);
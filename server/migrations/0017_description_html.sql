@@ -0,0 +1,4 @@
+-- Rendered, sanitised HTML of Markdown descriptions; NULL until first
+-- requested and whenever the description changes
+ALTER TABLE card ADD COLUMN description_html TEXT;
+ALTER TABLE board ADD COLUMN description_html TEXT;
@@ -0,0 +1,14 @@
+-- Title and description history of cards. Revisions are numbered per card;
+-- each edit of the title or description adds one with the full new text.
+CREATE TABLE card_revision (
+    id SERIAL PRIMARY KEY,
+    card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    author_id TEXT,
+    -- Revision whose text this one restored
+    restored_from INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (card_id, revision)
+);
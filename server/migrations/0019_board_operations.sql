@@ -0,0 +1,16 @@
+-- Per-user, per-board undo/redo stacks. Each row is one invertible change;
+-- undoing marks it undone, redoing marks it done again, and recording a new
+-- change drops the user's undone changes on that board.
+CREATE TYPE board_operation_state AS ENUM ('done', 'undone');
+
+CREATE TABLE board_operation (
+    id SERIAL PRIMARY KEY,
+    board_id INTEGER NOT NULL REFERENCES board(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    operation JSONB NOT NULL,
+    state board_operation_state NOT NULL DEFAULT 'done',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX board_operation_stack_idx ON board_operation (board_id, user_id, id);
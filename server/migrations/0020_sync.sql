@@ -0,0 +1,134 @@
+-- Delta sync for offline clients (GET/POST /api/sync). Every change to a
+-- board, list or card takes a new version from a server-wide sequence and
+-- records the transaction that made it; deletions, and moves to another
+-- board, leave tombstones. Changing a board's owner or members stamps its
+-- access_xid, so lists and cards of a board that became visible are sent
+-- whole, and tombstones the board for those who could see it before. Sync
+-- tokens are transaction horizons; see handlers/sync.rs.
+CREATE SEQUENCE sync_change_seq;
+
+ALTER TABLE board
+    ADD COLUMN change_seq BIGINT NOT NULL DEFAULT nextval('sync_change_seq'),
+    ADD COLUMN change_xid XID8 NOT NULL DEFAULT pg_current_xact_id(),
+    ADD COLUMN created_xid XID8 NOT NULL DEFAULT pg_current_xact_id(),
+    ADD COLUMN access_xid XID8 NOT NULL DEFAULT pg_current_xact_id();
+
+ALTER TABLE board_column
+    ADD COLUMN change_seq BIGINT NOT NULL DEFAULT nextval('sync_change_seq'),
+    ADD COLUMN change_xid XID8 NOT NULL DEFAULT pg_current_xact_id(),
+    ADD COLUMN created_xid XID8 NOT NULL DEFAULT pg_current_xact_id();
+
+ALTER TABLE card
+    ADD COLUMN change_seq BIGINT NOT NULL DEFAULT nextval('sync_change_seq'),
+    ADD COLUMN change_xid XID8 NOT NULL DEFAULT pg_current_xact_id(),
+    ADD COLUMN created_xid XID8 NOT NULL DEFAULT pg_current_xact_id();
+
+CREATE INDEX board_change_xid_idx ON board (change_xid);
+CREATE INDEX board_column_change_xid_idx ON board_column (change_xid);
+CREATE INDEX card_change_xid_idx ON card (change_xid);
+
+CREATE TYPE sync_entity AS ENUM ('board', 'column', 'card');
+
+CREATE TABLE sync_tombstone (
+    id BIGSERIAL PRIMARY KEY,
+    entity sync_entity NOT NULL,
+    entity_id INTEGER NOT NULL,
+    -- Board the entity was removed from, and who could see it then
+    board_id INTEGER NOT NULL,
+    owner_id TEXT,
+    members TEXT[] NOT NULL DEFAULT '{}',
+    change_seq BIGINT NOT NULL DEFAULT nextval('sync_change_seq'),
+    change_xid XID8 NOT NULL DEFAULT pg_current_xact_id(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX sync_tombstone_change_xid_idx ON sync_tombstone (change_xid);
+
+-- Stamp inserted and changed rows. Caching rendered description HTML and
+-- allocating card numbers are not changes clients see.
+CREATE FUNCTION sync_stamp() RETURNS TRIGGER AS $$
+DECLARE
+    internal CONSTANT TEXT[] := ARRAY['description_html', 'next_card_number'];
+BEGIN
+    IF TG_OP = 'UPDATE' AND to_jsonb(NEW) - internal = to_jsonb(OLD) - internal THEN
+        RETURN NEW;
+    END IF;
+    NEW.change_seq := nextval('sync_change_seq');
+    NEW.change_xid := pg_current_xact_id();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER board_sync_stamp BEFORE INSERT OR UPDATE ON board
+    FOR EACH ROW EXECUTE FUNCTION sync_stamp();
+CREATE TRIGGER board_column_sync_stamp BEFORE INSERT OR UPDATE ON board_column
+    FOR EACH ROW EXECUTE FUNCTION sync_stamp();
+CREATE TRIGGER card_sync_stamp BEFORE INSERT OR UPDATE ON card
+    FOR EACH ROW EXECUTE FUNCTION sync_stamp();
+
+-- Tombstone an entity removed from a board. Lists and cards deleted along
+-- with their board are covered by the board's tombstone.
+CREATE FUNCTION sync_record_tombstone(removed sync_entity, removed_id INTEGER, from_board INTEGER)
+RETURNS VOID AS $$
+    INSERT INTO sync_tombstone (entity, entity_id, board_id, owner_id, members)
+    SELECT removed, removed_id, id, owner_id, members FROM board WHERE id = from_board;
+$$ LANGUAGE sql;
+
+CREATE FUNCTION board_sync_tombstone() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO sync_tombstone (entity, entity_id, board_id, owner_id, members)
+    VALUES ('board', OLD.id, OLD.id, OLD.owner_id, OLD.members);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION board_column_sync_tombstone() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM sync_record_tombstone('column', OLD.id, OLD.board_id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION card_sync_tombstone() RETURNS TRIGGER AS $$
+DECLARE
+    old_board INTEGER := (SELECT board_id FROM board_column WHERE id = OLD.list_id);
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        IF old_board = (SELECT board_id FROM board_column WHERE id = NEW.list_id) THEN
+            RETURN NULL;
+        END IF;
+    END IF;
+    PERFORM sync_record_tombstone('card', OLD.id, old_board);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER board_sync_tombstone AFTER DELETE ON board
+    FOR EACH ROW EXECUTE FUNCTION board_sync_tombstone();
+CREATE TRIGGER board_column_sync_tombstone AFTER DELETE ON board_column
+    FOR EACH ROW EXECUTE FUNCTION board_column_sync_tombstone();
+CREATE TRIGGER board_column_sync_move AFTER UPDATE OF board_id ON board_column
+    FOR EACH ROW WHEN (OLD.board_id IS DISTINCT FROM NEW.board_id)
+    EXECUTE FUNCTION board_column_sync_tombstone();
+CREATE TRIGGER card_sync_tombstone AFTER DELETE ON card
+    FOR EACH ROW EXECUTE FUNCTION card_sync_tombstone();
+CREATE TRIGGER card_sync_move AFTER UPDATE OF list_id ON card
+    FOR EACH ROW WHEN (OLD.list_id IS DISTINCT FROM NEW.list_id)
+    EXECUTE FUNCTION card_sync_tombstone();
+
+CREATE FUNCTION board_sync_access() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.access_xid := pg_current_xact_id();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER board_sync_access BEFORE UPDATE OF owner_id, members ON board
+    FOR EACH ROW WHEN (OLD.owner_id IS DISTINCT FROM NEW.owner_id OR OLD.members IS DISTINCT FROM NEW.members)
+    EXECUTE FUNCTION board_sync_access();
+
+-- Tombstoned with the old owner and members; those who can still see the
+-- board get its updated row instead
+CREATE TRIGGER board_sync_access_tombstone AFTER UPDATE OF owner_id, members ON board
+    FOR EACH ROW WHEN (OLD.owner_id IS DISTINCT FROM NEW.owner_id OR OLD.members IS DISTINCT FROM NEW.members)
+    EXECUTE FUNCTION board_sync_tombstone();
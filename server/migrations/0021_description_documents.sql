@@ -0,0 +1,20 @@
+-- Collaborative editing of card descriptions. A card edited over the
+-- collaboration channel has an Automerge document whose text is written
+-- back to card.description by snapshots; see handlers/collab.rs.
+CREATE TABLE card_description_doc (
+    card_id INTEGER PRIMARY KEY REFERENCES card(id) ON DELETE CASCADE,
+    document BYTEA NOT NULL,
+    -- Bumped whenever the document is saved
+    revision BIGINT NOT NULL DEFAULT 0,
+    -- Revision last written back to card.description, and the document
+    -- heads whose text that is
+    snapshot_revision BIGINT NOT NULL DEFAULT 0,
+    snapshot_heads TEXT[] NOT NULL,
+    -- Last user to change the document, credited with the next snapshot
+    edited_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX card_description_doc_pending_idx ON card_description_doc (card_id)
+    WHERE snapshot_revision < revision;
@@ -0,0 +1,17 @@
+-- Users viewing a board, one row per real-time connection. Rows not heard
+-- from within the presence TTL are stale and removed by the
+-- `presence_expiry` job; see handlers/presence.rs.
+CREATE TABLE board_presence (
+    id BIGSERIAL PRIMARY KEY,
+    board_id INTEGER NOT NULL REFERENCES board(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    -- Card the user has open
+    card_id INTEGER REFERENCES card(id) ON DELETE SET NULL,
+    connected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Last change of the open card
+    focused_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX board_presence_board_id_idx ON board_presence (board_id);
+CREATE INDEX board_presence_last_seen_at_idx ON board_presence (last_seen_at);
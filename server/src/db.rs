@@ -9,7 +9,7 @@ pub async fn create_pool() -> Result<DbPool, sqlx::Error> {
         .expect("DATABASE_URL must be set in environment");
 
     tracing::info!("Connecting to database: {}", 
-        database_url.split('@').last().unwrap_or("unknown"));
+        database_url.split('@').next_back().unwrap_or("unknown"));
 
     let pool = sqlx::postgres::PgPoolOptions::new()
         .max_connections(5)
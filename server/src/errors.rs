@@ -11,7 +11,11 @@ pub enum AppError {
     DatabaseError(sqlx::Error),
     NotFound(String),
     ValidationError(String),
-    #[allow(dead_code)]
+    /// The request is missing valid credentials
+    Unauthorized(String),
+    Conflict(String),
+    /// A card would exceed the strict WIP limit of a column
+    WipLimitExceeded(String),
     InternalError(String),
 }
 
@@ -24,6 +28,13 @@ impl From<sqlx::Error> for AppError {
     }
 }
 
+/// Whether a database error is a unique constraint violation
+pub fn is_unique_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .and_then(|e| e.code())
+        .is_some_and(|code| code == "23505")
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, msg) = match self {
@@ -33,6 +44,9 @@ impl IntoResponse for AppError {
             }
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             AppError::ValidationError(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            AppError::WipLimitExceeded(msg) => (StatusCode::CONFLICT, msg),
             AppError::InternalError(msg) => {
                 tracing::error!("Internal error: {}", msg);
                 (StatusCode::INTERNAL_SERVER_ERROR, msg)
@@ -0,0 +1,605 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use chrono::{TimeDelta, Utc};
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::boards::get_board_by_id;
+use crate::handlers::card_lifecycle::{get_column_category, stamps_on_enter};
+use crate::handlers::cards::CARD_COLUMNS;
+use crate::handlers::{card_keys, notifications, ranks, webhooks, wip};
+use crate::models::{
+    AutomationDryRunRequest, AutomationDryRunResult, AutomationRule, AutomationRun, Card,
+    CreateAutomationRuleRequest, RuleAction, RuleCondition, RuleTrigger, UpdateAutomationRuleRequest,
+    WebhookEvent,
+};
+
+pub(crate) const RULE_COLUMNS: &str = "id, board_id, name, enabled, trigger, conditions, actions, created_at, updated_at";
+
+const RUN_COLUMNS: &str = "id, rule_id, card_id, event, actions, depth, created_at";
+
+/// Events caused by rule actions are followed this many levels deep
+const MAX_CHAIN_DEPTH: i32 = 5;
+
+/// Most recent runs returned for a rule
+const RUN_LOG_LIMIT: i64 = 100;
+
+/// Furthest a due date can be set from today, in either direction
+const MAX_DUE_IN_DAYS: i64 = 3650;
+
+/// Something that happened to a card and may fire rules
+#[derive(Debug, Clone)]
+pub struct CardEvent {
+    pub card_id: i32,
+    pub event: RuleTrigger,
+}
+
+impl CardEvent {
+    pub fn new(card_id: i32, event: RuleTrigger) -> Self {
+        Self { card_id, event }
+    }
+}
+
+/// Events for a card that moved from one list to another
+pub async fn move_events(
+    conn: &mut PgConnection,
+    from_list_id: i32,
+    to_list_id: i32,
+) -> Result<Vec<RuleTrigger>, AppError> {
+    let category = get_column_category(conn, to_list_id).await?;
+    Ok(vec![
+        RuleTrigger::CardMoved { from_list_id: Some(from_list_id), to_list_id: Some(to_list_id) },
+        RuleTrigger::CardEnteredCategory { category },
+    ])
+}
+
+fn list_filter_matches(filter: Option<i32>, list_id: Option<i32>) -> bool {
+    filter.is_none_or(|filter| Some(filter) == list_id)
+}
+
+fn trigger_matches(trigger: &RuleTrigger, event: &RuleTrigger) -> bool {
+    match (trigger, event) {
+        (RuleTrigger::CardCreated { list_id: filter }, RuleTrigger::CardCreated { list_id }) => {
+            list_filter_matches(*filter, *list_id)
+        }
+        (
+            RuleTrigger::CardMoved { from_list_id: from_filter, to_list_id: to_filter },
+            RuleTrigger::CardMoved { from_list_id, to_list_id },
+        ) => list_filter_matches(*from_filter, *from_list_id) && list_filter_matches(*to_filter, *to_list_id),
+        (
+            RuleTrigger::CardEnteredCategory { category: filter },
+            RuleTrigger::CardEnteredCategory { category },
+        ) => filter == category,
+        (RuleTrigger::CardUpdated, RuleTrigger::CardUpdated) => true,
+        _ => false,
+    }
+}
+
+fn condition_holds(condition: &RuleCondition, card: &Card) -> bool {
+    match condition {
+        RuleCondition::HasLabel { label } => card.labels.contains(label),
+        RuleCondition::LacksLabel { label } => !card.labels.contains(label),
+        RuleCondition::PriorityIs { priority } => card.priority == *priority,
+        RuleCondition::AssigneeIs { assignee_id } => card.assignee_id == *assignee_id,
+        RuleCondition::TitleContains { text } => {
+            card.title.to_lowercase().contains(&text.to_lowercase())
+        }
+    }
+}
+
+async fn fetch_card(conn: &mut PgConnection, id: i32) -> Result<Option<Card>, AppError> {
+    let card = sqlx::query_as::<_, Card>(&format!("SELECT {CARD_COLUMNS} FROM card WHERE id = $1"))
+        .bind(id)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(card)
+}
+
+async fn enabled_rules(conn: &mut PgConnection, board_id: i32) -> Result<Vec<AutomationRule>, AppError> {
+    let rules = sqlx::query_as::<_, AutomationRule>(&format!(
+        "SELECT {RULE_COLUMNS} FROM automation_rule WHERE board_id = $1 AND enabled ORDER BY id ASC"
+    ))
+    .bind(board_id)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rules)
+}
+
+/// Apply a rule's actions to a card, returning the events they cause
+async fn apply_actions(
+    conn: &mut PgConnection,
+    rule: &AutomationRule,
+    card: &Card,
+) -> Result<Vec<CardEvent>, AppError> {
+    let mut events = Vec::new();
+    let mut list_id = card.list_id;
+    let mut stamps = (card.started_at, card.completed_at);
+
+    for action in rule.actions.iter() {
+        match action {
+            RuleAction::AddLabel { label } => {
+                sqlx::query(
+                    "UPDATE card SET labels = array_append(labels, $2), updated_at = NOW()
+                     WHERE id = $1 AND NOT ($2 = ANY(labels))"
+                )
+                .bind(card.id)
+                .bind(label)
+                .execute(&mut *conn)
+                .await?;
+            }
+            RuleAction::RemoveLabel { label } => {
+                sqlx::query("UPDATE card SET labels = array_remove(labels, $2), updated_at = NOW() WHERE id = $1")
+                    .bind(card.id)
+                    .bind(label)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            RuleAction::SetPriority { priority } => {
+                sqlx::query("UPDATE card SET priority = $2, updated_at = NOW() WHERE id = $1")
+                    .bind(card.id)
+                    .bind(priority)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            RuleAction::SetAssignee { assignee_id } => {
+                sqlx::query("UPDATE card SET assignee_id = $2, updated_at = NOW() WHERE id = $1")
+                    .bind(card.id)
+                    .bind(assignee_id)
+                    .execute(&mut *conn)
+                    .await?;
+
+                if assignee_id.is_some() && *assignee_id != card.assignee_id {
+                    if let Some(assigned) = fetch_card(&mut *conn, card.id).await? {
+                        notifications::notify_assignment(&mut *conn, &assigned).await?;
+                    }
+                }
+            }
+            RuleAction::SetDueInDays { days } => {
+                let due_date = match days {
+                    Some(days) => Some(
+                        TimeDelta::try_days(*days)
+                            .and_then(|delta| Utc::now().checked_add_signed(delta))
+                            .ok_or_else(|| {
+                                AppError::ValidationError(format!(
+                                    "Rule \"{}\" sets a due date out of range",
+                                    rule.name
+                                ))
+                            })?,
+                    ),
+                    None => None,
+                };
+                sqlx::query("UPDATE card SET due_date = $2, updated_at = NOW() WHERE id = $1")
+                    .bind(card.id)
+                    .bind(due_date)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            RuleAction::MoveToList { list_id: target } => {
+                if *target == list_id {
+                    continue;
+                }
+                if card_keys::get_board_id_for_list(&mut *conn, *target).await? != rule.board_id {
+                    return Err(AppError::ValidationError(format!(
+                        "Rule \"{}\" moves cards to list {}, which is not on its board",
+                        rule.name, target
+                    )));
+                }
+
+                wip::check_wip_limit(&mut *conn, *target, 1).await?;
+                let category = get_column_category(&mut *conn, *target).await?;
+                stamps = stamps_on_enter(category, stamps, Utc::now());
+                let rank = ranks::card_rank_at(&mut *conn, *target, card.lane_id, None, Some(card.id)).await?;
+
+                let moved_card = sqlx::query_as::<_, Card>(&format!(
+                    "UPDATE card
+                     SET list_id = $2, rank = $3, started_at = $4, completed_at = $5, updated_at = NOW()
+                     WHERE id = $1
+                     RETURNING {CARD_COLUMNS}"
+                ))
+                .bind(card.id)
+                .bind(target)
+                .bind(&rank)
+                .bind(stamps.0)
+                .bind(stamps.1)
+                .fetch_one(&mut *conn)
+                .await?;
+                webhooks::emit_card(&mut *conn, WebhookEvent::CardMoved, &moved_card).await?;
+
+                let moved = move_events(&mut *conn, list_id, *target).await?;
+                events.extend(moved.into_iter().map(|event| CardEvent::new(card.id, event)));
+                list_id = *target;
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// Run the rules of the affected boards for a batch of card events, in the
+/// caller's transaction. Events caused by rule actions are processed in turn.
+/// Loop protection: a rule fires at most once per card per batch, and chains
+/// stop after `MAX_CHAIN_DEPTH` levels. Every execution is logged.
+pub async fn run_automations(
+    conn: &mut PgConnection,
+    events: Vec<CardEvent>,
+) -> Result<Vec<AutomationRun>, AppError> {
+    let mut queue: VecDeque<(CardEvent, i32)> = events.into_iter().map(|event| (event, 0)).collect();
+    let mut fired: HashSet<(i32, i32)> = HashSet::new();
+    let mut runs = Vec::new();
+
+    while let Some((event, depth)) = queue.pop_front() {
+        if depth > MAX_CHAIN_DEPTH {
+            tracing::warn!(
+                "Automation chain for card {} stopped after {} levels",
+                event.card_id,
+                MAX_CHAIN_DEPTH
+            );
+            continue;
+        }
+
+        let Some(card) = fetch_card(&mut *conn, event.card_id).await? else {
+            continue;
+        };
+        let board_id = card_keys::get_board_id_for_list(&mut *conn, card.list_id).await?;
+
+        for rule in enabled_rules(&mut *conn, board_id).await? {
+            if !trigger_matches(&rule.trigger, &event.event) || fired.contains(&(rule.id, card.id)) {
+                continue;
+            }
+
+            // Earlier rules may have changed the card
+            let Some(card) = fetch_card(&mut *conn, event.card_id).await? else {
+                break;
+            };
+            if !rule.conditions.iter().all(|condition| condition_holds(condition, &card)) {
+                continue;
+            }
+
+            fired.insert((rule.id, card.id));
+            let follow_ups = apply_actions(&mut *conn, &rule, &card).await?;
+
+            let run = sqlx::query_as::<_, AutomationRun>(&format!(
+                "INSERT INTO automation_run (rule_id, card_id, event, actions, depth)
+                 VALUES ($1, $2, $3, $4, $5)
+                 RETURNING {RUN_COLUMNS}"
+            ))
+            .bind(rule.id)
+            .bind(card.id)
+            .bind(Json(&event.event))
+            .bind(&rule.actions)
+            .bind(depth)
+            .fetch_one(&mut *conn)
+            .await?;
+            runs.push(run);
+
+            queue.extend(follow_ups.into_iter().map(|follow_up| (follow_up, depth + 1)));
+        }
+    }
+
+    Ok(runs)
+}
+
+/// Run rules for events on one card and return the card as the rules left it
+pub async fn apply_rules_to_card(
+    conn: &mut PgConnection,
+    card: Card,
+    events: Vec<RuleTrigger>,
+) -> Result<Card, AppError> {
+    let card_id = card.id;
+    let runs = run_automations(
+        &mut *conn,
+        events.into_iter().map(|event| CardEvent::new(card_id, event)).collect(),
+    )
+    .await?;
+
+    if runs.is_empty() {
+        return Ok(card);
+    }
+
+    fetch_card(conn, card_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Card not found".to_string()))
+}
+
+/// Check a rule's actions on their own: there is at least one, and due
+/// dates stay within `MAX_DUE_IN_DAYS`
+fn validate_actions(actions: &[RuleAction]) -> Result<(), AppError> {
+    if actions.is_empty() {
+        return Err(AppError::ValidationError("At least one action is required".to_string()));
+    }
+
+    for action in actions {
+        if let RuleAction::SetDueInDays { days: Some(days) } = action {
+            if !(-MAX_DUE_IN_DAYS..=MAX_DUE_IN_DAYS).contains(days) {
+                return Err(AppError::ValidationError(format!(
+                    "days must be between -{} and {}",
+                    MAX_DUE_IN_DAYS, MAX_DUE_IN_DAYS
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check a rule's actions, and that the lists it refers to are on its board
+async fn validate_rule(
+    conn: &mut PgConnection,
+    board_id: i32,
+    trigger: &RuleTrigger,
+    actions: &[RuleAction],
+) -> Result<(), AppError> {
+    validate_actions(actions)?;
+
+    let mut list_ids: Vec<i32> = actions
+        .iter()
+        .filter_map(|action| match action {
+            RuleAction::MoveToList { list_id } => Some(*list_id),
+            _ => None,
+        })
+        .collect();
+    match trigger {
+        RuleTrigger::CardCreated { list_id } => list_ids.extend(list_id),
+        RuleTrigger::CardMoved { from_list_id, to_list_id } => {
+            list_ids.extend(from_list_id);
+            list_ids.extend(to_list_id);
+        }
+        RuleTrigger::CardEnteredCategory { .. } | RuleTrigger::CardUpdated => {}
+    }
+
+    for list_id in list_ids {
+        if card_keys::get_board_id_for_list(&mut *conn, list_id).await? != board_id {
+            return Err(AppError::ValidationError(format!(
+                "List {} is not on board {}",
+                list_id, board_id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// A rule's trigger and actions with their lists replaced through `lists`,
+/// for a copy of the rule on another board. None when the rule refers to a
+/// list that `lists` does not map, such as one deleted since.
+pub(crate) fn remap_lists(
+    trigger: &RuleTrigger,
+    actions: &[RuleAction],
+    lists: &HashMap<i32, i32>,
+) -> Option<(RuleTrigger, Vec<RuleAction>)> {
+    let remap = |list_id: Option<i32>| match list_id {
+        Some(list_id) => lists.get(&list_id).map(|target| Some(*target)),
+        None => Some(None),
+    };
+
+    let trigger = match trigger {
+        RuleTrigger::CardCreated { list_id } => RuleTrigger::CardCreated { list_id: remap(*list_id)? },
+        RuleTrigger::CardMoved { from_list_id, to_list_id } => RuleTrigger::CardMoved {
+            from_list_id: remap(*from_list_id)?,
+            to_list_id: remap(*to_list_id)?,
+        },
+        trigger => trigger.clone(),
+    };
+    let actions = actions
+        .iter()
+        .map(|action| match action {
+            RuleAction::MoveToList { list_id } => {
+                lists.get(list_id).map(|target| RuleAction::MoveToList { list_id: *target })
+            }
+            action => Some(action.clone()),
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((trigger, actions))
+}
+
+/// Get all rules of a board
+pub async fn get_rules_for_board(pool: &DbPool, board_id: i32) -> Result<Vec<AutomationRule>, AppError> {
+    get_board_by_id(pool, board_id).await?;
+
+    let rules = sqlx::query_as::<_, AutomationRule>(&format!(
+        "SELECT {RULE_COLUMNS} FROM automation_rule WHERE board_id = $1 ORDER BY id ASC"
+    ))
+    .bind(board_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rules)
+}
+
+/// Get rule by ID
+pub async fn get_rule_by_id(pool: &DbPool, id: i32) -> Result<AutomationRule, AppError> {
+    let rule = sqlx::query_as::<_, AutomationRule>(&format!(
+        "SELECT {RULE_COLUMNS} FROM automation_rule WHERE id = $1"
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    rule.ok_or_else(|| AppError::NotFound(format!("Automation rule with id {} not found", id)))
+}
+
+/// Create a rule on a board
+pub async fn create_rule(
+    pool: &DbPool,
+    board_id: i32,
+    req: CreateAutomationRuleRequest,
+) -> Result<AutomationRule, AppError> {
+    if req.name.trim().is_empty() {
+        return Err(AppError::ValidationError("Name is required".to_string()));
+    }
+
+    get_board_by_id(pool, board_id).await?;
+
+    let mut conn = pool.acquire().await?;
+    validate_rule(&mut conn, board_id, &req.trigger, &req.actions).await?;
+
+    let rule = sqlx::query_as::<_, AutomationRule>(&format!(
+        "INSERT INTO automation_rule (board_id, name, enabled, trigger, conditions, actions)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING {RULE_COLUMNS}"
+    ))
+    .bind(board_id)
+    .bind(&req.name)
+    .bind(req.enabled.unwrap_or(true))
+    .bind(Json(&req.trigger))
+    .bind(Json(&req.conditions))
+    .bind(Json(&req.actions))
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(rule)
+}
+
+/// Update a rule
+pub async fn update_rule(
+    pool: &DbPool,
+    id: i32,
+    req: UpdateAutomationRuleRequest,
+) -> Result<AutomationRule, AppError> {
+    if req.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(AppError::ValidationError("Name cannot be empty".to_string()));
+    }
+
+    let current = get_rule_by_id(pool, id).await?;
+
+    let name = req.name.unwrap_or(current.name);
+    let enabled = req.enabled.unwrap_or(current.enabled);
+    let trigger = req.trigger.unwrap_or(current.trigger.0);
+    let conditions = req.conditions.unwrap_or(current.conditions.0);
+    let actions = req.actions.unwrap_or(current.actions.0);
+
+    let mut conn = pool.acquire().await?;
+    validate_rule(&mut conn, current.board_id, &trigger, &actions).await?;
+
+    let rule = sqlx::query_as::<_, AutomationRule>(&format!(
+        "UPDATE automation_rule
+         SET name = $1, enabled = $2, trigger = $3, conditions = $4, actions = $5, updated_at = NOW()
+         WHERE id = $6
+         RETURNING {RULE_COLUMNS}"
+    ))
+    .bind(&name)
+    .bind(enabled)
+    .bind(Json(&trigger))
+    .bind(Json(&conditions))
+    .bind(Json(&actions))
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(rule)
+}
+
+/// Delete a rule and its execution log
+pub async fn delete_rule(pool: &DbPool, id: i32) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM automation_rule WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Automation rule with id {} not found", id)));
+    }
+
+    Ok(())
+}
+
+/// Get the most recent executions of a rule, newest first
+pub async fn get_rule_runs(pool: &DbPool, id: i32) -> Result<Vec<AutomationRun>, AppError> {
+    get_rule_by_id(pool, id).await?;
+
+    let runs = sqlx::query_as::<_, AutomationRun>(&format!(
+        "SELECT {RUN_COLUMNS} FROM automation_run
+         WHERE rule_id = $1
+         ORDER BY created_at DESC, id DESC
+         LIMIT $2"
+    ))
+    .bind(id)
+    .bind(RUN_LOG_LIMIT)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(runs)
+}
+
+/// Simulate an event on a card against a board's enabled rules. Everything
+/// happens in a transaction that is rolled back, so nothing is changed or logged.
+pub async fn dry_run(
+    pool: &DbPool,
+    board_id: i32,
+    req: AutomationDryRunRequest,
+) -> Result<AutomationDryRunResult, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let Some(card) = fetch_card(&mut tx, req.card_id).await? else {
+        return Err(AppError::NotFound(format!("Card with id {} not found", req.card_id)));
+    };
+    if card_keys::get_board_id_for_list(&mut tx, card.list_id).await? != board_id {
+        return Err(AppError::ValidationError(format!(
+            "Card {} is not on board {}",
+            card.card_key, board_id
+        )));
+    }
+
+    let event = match req.event {
+        RuleTrigger::CardCreated { list_id } => RuleTrigger::CardCreated {
+            list_id: list_id.or(Some(card.list_id)),
+        },
+        RuleTrigger::CardMoved { from_list_id, to_list_id } => RuleTrigger::CardMoved {
+            from_list_id: from_list_id.or(Some(card.list_id)),
+            to_list_id: to_list_id.or(Some(card.list_id)),
+        },
+        event => event,
+    };
+
+    let runs = run_automations(&mut tx, vec![CardEvent::new(card.id, event)]).await?;
+    let card = fetch_card(&mut tx, card.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Card not found".to_string()))?;
+
+    tx.rollback().await?;
+
+    Ok(AutomationDryRunResult { runs, card })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_are_required() {
+        assert!(validate_actions(&[]).is_err());
+        assert!(validate_actions(&[RuleAction::SetDueInDays { days: None }]).is_ok());
+    }
+
+    #[test]
+    fn remaps_lists_of_copied_rules() {
+        let lists = HashMap::from([(1, 11), (2, 12)]);
+        let trigger = RuleTrigger::CardMoved { from_list_id: None, to_list_id: Some(1) };
+        let actions = [RuleAction::AddLabel { label: "wip".to_string() }, RuleAction::MoveToList { list_id: 2 }];
+
+        let (trigger, actions) = remap_lists(&trigger, &actions, &lists).unwrap();
+        assert_eq!(trigger, RuleTrigger::CardMoved { from_list_id: None, to_list_id: Some(11) });
+        assert!(matches!(actions[..], [RuleAction::AddLabel { .. }, RuleAction::MoveToList { list_id: 12 }]));
+
+        let unknown = RuleTrigger::CardCreated { list_id: Some(3) };
+        assert!(remap_lists(&unknown, &[], &lists).is_none());
+        assert!(remap_lists(&RuleTrigger::CardUpdated, &[RuleAction::MoveToList { list_id: 3 }], &lists).is_none());
+    }
+
+    #[test]
+    fn due_in_days_is_bounded() {
+        let due_in = |days| [RuleAction::SetDueInDays { days: Some(days) }];
+        assert!(validate_actions(&due_in(0)).is_ok());
+        assert!(validate_actions(&due_in(MAX_DUE_IN_DAYS)).is_ok());
+        assert!(validate_actions(&due_in(-MAX_DUE_IN_DAYS)).is_ok());
+        assert!(validate_actions(&due_in(MAX_DUE_IN_DAYS + 1)).is_err());
+        assert!(validate_actions(&due_in(-MAX_DUE_IN_DAYS - 1)).is_err());
+        assert!(validate_actions(&due_in(i64::MAX)).is_err());
+        assert!(validate_actions(&due_in(i64::MIN)).is_err());
+    }
+}
@@ -1,14 +1,21 @@
+use sqlx::PgConnection;
+
 use crate::db::DbPool;
 use crate::errors::AppError;
-use crate::models::{Board, CreateBoardRequest, UpdateBoardRequest};
+use crate::handlers::{card_keys, lanes, templates, webhooks};
+use crate::models::{Board, CreateBoardRequest, UpdateBoardRequest, WebhookEvent};
+
+/// Columns selected for every `Board` query
+pub(crate) const BOARD_COLUMNS: &str =
+    "id, title, description, key_prefix, owner_id, members, created_at, updated_at";
 
 /// Get all boards
 pub async fn get_all_boards(pool: &DbPool) -> Result<Vec<Board>, AppError> {
-    let boards = sqlx::query_as::<_, Board>(
-        "SELECT id, title, description, owner_id, members, created_at, updated_at 
-         FROM board 
+    let boards = sqlx::query_as::<_, Board>(&format!(
+        "SELECT {BOARD_COLUMNS}
+         FROM board
          ORDER BY created_at DESC"
-    )
+    ))
     .fetch_all(pool)
     .await?;
 
@@ -17,11 +24,11 @@ pub async fn get_all_boards(pool: &DbPool) -> Result<Vec<Board>, AppError> {
 
 /// Get board by ID
 pub async fn get_board_by_id(pool: &DbPool, id: i32) -> Result<Board, AppError> {
-    let board = sqlx::query_as::<_, Board>(
-        "SELECT id, title, description, owner_id, members, created_at, updated_at 
-         FROM board 
+    let board = sqlx::query_as::<_, Board>(&format!(
+        "SELECT {BOARD_COLUMNS}
+         FROM board
          WHERE id = $1"
-    )
+    ))
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -39,15 +46,36 @@ pub async fn create_board(
         return Err(AppError::ValidationError("Title is required".to_string()));
     }
 
-    let board = sqlx::query_as::<_, Board>(
-        "INSERT INTO board (title, description) 
-         VALUES ($1, $2) 
-         RETURNING id, title, description, owner_id, members, created_at, updated_at"
-    )
+    let template = match req.template_id {
+        Some(template_id) => Some(templates::get_template_by_id(pool, template_id).await?),
+        None => None,
+    };
+
+    let key_prefix =
+        card_keys::choose_key_prefix(pool, req.key_prefix.as_deref(), &req.title).await?;
+
+    // Create the board and instantiate its template atomically
+    let mut tx = pool.begin().await?;
+
+    let board = sqlx::query_as::<_, Board>(&format!(
+        "INSERT INTO board (title, description, key_prefix)
+         VALUES ($1, $2, $3)
+         RETURNING {BOARD_COLUMNS}"
+    ))
     .bind(&req.title)
     .bind(&req.description)
-    .fetch_one(pool)
-    .await?;
+    .bind(&key_prefix)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| card_keys::key_prefix_conflict(err, &key_prefix))?;
+
+    lanes::create_default_lane(&mut tx, board.id).await?;
+
+    if let Some(template) = template {
+        templates::instantiate_template(&mut tx, board.id, &template.definition).await?;
+    }
+
+    tx.commit().await?;
 
     Ok(board)
 }
@@ -57,6 +85,21 @@ pub async fn update_board(
     pool: &DbPool,
     id: i32,
     req: UpdateBoardRequest,
+) -> Result<Board, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let board = update_board_in(&mut tx, id, req).await?;
+
+    tx.commit().await?;
+
+    Ok(board)
+}
+
+/// Update a board within a transaction
+pub(crate) async fn update_board_in(
+    conn: &mut PgConnection,
+    id: i32,
+    req: UpdateBoardRequest,
 ) -> Result<Board, AppError> {
     // Build dynamic update query
     let mut query = String::from("UPDATE board SET ");
@@ -73,6 +116,11 @@ pub async fn update_board(
 
     if req.description.is_some() {
         updates.push(format!("description = ${}", param_count));
+        // Drop the rendered HTML when the text changes
+        updates.push(format!(
+            "description_html = CASE WHEN description IS DISTINCT FROM ${} THEN NULL ELSE description_html END",
+            param_count
+        ));
         param_count += 1;
     }
 
@@ -82,7 +130,7 @@ pub async fn update_board(
 
     updates.push("updated_at = NOW()".to_string());
     query.push_str(&updates.join(", "));
-    query.push_str(&format!(" WHERE id = ${} RETURNING id, title, description, owner_id, members, created_at, updated_at", param_count));
+    query.push_str(&format!(" WHERE id = ${} RETURNING {}", param_count, BOARD_COLUMNS));
 
     // Build and execute query
     let mut query_builder = sqlx::query_as::<_, Board>(&query);
@@ -96,9 +144,11 @@ pub async fn update_board(
     query_builder = query_builder.bind(id);
 
     let board = query_builder
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await?;
 
+    webhooks::emit(&mut *conn, board.id, WebhookEvent::BoardUpdated, &board).await?;
+
     Ok(board)
 }
 
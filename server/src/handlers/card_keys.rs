@@ -0,0 +1,182 @@
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::{is_unique_violation, AppError};
+
+const MAX_KEY_PREFIX_LEN: usize = 10;
+
+/// A card reference from a URL, resolved to the card's numeric ID
+#[derive(Debug)]
+pub struct ResolvedCardRef {
+    pub id: i32,
+    /// Current key of the card when the reference was a key it no longer uses
+    pub redirect_to: Option<String>,
+}
+
+/// Validate a board key prefix: an uppercase letter followed by uppercase
+/// letters or digits
+pub fn validate_key_prefix(prefix: &str) -> Result<(), AppError> {
+    let mut chars = prefix.chars();
+    let starts_with_letter = chars.next().is_some_and(|c| c.is_ascii_uppercase());
+    let rest_is_valid = chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+
+    if !starts_with_letter || !rest_is_valid || prefix.len() > MAX_KEY_PREFIX_LEN {
+        return Err(AppError::ValidationError(format!(
+            "Key prefix must be 1-{} uppercase letters or digits, starting with a letter",
+            MAX_KEY_PREFIX_LEN
+        )));
+    }
+
+    Ok(())
+}
+
+/// Derive a key prefix from a board title: the initials of a multi-word
+/// title ("Platform Ops" -> "PO"), otherwise its first three letters
+fn derive_key_prefix(title: &str) -> String {
+    let words: Vec<String> = title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| w.starts_with(|c: char| c.is_ascii_alphabetic()))
+        .map(|w| w.to_ascii_uppercase())
+        .collect();
+
+    let prefix: String = match words.as_slice() {
+        [] => "CARD".to_string(),
+        [word] => word.chars().take(3).collect(),
+        _ => words
+            .iter()
+            .filter_map(|w| w.chars().next())
+            .take(4)
+            .collect(),
+    };
+
+    prefix
+}
+
+/// Pick a key prefix for a new board, appending a counter when the derived
+/// or requested prefix is already taken
+pub async fn choose_key_prefix(
+    pool: &DbPool,
+    requested: Option<&str>,
+    title: &str,
+) -> Result<String, AppError> {
+    if let Some(prefix) = requested {
+        validate_key_prefix(prefix)?;
+        if key_prefix_exists(pool, prefix).await? {
+            return Err(AppError::Conflict(format!("Key prefix {} is already in use", prefix)));
+        }
+        return Ok(prefix.to_string());
+    }
+
+    let base = derive_key_prefix(title);
+    let mut candidate = base.clone();
+    let mut suffix = 2;
+    while key_prefix_exists(pool, &candidate).await? {
+        candidate = format!("{}{}", base, suffix);
+        suffix += 1;
+    }
+
+    Ok(candidate)
+}
+
+/// Map a unique violation raised while inserting a board to a key prefix conflict
+pub fn key_prefix_conflict(err: sqlx::Error, prefix: &str) -> AppError {
+    if is_unique_violation(&err) {
+        AppError::Conflict(format!("Key prefix {} is already in use", prefix))
+    } else {
+        err.into()
+    }
+}
+
+async fn key_prefix_exists(pool: &DbPool, prefix: &str) -> Result<bool, AppError> {
+    let exists = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM board WHERE key_prefix = $1)")
+        .bind(prefix)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(exists)
+}
+
+/// Get the board a column belongs to
+pub async fn get_board_id_for_list(conn: &mut PgConnection, list_id: i32) -> Result<i32, AppError> {
+    let board_id: Option<i32> = sqlx::query_scalar("SELECT board_id FROM board_column WHERE id = $1")
+        .bind(list_id)
+        .fetch_optional(conn)
+        .await?;
+
+    board_id.ok_or_else(|| AppError::NotFound(format!("List with id {} not found", list_id)))
+}
+
+/// Reserve the next card number of a board and build the card key from it.
+/// The row lock taken by the UPDATE serialises concurrent card creation.
+pub async fn allocate_card_key(conn: &mut PgConnection, board_id: i32) -> Result<(i32, String), AppError> {
+    let (number, prefix) = allocate_card_numbers(conn, board_id, 1).await?;
+    Ok((number, format!("{}-{}", prefix, number)))
+}
+
+/// Reserve `count` consecutive card numbers of a board, returning the first
+/// number and the board's key prefix
+pub async fn allocate_card_numbers(
+    conn: &mut PgConnection,
+    board_id: i32,
+    count: i32,
+) -> Result<(i32, String), AppError> {
+    let allocated = sqlx::query_as(
+        "UPDATE board
+         SET next_card_number = next_card_number + $2
+         WHERE id = $1
+         RETURNING next_card_number - $2, key_prefix"
+    )
+    .bind(board_id)
+    .bind(count)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(allocated)
+}
+
+/// Keep an old card key resolving to the card after it moved boards
+pub async fn record_key_alias(conn: &mut PgConnection, old_key: &str, card_id: i32) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO card_key_alias (key, card_id) VALUES ($1, $2) ON CONFLICT (key) DO NOTHING")
+        .bind(old_key)
+        .bind(card_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Resolve a numeric card ID or a card key such as `OPS-42`
+pub async fn resolve_card_ref(pool: &DbPool, card_ref: &str) -> Result<ResolvedCardRef, AppError> {
+    if let Ok(id) = card_ref.parse::<i32>() {
+        return Ok(ResolvedCardRef { id, redirect_to: None });
+    }
+
+    let key = card_ref.to_ascii_uppercase();
+
+    let current: Option<i32> = sqlx::query_scalar("SELECT id FROM card WHERE card_key = $1")
+        .bind(&key)
+        .fetch_optional(pool)
+        .await?;
+
+    if let Some(id) = current {
+        return Ok(ResolvedCardRef { id, redirect_to: None });
+    }
+
+    let alias: Option<(i32, String)> = sqlx::query_as(
+        "SELECT c.id, c.card_key
+         FROM card_key_alias a
+         JOIN card c ON c.id = a.card_id
+         WHERE a.key = $1"
+    )
+    .bind(&key)
+    .fetch_optional(pool)
+    .await?;
+
+    match alias {
+        Some((id, current_key)) => Ok(ResolvedCardRef {
+            id,
+            redirect_to: Some(current_key),
+        }),
+        None => Err(AppError::NotFound(format!("Card {} not found", card_ref))),
+    }
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+
+use crate::errors::AppError;
+use crate::models::ColumnCategory;
+
+/// Lifecycle timestamps of a card
+pub type LifecycleStamps = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// Get the category of a column
+pub async fn get_column_category(conn: &mut PgConnection, list_id: i32) -> Result<ColumnCategory, AppError> {
+    let category: Option<ColumnCategory> =
+        sqlx::query_scalar("SELECT category FROM board_column WHERE id = $1")
+            .bind(list_id)
+            .fetch_optional(conn)
+            .await?;
+
+    category.ok_or_else(|| AppError::NotFound(format!("List with id {} not found", list_id)))
+}
+
+/// Compute `(started_at, completed_at)` for a card entering a column.
+///
+/// Entering in-progress starts the card (keeping an earlier start) and clears
+/// completion; entering done also completes it; moving back to backlog or
+/// todo clears both.
+pub fn stamps_on_enter(
+    category: ColumnCategory,
+    (started_at, completed_at): LifecycleStamps,
+    now: DateTime<Utc>,
+) -> LifecycleStamps {
+    match category {
+        ColumnCategory::Backlog | ColumnCategory::Todo => (None, None),
+        ColumnCategory::InProgress => (Some(started_at.unwrap_or(now)), None),
+        ColumnCategory::Done => (Some(started_at.unwrap_or(now)), Some(completed_at.unwrap_or(now))),
+    }
+}
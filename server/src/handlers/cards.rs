@@ -1,25 +1,55 @@
+use chrono::Utc;
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
 use crate::db::DbPool;
 use crate::errors::AppError;
-use crate::models::{Card, CreateCardRequest, UpdateCardRequest};
+use crate::handlers::card_lifecycle::{get_column_category, stamps_on_enter, LifecycleStamps};
+use crate::handlers::{
+    automation, card_keys, collab, history, lanes, mentions, notifications, ranks, revisions, webhooks, wip,
+};
+use crate::models::{
+    Card, CardPlacement, CreateCardRequest, EstimateUnit, MoveCardRequest, RuleTrigger, UndoableOperation,
+    UpdateCardRequest, WebhookEvent,
+};
+
+/// Columns selected for every `Card` query. The position (the card's rank
+/// within its list and lane), the description's mentions and the tracked
+/// time total refer to the unaliased `card` table, so queries using it must
+/// not alias `card`.
+pub(crate) const CARD_COLUMNS: &str = "id, card_number, card_key, title, description, \
+     (SELECT COALESCE(jsonb_agg(jsonb_build_object( \
+                 'userId', m.user_id, 'displayName', COALESCE(p.display_name, m.user_id), \
+                 'start', m.span_start, 'end', m.span_end) ORDER BY m.span_start), '[]') \
+      FROM mention m LEFT JOIN user_profile p ON p.user_id = m.user_id \
+      WHERE m.card_id = card.id AND m.comment_id IS NULL) AS mentions, \
+     list_id, lane_id, \
+     (SELECT COUNT(*) FROM card s \
+      WHERE s.list_id = card.list_id AND s.lane_id = card.lane_id AND s.rank < card.rank \
+        AND s.id <> card.id)::INT AS position, \
+     assignee_id, due_date, labels, priority, estimate, estimate_unit, \
+     (SELECT COALESCE(SUM(te.duration_seconds), 0)::BIGINT FROM time_entry te \
+      WHERE te.card_id = card.id AND te.ended_at IS NOT NULL) AS tracked_seconds, \
+     started_at, completed_at, created_at, updated_at";
 
 /// Get all cards, optionally filtered by list_id
 pub async fn get_all_cards(pool: &DbPool, list_id: Option<i32>) -> Result<Vec<Card>, AppError> {
     let cards = if let Some(list_id) = list_id {
-        sqlx::query_as::<_, Card>(
-            "SELECT id, title, description, list_id, position, assignee_id, due_date, labels, created_at, updated_at 
-             FROM card 
+        sqlx::query_as::<_, Card>(&format!(
+            "SELECT {CARD_COLUMNS}
+             FROM card
              WHERE list_id = $1
-             ORDER BY position ASC"
-        )
+             ORDER BY (SELECT position FROM swimlane WHERE swimlane.id = card.lane_id), rank ASC"
+        ))
         .bind(list_id)
         .fetch_all(pool)
         .await?
     } else {
-        sqlx::query_as::<_, Card>(
-            "SELECT id, title, description, list_id, position, assignee_id, due_date, labels, created_at, updated_at 
-             FROM card 
+        sqlx::query_as::<_, Card>(&format!(
+            "SELECT {CARD_COLUMNS}
+             FROM card
              ORDER BY created_at DESC"
-        )
+        ))
         .fetch_all(pool)
         .await?
     };
@@ -29,11 +59,11 @@ pub async fn get_all_cards(pool: &DbPool, list_id: Option<i32>) -> Result<Vec<Ca
 
 /// Get card by ID
 pub async fn get_card_by_id(pool: &DbPool, id: i32) -> Result<Card, AppError> {
-    let card = sqlx::query_as::<_, Card>(
-        "SELECT id, title, description, list_id, position, assignee_id, due_date, labels, created_at, updated_at 
-         FROM card 
+    let card = sqlx::query_as::<_, Card>(&format!(
+        "SELECT {CARD_COLUMNS}
+         FROM card
          WHERE id = $1"
-    )
+    ))
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -41,91 +71,403 @@ pub async fn get_card_by_id(pool: &DbPool, id: i32) -> Result<Card, AppError> {
     Ok(card)
 }
 
-/// Create a new card
+/// Validate an estimate value and default its unit to story points
+fn resolve_estimate(
+    estimate: Option<f64>,
+    unit: Option<EstimateUnit>,
+) -> Result<(Option<f64>, Option<EstimateUnit>), AppError> {
+    match estimate {
+        Some(value) if !value.is_finite() || value < 0.0 => Err(AppError::ValidationError(
+            "Estimate must be a non-negative number".to_string(),
+        )),
+        Some(value) => Ok((Some(value), Some(unit.unwrap_or_default()))),
+        None => Ok((None, None)),
+    }
+}
+
+/// Where a card ends up when it is moved, and what moving it changed
+struct Placement {
+    list_id: i32,
+    lane_id: i32,
+    card_number: i32,
+    card_key: String,
+    stamps: LifecycleStamps,
+    wip_warning: Option<String>,
+}
+
+/// Resolve the list and lane a card is moving to. Entering another list
+/// checks its WIP limit and updates the lifecycle stamps; entering another
+/// board gives the card the next key of that board (the old key keeps
+/// resolving to it) and puts it in the requested or default lane there.
+async fn place_card(
+    conn: &mut PgConnection,
+    card: &Card,
+    list_id: i32,
+    lane_id: Option<i32>,
+) -> Result<Placement, AppError> {
+    let mut placement = Placement {
+        list_id,
+        lane_id: card.lane_id,
+        card_number: card.card_number,
+        card_key: card.card_key.clone(),
+        stamps: (card.started_at, card.completed_at),
+        wip_warning: None,
+    };
+
+    let old_board_id = card_keys::get_board_id_for_list(&mut *conn, card.list_id).await?;
+    let new_board_id = card_keys::get_board_id_for_list(&mut *conn, list_id).await?;
+
+    if list_id != card.list_id {
+        placement.wip_warning = wip::check_wip_limit(&mut *conn, list_id, 1).await?;
+        let category = get_column_category(&mut *conn, list_id).await?;
+        placement.stamps = stamps_on_enter(category, placement.stamps, Utc::now());
+    }
+
+    if old_board_id != new_board_id {
+        card_keys::record_key_alias(&mut *conn, &card.card_key, card.id).await?;
+        (placement.card_number, placement.card_key) =
+            card_keys::allocate_card_key(&mut *conn, new_board_id).await?;
+        placement.lane_id = lanes::resolve_lane_id(&mut *conn, new_board_id, lane_id).await?;
+    } else if lane_id.is_some() {
+        placement.lane_id = lanes::resolve_lane_id(&mut *conn, new_board_id, lane_id).await?;
+    }
+
+    Ok(placement)
+}
+
+/// Create a new card. Returns a warning when an advisory WIP limit is exceeded.
 pub async fn create_card(
     pool: &DbPool,
     req: CreateCardRequest,
-) -> Result<Card, AppError> {
+) -> Result<(Card, Option<String>), AppError> {
     // Validate title
     if req.title.trim().is_empty() {
         return Err(AppError::ValidationError("Title is required".to_string()));
     }
 
-    // Get the next position for this list
-    let next_position: i32 = sqlx::query_scalar(
-        "SELECT COALESCE(MAX(position), -1) + 1 FROM card WHERE list_id = $1"
-    )
-    .bind(req.list_id)
-    .fetch_one(pool)
-    .await?;
+    let (estimate, estimate_unit) = resolve_estimate(req.estimate, req.estimate_unit)?;
+
+    let mut tx = pool.begin().await?;
+
+    let board_id = card_keys::get_board_id_for_list(&mut tx, req.list_id).await?;
+    let lane_id = lanes::resolve_lane_id(&mut tx, board_id, req.lane_id).await?;
+    let wip_warning = wip::check_wip_limit(&mut tx, req.list_id, 1).await?;
+    let (card_number, card_key) = card_keys::allocate_card_key(&mut tx, board_id).await?;
+    let category = get_column_category(&mut tx, req.list_id).await?;
+    let (started_at, completed_at) = stamps_on_enter(category, (None, None), Utc::now());
+    let rank = ranks::card_rank_at(&mut tx, req.list_id, lane_id, None, None).await?;
 
-    let card = sqlx::query_as::<_, Card>(
-        "INSERT INTO card (title, description, list_id, position, assignee_id, due_date, labels) 
-         VALUES ($1, $2, $3, $4, $5, $6, $7) 
-         RETURNING id, title, description, list_id, position, assignee_id, due_date, labels, created_at, updated_at"
-    )
+    let mut card = sqlx::query_as::<_, Card>(&format!(
+        "INSERT INTO card (card_number, card_key, title, description, list_id, lane_id, rank,
+                           assignee_id, due_date, labels, priority, estimate, estimate_unit,
+                           started_at, completed_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+         RETURNING {CARD_COLUMNS}"
+    ))
+    .bind(card_number)
+    .bind(&card_key)
     .bind(&req.title)
     .bind(&req.description)
     .bind(req.list_id)
-    .bind(next_position)
+    .bind(lane_id)
+    .bind(&rank)
     .bind(&req.assignee_id)
-    .bind(&req.due_date)
+    .bind(req.due_date)
     .bind(&req.labels)
-    .fetch_one(pool)
+    .bind(req.priority)
+    .bind(estimate)
+    .bind(estimate_unit)
+    .bind(started_at)
+    .bind(completed_at)
+    .fetch_one(&mut *tx)
     .await?;
 
-    Ok(card)
+    if let Some(description) = req.description.as_deref() {
+        let mentioned = mentions::sync_mentions(&mut tx, card.id, card.list_id, None, description).await?;
+        mentions::notify_mentioned(&mut tx, &card, None, &mentioned).await?;
+        card.mentions = Json(mentioned);
+    }
+    revisions::record_revision(&mut tx, None, &card, None, None).await?;
+    notifications::notify_assignment(&mut tx, &card).await?;
+    let created = RuleTrigger::CardCreated { list_id: Some(card.list_id) };
+    let card = automation::apply_rules_to_card(&mut tx, card, vec![created]).await?;
+    webhooks::emit_card(&mut tx, WebhookEvent::CardCreated, &card).await?;
+
+    tx.commit().await?;
+
+    Ok((card, wip_warning))
 }
 
-/// Update a card
+/// Update a card. Returns a warning when moving it exceeds an advisory WIP
+/// limit. Moves within the board and renames by a known user (`editedBy`) go
+/// on their undo stack.
 pub async fn update_card(
     pool: &DbPool,
     id: i32,
     req: UpdateCardRequest,
-) -> Result<Card, AppError> {
-    // Fetch current card first
+) -> Result<(Card, Option<String>), AppError> {
     let current_card = get_card_by_id(pool, id).await?;
-    
+    let previous_title = current_card.title.clone();
+    let user_id = req.edited_by.clone();
+    let merges_into_document = (req.description.is_some() || req.clears_description) && !req.from_document;
+
+    let mut tx = pool.begin().await?;
+
+    let previous = current_card.clone();
+    let (card, wip_warning) = update_card_in(&mut tx, current_card, req).await?;
+    if let Some(user_id) = user_id {
+        record_move(&mut tx, &user_id, &previous, &card).await?;
+        if card.title != previous_title {
+            let board_id = card_keys::get_board_id_for_list(&mut tx, card.list_id).await?;
+            let operation = UndoableOperation::CardRenamed {
+                card_id: card.id,
+                from: previous_title,
+                to: card.title.clone(),
+            };
+            history::record_operation(&mut tx, board_id, &user_id, operation).await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    if merges_into_document {
+        collab::refresh_session(pool, card.id);
+    }
+
+    Ok((card, wip_warning))
+}
+
+/// Update a card within a transaction. Returns a warning when moving it
+/// exceeds an advisory WIP limit. After committing a description edit that is
+/// not `from_document`, callers refresh the card's collaboration session.
+pub(crate) async fn update_card_in(
+    conn: &mut PgConnection,
+    current_card: Card,
+    req: UpdateCardRequest,
+) -> Result<(Card, Option<String>), AppError> {
+    let id = current_card.id;
+    let list_id = req.list_id.unwrap_or(current_card.list_id);
+    let placement = place_card(&mut *conn, &current_card, list_id, req.lane_id).await?;
+
+    // Re-rank only when the card is repositioned or changes cell, appending
+    // it to the new cell unless a position is given
+    let changes_cell = (placement.list_id, placement.lane_id) != (current_card.list_id, current_card.lane_id);
+    let rank = if req.position.is_some() || changes_cell {
+        Some(ranks::card_rank_at(&mut *conn, placement.list_id, placement.lane_id, req.position, Some(id)).await?)
+    } else {
+        None
+    };
+
+    let moved = placement.list_id != current_card.list_id;
+    let mut events = if moved {
+        automation::move_events(&mut *conn, current_card.list_id, placement.list_id).await?
+    } else {
+        Vec::new()
+    };
+    let edits_fields = req.title.is_some()
+        || req.description.is_some()
+        || req.clears_description
+        || req.assignee_id.is_some()
+        || req.due_date.is_some()
+        || req.labels.is_some()
+        || req.priority.is_some()
+        || req.estimate.is_some()
+        || req.estimate_unit.is_some();
+    if edits_fields {
+        events.push(RuleTrigger::CardUpdated);
+    }
+    let reassigned = req.assignee_id.is_some() && req.assignee_id != current_card.assignee_id;
+    let edits_description = req.description.is_some() || req.clears_description;
+    let merges_into_document = edits_description && !req.from_document;
+    let previous_card = current_card.clone();
+
     // Use provided values or keep current ones
     let title = req.title.unwrap_or(current_card.title);
-    let description = req.description.or(current_card.description);
-    let list_id = req.list_id.unwrap_or(current_card.list_id);
-    let position = req.position.unwrap_or(current_card.position);
+    let description = if req.clears_description {
+        None
+    } else {
+        req.description.or(current_card.description)
+    };
     let assignee_id = req.assignee_id.or(current_card.assignee_id);
     let due_date = req.due_date.or(current_card.due_date);
     let labels = req.labels.unwrap_or(current_card.labels);
+    let priority = req.priority.or(current_card.priority);
+    let (estimate, estimate_unit) = resolve_estimate(
+        req.estimate.or(current_card.estimate),
+        req.estimate_unit.or(current_card.estimate_unit),
+    )?;
 
-    let card = sqlx::query_as::<_, Card>(
-        "UPDATE card 
-         SET title = $1, description = $2, list_id = $3, position = $4, 
-             assignee_id = $5, due_date = $6, labels = $7, updated_at = NOW()
-         WHERE id = $8
-         RETURNING id, title, description, list_id, position, assignee_id, due_date, labels, created_at, updated_at"
-    )
+    let mut card = sqlx::query_as::<_, Card>(&format!(
+        "UPDATE card
+         SET title = $1, description = $2,
+             description_html = CASE WHEN description IS DISTINCT FROM $2 THEN NULL ELSE description_html END,
+             list_id = $3, lane_id = $4, rank = COALESCE($5, rank),
+             assignee_id = $6, due_date = $7, labels = $8,
+             priority = $9, estimate = $10, estimate_unit = $11,
+             card_number = $12, card_key = $13, started_at = $14, completed_at = $15,
+             updated_at = NOW()
+         WHERE id = $16
+         RETURNING {CARD_COLUMNS}"
+    ))
     .bind(title)
     .bind(description)
-    .bind(list_id)
-    .bind(position)
+    .bind(placement.list_id)
+    .bind(placement.lane_id)
+    .bind(rank)
     .bind(assignee_id)
     .bind(due_date)
     .bind(labels)
+    .bind(priority)
+    .bind(estimate)
+    .bind(estimate_unit)
+    .bind(placement.card_number)
+    .bind(&placement.card_key)
+    .bind(placement.stamps.0)
+    .bind(placement.stamps.1)
     .bind(id)
-    .fetch_one(pool)
+    .fetch_one(&mut *conn)
     .await?;
 
-    Ok(card)
+    revisions::record_revision(&mut *conn, Some(&previous_card), &card, req.edited_by.as_deref(), req.restored_from)
+        .await?;
+    if edits_description {
+        let description = card.description.clone().unwrap_or_default();
+        let mentioned = mentions::sync_mentions(&mut *conn, card.id, card.list_id, None, &description).await?;
+        mentions::notify_mentioned(&mut *conn, &card, None, &mentioned).await?;
+        card.mentions = Json(mentioned);
+        if merges_into_document {
+            collab::merge_description_edit(&mut *conn, card.id, &description).await?;
+        }
+    }
+    if reassigned {
+        notifications::notify_assignment(&mut *conn, &card).await?;
+    }
+    let card = automation::apply_rules_to_card(&mut *conn, card, events).await?;
+    if moved {
+        webhooks::emit_card(&mut *conn, WebhookEvent::CardMoved, &card).await?;
+    }
+    if edits_fields || !moved {
+        webhooks::emit_card(&mut *conn, WebhookEvent::CardUpdated, &card).await?;
+    }
+
+    Ok((card, placement.wip_warning))
+}
+
+/// Put a move of a card within its board on a user's undo stack. Moves to
+/// another board, where the card takes a new key, cannot be undone and are
+/// not recorded.
+async fn record_move(conn: &mut PgConnection, user_id: &str, previous: &Card, card: &Card) -> Result<(), AppError> {
+    let from = CardPlacement { list_id: previous.list_id, lane_id: previous.lane_id, position: previous.position };
+    let to = CardPlacement { list_id: card.list_id, lane_id: card.lane_id, position: card.position };
+    if from == to {
+        return Ok(());
+    }
+
+    let from_board = card_keys::get_board_id_for_list(&mut *conn, from.list_id).await?;
+    let to_board = card_keys::get_board_id_for_list(&mut *conn, to.list_id).await?;
+    if from_board == to_board {
+        let operation = UndoableOperation::CardMoved { card_id: card.id, from, to };
+        history::record_operation(&mut *conn, to_board, user_id, operation).await?;
+    }
+    Ok(())
+}
+
+/// Move a card to an index within a (list, lane) cell. Only the moved card
+/// is updated: it gets a rank between its new neighbours. Returns a warning
+/// when an advisory WIP limit is exceeded. Moves within a board by a known
+/// user go on their undo stack; moves to another board cannot be undone.
+pub async fn move_card(
+    pool: &DbPool,
+    id: i32,
+    req: MoveCardRequest,
+) -> Result<(Card, Option<String>), AppError> {
+    let current_card = get_card_by_id(pool, id).await?;
+    let user_id = req.user_id.clone();
+
+    let mut tx = pool.begin().await?;
+
+    let (card, wip_warning) = move_card_in(&mut tx, &current_card, req).await?;
+    if let Some(user_id) = user_id {
+        record_move(&mut tx, &user_id, &current_card, &card).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok((card, wip_warning))
+}
+
+/// Move a card within a transaction; see [`move_card`]
+pub(crate) async fn move_card_in(
+    conn: &mut PgConnection,
+    current_card: &Card,
+    req: MoveCardRequest,
+) -> Result<(Card, Option<String>), AppError> {
+    let id = current_card.id;
+    let list_id = req.list_id.unwrap_or(current_card.list_id);
+    let placement = place_card(&mut *conn, current_card, list_id, req.lane_id).await?;
+    let rank = ranks::card_rank_at(&mut *conn, placement.list_id, placement.lane_id, Some(req.position), Some(id)).await?;
+
+    let card = sqlx::query_as::<_, Card>(&format!(
+        "UPDATE card
+         SET list_id = $1, lane_id = $2, rank = $3, card_number = $4, card_key = $5,
+             started_at = $6, completed_at = $7, updated_at = NOW()
+         WHERE id = $8
+         RETURNING {CARD_COLUMNS}"
+    ))
+    .bind(placement.list_id)
+    .bind(placement.lane_id)
+    .bind(&rank)
+    .bind(placement.card_number)
+    .bind(&placement.card_key)
+    .bind(placement.stamps.0)
+    .bind(placement.stamps.1)
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let card = if placement.list_id != current_card.list_id {
+        let events = automation::move_events(&mut *conn, current_card.list_id, placement.list_id).await?;
+        automation::apply_rules_to_card(&mut *conn, card, events).await?
+    } else {
+        card
+    };
+    webhooks::emit_card(&mut *conn, WebhookEvent::CardMoved, &card).await?;
+
+    Ok((card, placement.wip_warning))
 }
 
-/// Delete a card
-pub async fn delete_card(pool: &DbPool, id: i32) -> Result<(), AppError> {
-    let result = sqlx::query("DELETE FROM card WHERE id = $1")
+/// Delete a card. Deletions by a known user go on their undo stack.
+pub async fn delete_card(pool: &DbPool, id: i32, user_id: Option<&str>) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+
+    // The rows hanging off the card go with it; keep them for undo
+    let dependents = match user_id {
+        Some(_) => Some(history::snapshot_dependents(&mut tx, id).await?),
+        None => None,
+    };
+    let card = delete_card_in(&mut tx, id).await?;
+    if let (Some(user_id), Some(dependents)) = (user_id, dependents) {
+        let board_id = card_keys::get_board_id_for_list(&mut tx, card.list_id).await?;
+        let operation = UndoableOperation::CardDeleted { card: Box::new(card), dependents };
+        history::record_operation(&mut tx, board_id, user_id, operation).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Delete a card within a transaction, returning it as it was
+pub(crate) async fn delete_card_in(conn: &mut PgConnection, id: i32) -> Result<Card, AppError> {
+    let card = sqlx::query_as::<_, Card>(&format!("DELETE FROM card WHERE id = $1 RETURNING {CARD_COLUMNS}"))
         .bind(id)
-        .execute(pool)
+        .fetch_optional(&mut *conn)
         .await?;
 
-    if result.rows_affected() == 0 {
+    let Some(card) = card else {
         return Err(AppError::NotFound("Card not found".to_string()));
-    }
+    };
+    webhooks::emit_card(&mut *conn, WebhookEvent::CardDeleted, &card).await?;
 
-    Ok(())
+    Ok(card)
 }
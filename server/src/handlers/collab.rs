@@ -0,0 +1,560 @@
+//! Collaborative editing of card descriptions.
+//!
+//! Clients editing a description together connect to its collaboration
+//! channel, a WebSocket carrying binary Automerge sync messages. The server
+//! takes part in the sync protocol as a peer of each client: it merges what
+//! a client sends into the card's document and syncs the result to the
+//! others. The description is a text object under the document's root key
+//! `description`; clients sync with the server before editing rather than
+//! create it themselves.
+//!
+//! Documents are saved in `card_description_doc` shortly after they change.
+//! Saves merge in the stored document first, so sessions of the same card on
+//! other instances never lose each other's changes, but a card's clients
+//! only see each other's edits live when they are served by one instance.
+//! The document text is written back to `card.description` through
+//! `update_card` (recording a revision, syncing mentions and clearing the
+//! rendered HTML) by the `description_snapshots` job and when the last
+//! client leaves. Edits made through the REST endpoints are applied to the
+//! text of the last snapshot and merged into the document, so they combine
+//! with collaborative edits not yet written back instead of overwriting them.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::Duration;
+
+use automerge::sync::{self, SyncDoc};
+use automerge::transaction::Transactable;
+use automerge::{AutoCommit, ChangeHash, ObjId, ObjType, ReadDoc, Value, ROOT};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
+use sqlx::PgConnection;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::cards::{update_card_in, CARD_COLUMNS};
+use crate::handlers::sync::failure_message;
+use crate::models::{Card, UpdateCardRequest};
+
+/// Root key of the description text in each document
+const TEXT_KEY: &str = "description";
+
+/// How long changes wait to be saved, so a burst of edits is saved once
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// An open document and the clients editing it
+struct Session {
+    card_id: i32,
+    state: Mutex<DocState>,
+    /// Signalled when the document changes, so every client gets synced
+    changed: broadcast::Sender<()>,
+    save_scheduled: AtomicBool,
+}
+
+struct DocState {
+    doc: AutoCommit,
+    /// Last user to change the document
+    edited_by: Option<String>,
+}
+
+impl Session {
+    fn new(card_id: i32, doc: AutoCommit) -> Self {
+        Self {
+            card_id,
+            state: Mutex::new(DocState { doc, edited_by: None }),
+            changed: broadcast::channel(16).0,
+            save_scheduled: AtomicBool::new(false),
+        }
+    }
+
+    /// Apply a client's sync message. Returns whether the document changed.
+    fn receive(&self, sync_state: &mut sync::State, message: sync::Message, user_id: &str) -> Result<bool, AppError> {
+        let mut state = lock(&self.state);
+        let before = state.doc.get_heads();
+        state
+            .doc
+            .sync()
+            .receive_sync_message(sync_state, message)
+            .map_err(|err| AppError::ValidationError(format!("Invalid sync message: {}", err)))?;
+        let changed = state.doc.get_heads() != before;
+        if changed {
+            state.edited_by = Some(user_id.to_string());
+        }
+        Ok(changed)
+    }
+
+    /// The next sync message for a client, if it is missing anything
+    fn generate(&self, sync_state: &mut sync::State) -> Option<sync::Message> {
+        lock(&self.state).doc.sync().generate_sync_message(sync_state)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Open sessions by card, with their number of clients
+type Sessions = HashMap<i32, (Arc<Session>, usize)>;
+
+fn sessions() -> &'static Mutex<Sessions> {
+    static SESSIONS: OnceLock<Mutex<Sessions>> = OnceLock::new();
+    SESSIONS.get_or_init(Default::default)
+}
+
+fn invalid_document(err: impl std::fmt::Display) -> AppError {
+    AppError::InternalError(format!("Invalid description document: {}", err))
+}
+
+/// The document's description text object, if it has one
+fn text_object(doc: &AutoCommit) -> Option<ObjId> {
+    match doc.get(ROOT, TEXT_KEY) {
+        Ok(Some((Value::Object(ObjType::Text), id))) => Some(id),
+        _ => None,
+    }
+}
+
+fn document_text(doc: &AutoCommit) -> String {
+    text_object(doc).and_then(|id| doc.text(&id).ok()).unwrap_or_default()
+}
+
+fn new_document(text: &str) -> Result<AutoCommit, AppError> {
+    let mut doc = AutoCommit::new();
+    let id = doc.put_object(ROOT, TEXT_KEY, ObjType::Text).map_err(invalid_document)?;
+    doc.splice_text(&id, 0, 0, text).map_err(invalid_document)?;
+    Ok(doc)
+}
+
+fn encode_heads(heads: &[ChangeHash]) -> Vec<String> {
+    heads.iter().map(ChangeHash::to_string).collect()
+}
+
+fn decode_heads(heads: &[String]) -> Result<Vec<ChangeHash>, AppError> {
+    heads.iter().map(|head| head.parse().map_err(invalid_document)).collect()
+}
+
+/// Replace the text as of `base` heads and merge the edit into the document.
+/// Returns the heads of the edited text.
+fn merge_edit(doc: &mut AutoCommit, base: &[ChangeHash], text: &str) -> Result<Vec<ChangeHash>, AppError> {
+    let mut fork = match doc.fork_at(base) {
+        Ok(fork) => fork,
+        Err(_) => doc.fork(),
+    };
+    let id = text_object(&fork).ok_or_else(|| invalid_document("missing description text"))?;
+    fork.update_text(&id, text).map_err(invalid_document)?;
+    let heads = fork.get_heads();
+    doc.merge(&mut fork).map_err(invalid_document)?;
+    Ok(heads)
+}
+
+/// Load a card's document, creating it from the description on first use
+async fn load_document(pool: &DbPool, card_id: i32) -> Result<AutoCommit, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // Hold off description edits until the document exists, so that none is
+    // left out of it
+    let description: Option<Option<String>> =
+        sqlx::query_scalar("SELECT description FROM card WHERE id = $1 FOR SHARE")
+            .bind(card_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let description = description.ok_or_else(|| AppError::NotFound("Card not found".to_string()))?;
+
+    let stored: Option<Vec<u8>> = sqlx::query_scalar("SELECT document FROM card_description_doc WHERE card_id = $1")
+        .bind(card_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let doc = match stored {
+        Some(document) => AutoCommit::load(&document).map_err(invalid_document)?,
+        None => {
+            let mut doc = new_document(description.as_deref().unwrap_or_default())?;
+            let heads = encode_heads(&doc.get_heads());
+            let created: Option<Vec<u8>> = sqlx::query_scalar(
+                "INSERT INTO card_description_doc (card_id, document, snapshot_heads)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (card_id) DO NOTHING
+                 RETURNING document"
+            )
+            .bind(card_id)
+            .bind(doc.save())
+            .bind(&heads)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if created.is_none() {
+                // Created concurrently by another session
+                let document: Vec<u8> =
+                    sqlx::query_scalar("SELECT document FROM card_description_doc WHERE card_id = $1")
+                        .bind(card_id)
+                        .fetch_one(&mut *tx)
+                        .await?;
+                doc = AutoCommit::load(&document).map_err(invalid_document)?;
+            }
+            doc
+        }
+    };
+
+    tx.commit().await?;
+
+    Ok(doc)
+}
+
+/// Add a client to the session of a card, starting one from `doc` if there
+/// is none. Returns None when there is no session and no document.
+fn enter(card_id: i32, doc: Option<AutoCommit>) -> Option<Arc<Session>> {
+    let mut sessions = lock(sessions());
+    if let Some((session, clients)) = sessions.get_mut(&card_id) {
+        *clients += 1;
+        return Some(session.clone());
+    }
+    let session = Arc::new(Session::new(card_id, doc?));
+    sessions.insert(card_id, (session.clone(), 1));
+    Some(session)
+}
+
+async fn join(pool: &DbPool, card_id: i32) -> Result<Arc<Session>, AppError> {
+    if let Some(session) = enter(card_id, None) {
+        return Ok(session);
+    }
+    let doc = load_document(pool, card_id).await?;
+    enter(card_id, Some(doc)).ok_or_else(|| AppError::InternalError("Failed to open description document".to_string()))
+}
+
+/// Remove a client from its session. The last client to leave closes the
+/// session, saving the document and writing its text back.
+async fn leave(pool: &DbPool, session: &Session) {
+    let last = {
+        let mut sessions = lock(sessions());
+        match sessions.get_mut(&session.card_id) {
+            Some((_, clients)) if *clients > 1 => {
+                *clients -= 1;
+                false
+            }
+            _ => {
+                sessions.remove(&session.card_id);
+                true
+            }
+        }
+    };
+    if !last {
+        return;
+    }
+
+    let result = match save(pool, session).await {
+        Ok(()) => snapshot_description(pool, session.card_id).await.map(|_| ()),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        tracing::warn!("Failed to close description document of card {}: {:?}", session.card_id, err);
+    }
+}
+
+/// Save a session's document, merging in the stored one first so that
+/// changes saved by other sessions of the card are kept
+async fn save(pool: &DbPool, session: &Session) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+
+    let stored: Option<Vec<u8>> =
+        sqlx::query_scalar("SELECT document FROM card_description_doc WHERE card_id = $1 FOR UPDATE")
+            .bind(session.card_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    // The card has been deleted
+    let Some(stored) = stored else {
+        return Ok(());
+    };
+    let mut stored = AutoCommit::load(&stored).map_err(invalid_document)?;
+    let stored_heads = stored.get_heads();
+
+    let (document, edited_by) = {
+        let mut state = lock(&session.state);
+        let before = state.doc.get_heads();
+        state.doc.merge(&mut stored).map_err(invalid_document)?;
+        let heads = state.doc.get_heads();
+        if heads != before {
+            let _ = session.changed.send(());
+        }
+        if heads == stored_heads {
+            return Ok(());
+        }
+        (state.doc.save(), state.edited_by.clone())
+    };
+
+    sqlx::query(
+        "UPDATE card_description_doc
+         SET document = $2, edited_by = COALESCE($3, edited_by), revision = revision + 1, updated_at = NOW()
+         WHERE card_id = $1"
+    )
+    .bind(session.card_id)
+    .bind(document)
+    .bind(edited_by)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+fn schedule_save(pool: &DbPool, session: &Arc<Session>) {
+    if session.save_scheduled.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let pool = pool.clone();
+    let session = session.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(SAVE_DELAY).await;
+        session.save_scheduled.store(false, Ordering::Release);
+        if let Err(err) = save(&pool, &session).await {
+            tracing::warn!("Failed to save description document of card {}: {:?}", session.card_id, err);
+        }
+    });
+}
+
+/// Serve a client on a card's collaboration channel until it disconnects
+pub async fn collaborate(pool: DbPool, card_id: i32, user_id: String, mut socket: WebSocket) {
+    let session = match join(&pool, card_id).await {
+        Ok(session) => session,
+        Err(err) => return close(&mut socket, err).await,
+    };
+
+    let result = serve(&pool, &session, &user_id, &mut socket).await;
+    if let Err(err) = result {
+        close(&mut socket, err).await;
+    }
+
+    leave(&pool, &session).await;
+}
+
+async fn serve(pool: &DbPool, session: &Arc<Session>, user_id: &str, socket: &mut WebSocket) -> Result<(), AppError> {
+    let mut changed = session.changed.subscribe();
+    let mut sync_state = sync::State::new();
+    send_sync(session, &mut sync_state, socket).await?;
+
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Binary(bytes))) => {
+                    let message = sync::Message::decode(&bytes)
+                        .map_err(|err| AppError::ValidationError(format!("Invalid sync message: {}", err)))?;
+                    if session.receive(&mut sync_state, message, user_id)? {
+                        let _ = session.changed.send(());
+                        schedule_save(pool, session);
+                    }
+                    send_sync(session, &mut sync_state, socket).await?;
+                }
+                Some(Ok(Message::Text(_))) => {
+                    return Err(AppError::ValidationError(
+                        "Collaboration messages must be binary Automerge sync messages".to_string(),
+                    ));
+                }
+                Some(Ok(Message::Ping(_) | Message::Pong(_))) => {}
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return Ok(()),
+            },
+            signal = changed.recv() => match signal {
+                Ok(()) | Err(RecvError::Lagged(_)) => send_sync(session, &mut sync_state, socket).await?,
+                Err(RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
+async fn send_sync(session: &Session, sync_state: &mut sync::State, socket: &mut WebSocket) -> Result<(), AppError> {
+    if let Some(message) = session.generate(sync_state) {
+        socket
+            .send(Message::Binary(message.encode()))
+            .await
+            .map_err(|err| AppError::InternalError(format!("Failed to send sync message: {}", err)))?;
+    }
+    Ok(())
+}
+
+async fn close(socket: &mut WebSocket, err: AppError) {
+    let code = match err {
+        AppError::ValidationError(_) | AppError::NotFound(_) => close_code::POLICY,
+        _ => close_code::ERROR,
+    };
+    let frame = CloseFrame { code, reason: failure_message(err).into() };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+/// Merge an edit of a card's description made outside the collaboration
+/// channel into its stored document, if it has one. The edit replaces the
+/// text of the last snapshot, which the description held until now. Open
+/// sessions pick it up from [`refresh_session`] once the edit is committed.
+pub(crate) async fn merge_description_edit(
+    conn: &mut PgConnection,
+    card_id: i32,
+    description: &str,
+) -> Result<(), AppError> {
+    let stored: Option<(Vec<u8>, Vec<String>)> =
+        sqlx::query_as("SELECT document, snapshot_heads FROM card_description_doc WHERE card_id = $1 FOR UPDATE")
+            .bind(card_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    let Some((document, snapshot_heads)) = stored else {
+        return Ok(());
+    };
+
+    let mut doc = AutoCommit::load(&document).map_err(invalid_document)?;
+    let heads = merge_edit(&mut doc, &decode_heads(&snapshot_heads)?, description)?;
+    let document = doc.save();
+
+    sqlx::query(
+        "UPDATE card_description_doc
+         SET document = $2, snapshot_heads = $3, revision = revision + 1, updated_at = NOW()
+         WHERE card_id = $1"
+    )
+    .bind(card_id)
+    .bind(document)
+    .bind(encode_heads(&heads))
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Bring the open session of a card, if any, up to date with its stored
+/// document, after an edit merged in by [`merge_description_edit`] has been
+/// committed
+pub(crate) fn refresh_session(pool: &DbPool, card_id: i32) {
+    let session = lock(sessions()).get(&card_id).map(|(session, _)| session.clone());
+    if let Some(session) = session {
+        schedule_save(pool, &session);
+    }
+}
+
+/// Write a card's document text back to its description if the document
+/// was saved since the last snapshot. Returns whether the description
+/// changed.
+pub async fn snapshot_description(pool: &DbPool, card_id: i32) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let card = sqlx::query_as::<_, Card>(&format!("SELECT {CARD_COLUMNS} FROM card WHERE id = $1 FOR UPDATE"))
+        .bind(card_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(card) = card else {
+        return Ok(false);
+    };
+    let stored: Option<(Vec<u8>, i64, i64, Option<String>)> = sqlx::query_as(
+        "SELECT document, revision, snapshot_revision, edited_by FROM card_description_doc
+         WHERE card_id = $1
+         FOR UPDATE"
+    )
+    .bind(card_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((document, revision, snapshot_revision, edited_by)) = stored else {
+        return Ok(false);
+    };
+    if snapshot_revision >= revision {
+        return Ok(false);
+    }
+
+    let mut doc = AutoCommit::load(&document).map_err(invalid_document)?;
+    let text = document_text(&doc);
+    let changed = card.description.as_deref().unwrap_or_default() != text;
+    if changed {
+        let req = UpdateCardRequest {
+            description: Some(text),
+            edited_by,
+            from_document: true,
+            ..Default::default()
+        };
+        update_card_in(&mut tx, card, req).await?;
+    }
+
+    sqlx::query("UPDATE card_description_doc SET snapshot_revision = $2, snapshot_heads = $3 WHERE card_id = $1")
+        .bind(card_id)
+        .bind(revision)
+        .bind(encode_heads(&doc.get_heads()))
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(changed)
+}
+
+/// Write back the text of every document saved since its last snapshot.
+/// Returns the number of descriptions changed.
+pub async fn snapshot_descriptions(pool: &DbPool) -> Result<usize, AppError> {
+    let card_ids: Vec<i32> = sqlx::query_scalar(
+        "SELECT card_id FROM card_description_doc WHERE snapshot_revision < revision ORDER BY card_id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut changed = 0;
+    for card_id in card_ids {
+        if snapshot_description(pool, card_id).await? {
+            changed += 1;
+        }
+    }
+
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Edit the text of a document as a collaborating client would
+    fn splice(doc: &mut AutoCommit, pos: usize, del: isize, text: &str) {
+        let id = text_object(doc).unwrap();
+        doc.splice_text(&id, pos, del, text).unwrap();
+    }
+
+    #[test]
+    fn new_documents_hold_the_description() {
+        let doc = new_document("Hello").unwrap();
+        assert_eq!(document_text(&doc), "Hello");
+        assert_eq!(document_text(&AutoCommit::new()), "");
+    }
+
+    #[test]
+    fn an_edit_of_the_current_text_replaces_it() {
+        let mut doc = new_document("hello world").unwrap();
+        let base = doc.get_heads();
+        let heads = merge_edit(&mut doc, &base, "hello there").unwrap();
+        assert_eq!(document_text(&doc), "hello there");
+        assert_eq!(heads, doc.get_heads());
+    }
+
+    #[test]
+    fn an_edit_of_an_older_text_keeps_collaborative_changes() {
+        let mut doc = new_document("hello world").unwrap();
+        let snapshot = doc.get_heads();
+        splice(&mut doc, 11, 0, " again");
+
+        merge_edit(&mut doc, &snapshot, "Hello world").unwrap();
+        assert_eq!(document_text(&doc), "Hello world again");
+    }
+
+    #[test]
+    fn unknown_base_heads_edit_the_current_text() {
+        let mut doc = new_document("hello").unwrap();
+        let mut other = new_document("unrelated").unwrap();
+        splice(&mut other, 0, 0, "x");
+        let unknown = other.get_heads();
+
+        merge_edit(&mut doc, &unknown, "goodbye").unwrap();
+        assert_eq!(document_text(&doc), "goodbye");
+    }
+
+    #[test]
+    fn documents_without_text_cannot_be_edited() {
+        let mut doc = AutoCommit::new();
+        let base = doc.get_heads();
+        assert!(matches!(merge_edit(&mut doc, &base, "text"), Err(AppError::InternalError(_))));
+    }
+
+    #[test]
+    fn heads_round_trip_through_their_encoding() {
+        let mut doc = new_document("a").unwrap();
+        splice(&mut doc, 1, 0, "b");
+        let heads = doc.get_heads();
+        assert_eq!(decode_heads(&encode_heads(&heads)).unwrap(), heads);
+        assert!(decode_heads(&["not a hash".to_string()]).is_err());
+    }
+}
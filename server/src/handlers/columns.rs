@@ -1,25 +1,57 @@
+use chrono::Utc;
+use sqlx::PgConnection;
+
 use crate::db::DbPool;
 use crate::errors::AppError;
-use crate::models::{BoardColumn, CreateColumnRequest, UpdateColumnRequest};
+use crate::handlers::columns_move::move_column_in;
+use crate::handlers::automation::{self, CardEvent};
+use crate::handlers::card_lifecycle::stamps_on_enter;
+use crate::handlers::cards::CARD_COLUMNS;
+use crate::handlers::{history, ranks, webhooks};
+use crate::models::{
+    BoardColumn, Card, CreateColumnRequest, RuleTrigger, UndoableOperation, UpdateColumnRequest, WebhookEvent,
+};
+
+/// Columns selected for every `BoardColumn` query. The position (the column's
+/// rank on its board) and the card count refer to the unaliased
+/// `board_column` table, so queries using it must not alias it.
+pub(crate) const COLUMN_COLUMNS: &str = "id, title, board_id, \
+     (SELECT COUNT(*) FROM board_column s \
+      WHERE s.board_id = board_column.board_id AND s.rank < board_column.rank \
+        AND s.id <> board_column.id)::INT AS position, \
+     category, wip_limit, wip_mode, \
+     (SELECT COUNT(*) FROM card c WHERE c.list_id = board_column.id) AS card_count, \
+     created_at, updated_at";
+
+/// Validate a WIP limit, mapping `0` to "no limit"
+fn resolve_wip_limit(wip_limit: Option<i32>) -> Result<Option<i32>, AppError> {
+    match wip_limit {
+        Some(limit) if limit < 0 => Err(AppError::ValidationError(
+            "WIP limit must be a non-negative number".to_string(),
+        )),
+        Some(0) => Ok(None),
+        limit => Ok(limit),
+    }
+}
 
 /// Get all columns (optionally filtered by board_id)
 pub async fn get_all_columns(pool: &DbPool, board_id: Option<i32>) -> Result<Vec<BoardColumn>, AppError> {
     let cols = if let Some(board_id) = board_id {
-        sqlx::query_as::<_, BoardColumn>(
-            "SELECT id, title, board_id, position, created_at, updated_at 
-             FROM board_column 
+        sqlx::query_as::<_, BoardColumn>(&format!(
+            "SELECT {COLUMN_COLUMNS}
+             FROM board_column
              WHERE board_id = $1
-             ORDER BY position ASC, id ASC"
-        )
+             ORDER BY rank ASC"
+        ))
         .bind(board_id)
         .fetch_all(pool)
         .await?
     } else {
-        sqlx::query_as::<_, BoardColumn>(
-            "SELECT id, title, board_id, position, created_at, updated_at 
-             FROM board_column 
-             ORDER BY position ASC, id ASC"
-        )
+        sqlx::query_as::<_, BoardColumn>(&format!(
+            "SELECT {COLUMN_COLUMNS}
+             FROM board_column
+             ORDER BY rank ASC"
+        ))
         .fetch_all(pool)
         .await?
     };
@@ -29,11 +61,11 @@ pub async fn get_all_columns(pool: &DbPool, board_id: Option<i32>) -> Result<Vec
 
 /// Get column by ID
 pub async fn get_column_by_id(pool: &DbPool, id: i32) -> Result<BoardColumn, AppError> {
-    let col = sqlx::query_as::<_, BoardColumn>(
-        "SELECT id, title, board_id, position, created_at, updated_at 
-         FROM board_column 
+    let col = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS}
+         FROM board_column
          WHERE id = $1"
-    )
+    ))
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -46,81 +78,150 @@ pub async fn create_column(pool: &DbPool, req: CreateColumnRequest) -> Result<Bo
     if req.title.trim().is_empty() {
         return Err(AppError::ValidationError("Title is required".to_string()));
     }
+    let wip_limit = resolve_wip_limit(req.wip_limit)?;
+
+    let mut tx = pool.begin().await?;
 
     // Validate that the board exists
     let board_exists: bool = sqlx::query_scalar(
         "SELECT EXISTS(SELECT 1 FROM board WHERE id = $1)"
     )
     .bind(req.board_id)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
     if !board_exists {
         return Err(AppError::NotFound(format!("Board with id {} not found", req.board_id)));
     }
 
-    let next_position: i32 = sqlx::query_scalar(
-        "SELECT COALESCE(MAX(position), -1) + 1 FROM board_column WHERE board_id = $1"
-    )
-    .bind(req.board_id)
-    .fetch_one(pool)
-    .await?;
+    let rank = ranks::column_rank_at(&mut tx, req.board_id, None, None).await?;
 
-    let col = sqlx::query_as::<_, BoardColumn>(
-        "INSERT INTO board_column (title, board_id, position) 
-         VALUES ($1, $2, $3) 
-         RETURNING id, title, board_id, position, created_at, updated_at"
-    )
+    let col = sqlx::query_as::<_, BoardColumn>(&format!(
+        "INSERT INTO board_column (title, board_id, rank, category, wip_limit, wip_mode)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING {COLUMN_COLUMNS}"
+    ))
     .bind(&req.title)
     .bind(req.board_id)
-    .bind(next_position)
-    .fetch_one(pool)
+    .bind(&rank)
+    .bind(req.category.unwrap_or_default())
+    .bind(wip_limit)
+    .bind(req.wip_mode.unwrap_or_default())
+    .fetch_one(&mut *tx)
     .await?;
 
+    webhooks::emit(&mut tx, col.board_id, WebhookEvent::ColumnCreated, &col).await?;
+
+    tx.commit().await?;
+
     Ok(col)
 }
 
-/// Update a column
+/// Update a column. Renames by a known user (`userId`) go on their undo stack.
 pub async fn update_column(pool: &DbPool, id: i32, req: UpdateColumnRequest) -> Result<BoardColumn, AppError> {
-    if let Some(ref title) = req.title {
-        if title.trim().is_empty() {
-            return Err(AppError::ValidationError("Title is required".to_string()));
+    // Fetch current column
+    let current = get_column_by_id(pool, id).await?;
+    let previous = (current.board_id, current.title.clone());
+    let user_id = req.user_id.clone();
+
+    let mut tx = pool.begin().await?;
+
+    let col = update_column_in(&mut tx, current, req).await?;
+
+    // Renames within the board go on the user's undo stack
+    if let Some(user_id) = user_id.as_deref() {
+        let (board_id, title) = previous;
+        if col.board_id == board_id && col.title != title {
+            let operation = UndoableOperation::ColumnRenamed {
+                column_id: id,
+                from: title,
+                to: col.title.clone(),
+            };
+            history::record_operation(&mut tx, col.board_id, user_id, operation).await?;
         }
     }
 
-    // Fetch current column
-    let current = get_column_by_id(pool, id).await?;
+    tx.commit().await?;
 
-    // If a new board_id is provided, validate that the board exists
-    if let Some(new_board_id) = req.board_id {
-        let board_exists: bool = sqlx::query_scalar(
-            "SELECT EXISTS(SELECT 1 FROM board WHERE id = $1)"
-        )
-        .bind(new_board_id)
-        .fetch_one(pool)
-        .await?;
+    Ok(col)
+}
 
-        if !board_exists {
-            return Err(AppError::NotFound(format!("Board with id {} not found", new_board_id)));
+/// Update a column within a transaction
+pub(crate) async fn update_column_in(
+    conn: &mut PgConnection,
+    current: BoardColumn,
+    req: UpdateColumnRequest,
+) -> Result<BoardColumn, AppError> {
+    if let Some(ref title) = req.title {
+        if title.trim().is_empty() {
+            return Err(AppError::ValidationError("Title is required".to_string()));
         }
     }
+    let id = current.id;
+
+    // Moving to another board goes through the same path as `POST /lists/:id/move`
+    // so the cards are re-keyed and their lanes remapped
+    let rank = match req.board_id {
+        Some(board_id) if board_id != current.board_id => {
+            move_column_in(&mut *conn, id, board_id, req.position).await?;
+            None
+        }
+        _ => match req.position {
+            Some(position) => Some(ranks::column_rank_at(&mut *conn, current.board_id, Some(position), Some(id)).await?),
+            None => None,
+        },
+    };
     let title = req.title.unwrap_or(current.title);
-    let board_id = req.board_id.unwrap_or(current.board_id);
-    let position = req.position.unwrap_or(current.position);
-
-    let col = sqlx::query_as::<_, BoardColumn>(
-        "UPDATE board_column 
-         SET title = $1, board_id = $2, position = $3, updated_at = NOW()
-         WHERE id = $4
-         RETURNING id, title, board_id, position, created_at, updated_at"
-    )
+    let wip_limit = match req.wip_limit {
+        Some(limit) => resolve_wip_limit(Some(limit))?,
+        None => current.wip_limit,
+    };
+    let wip_mode = req.wip_mode.unwrap_or(current.wip_mode);
+    let category = req.category.unwrap_or(current.category);
+
+    let col = sqlx::query_as::<_, BoardColumn>(&format!(
+        "UPDATE board_column
+         SET title = $1, rank = COALESCE($2, rank), category = $3,
+             wip_limit = $4, wip_mode = $5, updated_at = NOW()
+         WHERE id = $6
+         RETURNING {COLUMN_COLUMNS}"
+    ))
     .bind(title)
-    .bind(board_id)
-    .bind(position)
+    .bind(rank)
+    .bind(category)
+    .bind(wip_limit)
+    .bind(wip_mode)
     .bind(id)
-    .fetch_one(pool)
+    .fetch_one(&mut *conn)
     .await?;
 
+    // Recategorising a list counts as its cards entering the new category,
+    // stamps and all
+    if col.category != current.category {
+        let cards = sqlx::query_as::<_, Card>(&format!(
+            "SELECT {CARD_COLUMNS} FROM card WHERE list_id = $1 ORDER BY rank ASC"
+        ))
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let now = Utc::now();
+        let mut events = Vec::with_capacity(cards.len());
+        for card in cards {
+            let (started_at, completed_at) = stamps_on_enter(col.category, (card.started_at, card.completed_at), now);
+            sqlx::query("UPDATE card SET started_at = $1, completed_at = $2, updated_at = NOW() WHERE id = $3")
+                .bind(started_at)
+                .bind(completed_at)
+                .bind(card.id)
+                .execute(&mut *conn)
+                .await?;
+            events.push(CardEvent::new(card.id, RuleTrigger::CardEnteredCategory { category: col.category }));
+        }
+        automation::run_automations(&mut *conn, events).await?;
+    }
+
+    webhooks::emit(&mut *conn, col.board_id, WebhookEvent::ColumnUpdated, &col).await?;
+
     Ok(col)
 }
 
@@ -129,28 +230,39 @@ pub async fn delete_column(pool: &DbPool, id: i32) -> Result<(), AppError> {
     // Perform related deletes in a single transaction to avoid partial updates
     let mut tx = pool.begin().await?;
 
+    delete_column_in(&mut tx, id).await?;
+
+    // The remaining columns keep their ranks, so their derived positions
+    // close the gap without renumbering
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Delete a column and its cards within a transaction
+pub(crate) async fn delete_column_in(conn: &mut PgConnection, id: i32) -> Result<(), AppError> {
     // Check if column exists first
-    let existing: Option<i32> = sqlx::query_scalar(
-        "SELECT board_id FROM board_column WHERE id = $1"
-    )
+    let existing = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS} FROM board_column WHERE id = $1"
+    ))
     .bind(id)
-    .fetch_optional(&mut *tx)
+    .fetch_optional(&mut *conn)
     .await?;
 
-    let Some(board_id) = existing else {
+    let Some(column) = existing else {
         return Err(AppError::NotFound("Column not found".to_string()));
     };
 
     // Delete all cards in this column first (cascade delete)
     sqlx::query("DELETE FROM card WHERE list_id = $1")
         .bind(id)
-        .execute(&mut *tx)
+        .execute(&mut *conn)
         .await?;
 
     // Now delete the column
     let result = sqlx::query("DELETE FROM board_column WHERE id = $1")
         .bind(id)
-        .execute(&mut *tx)
+        .execute(&mut *conn)
         .await?;
 
     if result.rows_affected() == 0 {
@@ -158,26 +270,7 @@ pub async fn delete_column(pool: &DbPool, id: i32) -> Result<(), AppError> {
         return Err(AppError::NotFound("Column not found".to_string()));
     }
 
-    // Renumber all remaining columns to ensure sequential positions starting from 0.
-    // This handles any gaps or duplicate positions that may exist due to bugs or concurrent modifications.
-    sqlx::query(
-        "WITH ordered AS (
-             SELECT id,
-                    ROW_NUMBER() OVER (ORDER BY position ASC, id ASC) - 1 AS new_position
-             FROM board_column
-             WHERE board_id = $1
-         )
-         UPDATE board_column AS bc
-         SET position = o.new_position,
-             updated_at = NOW()
-         FROM ordered AS o
-         WHERE bc.id = o.id"
-    )
-    .bind(board_id)
-    .execute(&mut *tx)
-    .await?;
-
-    tx.commit().await?;
+    webhooks::emit(&mut *conn, column.board_id, WebhookEvent::ColumnDeleted, &column).await?;
 
     Ok(())
 }
@@ -1,6 +1,9 @@
 use crate::db::DbPool;
 use crate::errors::AppError;
-use crate::models::BoardColumn;
+use crate::handlers::columns::COLUMN_COLUMNS;
+use crate::handlers::history;
+use crate::handlers::ranks::spread_ranks;
+use crate::models::{BoardColumn, UndoableOperation};
 use serde::Deserialize;
 use utoipa::ToSchema;
 
@@ -9,6 +12,9 @@ pub struct BulkColumnOrderUpdate {
     #[serde(rename = "boardId")]
     pub board_id: i32,
     pub columns: Vec<ColumnOrderUpdate>,
+    /// User reordering the columns; recorded on their undo stack for the board
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -34,7 +40,7 @@ pub async fn bulk_update_column_order(pool: &DbPool, req: BulkColumnOrderUpdate)
     }
 
     let existing_ids: Vec<i32> = sqlx::query_scalar(
-        "SELECT id FROM board_column WHERE board_id = $1"
+        "SELECT id FROM board_column WHERE board_id = $1 ORDER BY rank ASC"
     )
     .bind(req.board_id)
     .fetch_all(&mut *tx)
@@ -66,7 +72,7 @@ pub async fn bulk_update_column_order(pool: &DbPool, req: BulkColumnOrderUpdate)
         ));
     }
 
-    let mut existing_ids_sorted = existing_ids;
+    let mut existing_ids_sorted = existing_ids.clone();
     existing_ids_sorted.sort_unstable();
 
     let mut payload_ids: Vec<i32> = req.columns.iter().map(|c| c.id).collect();
@@ -100,6 +106,11 @@ pub async fn bulk_update_column_order(pool: &DbPool, req: BulkColumnOrderUpdate)
         ));
     }
 
+    // A full reorder gives every column a fresh, evenly spaced rank
+    let mut ordered: Vec<&ColumnOrderUpdate> = req.columns.iter().collect();
+    ordered.sort_unstable_by_key(|c| c.position);
+    let ranks = spread_ranks(ordered.len());
+
     // Perform bulk update in a single statement to reduce round-trips and lock time
     // Note: This dynamic SQL construction is safe because:
     // - The number of columns (req.columns.len()) is validated to be non-empty above
@@ -108,12 +119,12 @@ pub async fn bulk_update_column_order(pool: &DbPool, req: BulkColumnOrderUpdate)
     // Any mismatch between parameters and bindings would cause a runtime error from sqlx
     let mut sql = String::from(
         "UPDATE board_column AS bc \
-         SET position = v.position, updated_at = NOW() \
+         SET rank = v.rank, updated_at = NOW() \
          FROM (VALUES ",
     );
 
     // Build the VALUES list: ($1, $2), ($3, $4), ...
-    for (i, _col) in req.columns.iter().enumerate() {
+    for (i, _col) in ordered.iter().enumerate() {
         if i > 0 {
             sql.push_str(", ");
         }
@@ -122,15 +133,15 @@ pub async fn bulk_update_column_order(pool: &DbPool, req: BulkColumnOrderUpdate)
     }
 
     // Final parameter is board_id
-    let board_id_param = req.columns.len() * 2 + 1;
+    let board_id_param = ordered.len() * 2 + 1;
     sql.push_str(&format!(
-        ") AS v(id, position) WHERE bc.board_id = ${} AND bc.id = v.id",
+        ") AS v(id, rank) WHERE bc.board_id = ${} AND bc.id = v.id",
         board_id_param
     ));
 
     let mut query = sqlx::query(&sql);
-    for col in &req.columns {
-        query = query.bind(col.id).bind(col.position);
+    for (col, rank) in ordered.iter().zip(&ranks) {
+        query = query.bind(col.id).bind(rank);
     }
     query = query.bind(req.board_id);
     let result = query.execute(&mut *tx).await?;
@@ -146,10 +157,19 @@ pub async fn bulk_update_column_order(pool: &DbPool, req: BulkColumnOrderUpdate)
         ));
     }
 
+    // A changed order goes on the user's undo stack
+    let new_ids: Vec<i32> = ordered.iter().map(|c| c.id).collect();
+    if let Some(user_id) = req.user_id.as_deref() {
+        if new_ids != existing_ids {
+            let operation = UndoableOperation::ColumnsReordered { from: existing_ids, to: new_ids };
+            history::record_operation(&mut tx, req.board_id, user_id, operation).await?;
+        }
+    }
+
     // Return updated columns within the transaction to avoid race conditions
-    let updated = sqlx::query_as::<_, BoardColumn>(
-        "SELECT id, title, board_id, position, created_at, updated_at FROM board_column WHERE board_id = $1 ORDER BY position ASC, id ASC"
-    )
+    let updated = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS} FROM board_column WHERE board_id = $1 ORDER BY rank ASC"
+    ))
     .bind(req.board_id)
     .fetch_all(&mut *tx)
     .await?;
@@ -0,0 +1,210 @@
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::columns::COLUMN_COLUMNS;
+use crate::handlers::{card_keys, lanes, ranks, webhooks};
+use crate::models::{BoardColumn, CardKeyChange, ColumnMoveReport, LaneRemap, MoveColumnRequest, WebhookEvent};
+
+/// Move a column with all its cards to an index on any board in one
+/// transaction. See [`move_column_in`] for what changes on another board.
+pub async fn move_column(pool: &DbPool, id: i32, req: MoveColumnRequest) -> Result<ColumnMoveReport, AppError> {
+    let mut tx = pool.begin().await?;
+    let report = move_column_in(&mut tx, id, req.board_id, req.position).await?;
+    tx.commit().await?;
+
+    Ok(report)
+}
+
+/// Move a column to an index among a board's columns. Only the column's own
+/// rank changes. On another board its cards get keys from that
+/// board (old keys keep resolving) and land in the lane with the same title
+/// as their old lane, or in the default lane when there is none. Webhooks of
+/// the old board get `column.deleted` and those of the new one `column.created`.
+pub async fn move_column_in(
+    conn: &mut PgConnection,
+    id: i32,
+    board_id: i32,
+    position: Option<i32>,
+) -> Result<ColumnMoveReport, AppError> {
+    let source_board_id: Option<i32> = sqlx::query_scalar(
+        "SELECT board_id FROM board_column WHERE id = $1 FOR UPDATE"
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(source_board_id) = source_board_id else {
+        return Err(AppError::NotFound("Column not found".to_string()));
+    };
+
+    let board_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM board WHERE id = $1)"
+    )
+    .bind(board_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if !board_exists {
+        return Err(AppError::NotFound(format!("Board with id {} not found", board_id)));
+    }
+
+    let mut report = ColumnMoveReport {
+        column: get_column(&mut *conn, id).await?,
+        source_board_id,
+        target_board_id: board_id,
+        rekeyed_cards: Vec::new(),
+        remapped_lanes: Vec::new(),
+    };
+
+    if board_id != source_board_id {
+        report.remapped_lanes = remap_lanes(&mut *conn, id, source_board_id, board_id).await?;
+        report.rekeyed_cards = rekey_cards(&mut *conn, id, board_id).await?;
+    }
+
+    let rank = ranks::column_rank_at(&mut *conn, board_id, position, Some(id)).await?;
+
+    sqlx::query("UPDATE board_column SET board_id = $1, rank = $2, updated_at = NOW() WHERE id = $3")
+        .bind(board_id)
+        .bind(&rank)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+
+    report.column = get_column(&mut *conn, id).await?;
+
+    // Webhooks of each board see the column leave or arrive
+    if board_id != source_board_id {
+        webhooks::emit(&mut *conn, source_board_id, WebhookEvent::ColumnDeleted, &report.column).await?;
+        webhooks::emit(&mut *conn, board_id, WebhookEvent::ColumnCreated, &report.column).await?;
+    } else {
+        webhooks::emit(&mut *conn, board_id, WebhookEvent::ColumnUpdated, &report.column).await?;
+    }
+
+    Ok(report)
+}
+
+async fn get_column(conn: &mut PgConnection, id: i32) -> Result<BoardColumn, AppError> {
+    let column = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS} FROM board_column WHERE id = $1"
+    ))
+    .bind(id)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(column)
+}
+
+/// Move the column's cards into matching lanes of the target board,
+/// re-ranking each resulting (column, lane) cell
+async fn remap_lanes(
+    conn: &mut PgConnection,
+    column_id: i32,
+    source_board_id: i32,
+    target_board_id: i32,
+) -> Result<Vec<LaneRemap>, AppError> {
+    let target_lanes = lanes::get_lanes_for_board(&mut *conn, target_board_id).await?;
+    let Some(default_lane) = target_lanes.iter().find(|lane| lane.is_default) else {
+        return Err(AppError::InternalError(format!(
+            "Board {} has no default lane",
+            target_board_id
+        )));
+    };
+
+    let used_lanes: Vec<(i32, i64)> = sqlx::query_as(
+        "SELECT lane_id, COUNT(*) FROM card WHERE list_id = $1 GROUP BY lane_id"
+    )
+    .bind(column_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut remaps = Vec::new();
+    for source_lane in lanes::get_lanes_for_board(&mut *conn, source_board_id).await? {
+        let Some((_, card_count)) = used_lanes.iter().find(|(lane_id, _)| *lane_id == source_lane.id) else {
+            continue;
+        };
+
+        let matched = target_lanes
+            .iter()
+            .find(|lane| lane.title.trim().eq_ignore_ascii_case(source_lane.title.trim()));
+        let target_lane = matched.unwrap_or(default_lane);
+
+        remaps.push(LaneRemap {
+            source_lane_id: source_lane.id,
+            source_lane_title: source_lane.title,
+            target_lane_id: target_lane.id,
+            target_lane_title: target_lane.title.clone(),
+            matched_by_title: matched.is_some(),
+            card_count: *card_count,
+        });
+    }
+
+    let source_ids: Vec<i32> = remaps.iter().map(|remap| remap.source_lane_id).collect();
+    let target_ids: Vec<i32> = remaps.iter().map(|remap| remap.target_lane_id).collect();
+    let total: i64 = used_lanes.iter().map(|(_, count)| count).sum();
+    let spread = ranks::spread_ranks(usize::try_from(total).unwrap_or_default());
+
+    // Lanes merged into one target lane are interleaved by their old ranks
+    sqlx::query(
+        "WITH mapped AS (
+             SELECT c.id, m.target AS lane_id,
+                    ROW_NUMBER() OVER (PARTITION BY m.target ORDER BY c.rank ASC, c.id ASC) AS n
+             FROM card c
+             JOIN unnest($2::INT[], $3::INT[]) AS m(source, target) ON m.source = c.lane_id
+             WHERE c.list_id = $1
+         )
+         UPDATE card
+         SET lane_id = mapped.lane_id, rank = ($4::TEXT[])[mapped.n], updated_at = NOW()
+         FROM mapped
+         WHERE card.id = mapped.id"
+    )
+    .bind(column_id)
+    .bind(&source_ids)
+    .bind(&target_ids)
+    .bind(&spread)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(remaps)
+}
+
+/// Give the column's cards the next keys of the target board, in their
+/// current number order, keeping the old keys as aliases
+async fn rekey_cards(
+    conn: &mut PgConnection,
+    column_id: i32,
+    target_board_id: i32,
+) -> Result<Vec<CardKeyChange>, AppError> {
+    let cards: Vec<(i32, String)> = sqlx::query_as(
+        "SELECT id, card_key FROM card WHERE list_id = $1 ORDER BY card_number ASC, id ASC"
+    )
+    .bind(column_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if cards.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let count = i32::try_from(cards.len())
+        .map_err(|_| AppError::ValidationError("Too many cards to move".to_string()))?;
+    let (first_number, prefix) = card_keys::allocate_card_numbers(&mut *conn, target_board_id, count).await?;
+
+    let mut changes = Vec::with_capacity(cards.len());
+    for (offset, (card_id, old_key)) in (0..).zip(cards) {
+        card_keys::record_key_alias(&mut *conn, &old_key, card_id).await?;
+
+        let number = first_number + offset;
+        let new_key = format!("{}-{}", prefix, number);
+        sqlx::query("UPDATE card SET card_number = $1, card_key = $2, updated_at = NOW() WHERE id = $3")
+            .bind(number)
+            .bind(&new_key)
+            .bind(card_id)
+            .execute(&mut *conn)
+            .await?;
+
+        changes.push(CardKeyChange { card_id, old_key, new_key });
+    }
+
+    Ok(changes)
+}
@@ -0,0 +1,144 @@
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::cards::{get_card_by_id, CARD_COLUMNS};
+use crate::handlers::mentions;
+use crate::handlers::notifications::{self, NewNotification};
+use crate::models::{Card, CardComment, CreateCommentRequest, NotificationKind, UpdateCommentRequest};
+
+/// Columns selected for every `CardComment` query; the mentions refer to the
+/// unaliased `card_comment` table
+pub(crate) const COMMENT_COLUMNS: &str = "id, card_id, author_id, body, \
+     (SELECT COALESCE(jsonb_agg(jsonb_build_object( \
+                 'userId', m.user_id, 'displayName', COALESCE(p.display_name, m.user_id), \
+                 'start', m.span_start, 'end', m.span_end) ORDER BY m.span_start), '[]') \
+      FROM mention m LEFT JOIN user_profile p ON p.user_id = m.user_id \
+      WHERE m.comment_id = card_comment.id) AS mentions, \
+     created_at, updated_at";
+
+/// Longest comment excerpt quoted in a notification
+const EXCERPT_CHARS: usize = 80;
+
+fn validate_body(body: &str) -> Result<(), AppError> {
+    if body.trim().is_empty() {
+        return Err(AppError::ValidationError("Comment body is required".to_string()));
+    }
+    Ok(())
+}
+
+fn excerpt(body: &str) -> String {
+    let body = body.trim();
+    match body.char_indices().nth(EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+/// Get the comments of a card, oldest first
+pub async fn get_comments_for_card(pool: &DbPool, card_id: i32) -> Result<Vec<CardComment>, AppError> {
+    get_card_by_id(pool, card_id).await?;
+
+    let comments = sqlx::query_as::<_, CardComment>(&format!(
+        "SELECT {COMMENT_COLUMNS} FROM card_comment WHERE card_id = $1 ORDER BY created_at ASC, id ASC"
+    ))
+    .bind(card_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(comments)
+}
+
+/// Comment on a card. The author starts watching the card and its other
+/// watchers are notified.
+pub async fn create_comment(pool: &DbPool, card_id: i32, req: CreateCommentRequest) -> Result<CardComment, AppError> {
+    validate_body(&req.body)?;
+    if req.author_id.trim().is_empty() {
+        return Err(AppError::ValidationError("authorId is required".to_string()));
+    }
+
+    let card = get_card_by_id(pool, card_id).await?;
+    let mut tx = pool.begin().await?;
+
+    let comment = create_comment_in(&mut tx, &card, req).await?;
+
+    tx.commit().await?;
+
+    Ok(comment)
+}
+
+/// Comment on a card within a transaction; see [`create_comment`]
+pub(crate) async fn create_comment_in(
+    conn: &mut PgConnection,
+    card: &Card,
+    req: CreateCommentRequest,
+) -> Result<CardComment, AppError> {
+    let mut comment = sqlx::query_as::<_, CardComment>(&format!(
+        "INSERT INTO card_comment (card_id, author_id, body) VALUES ($1, $2, $3) RETURNING {COMMENT_COLUMNS}"
+    ))
+    .bind(card.id)
+    .bind(&req.author_id)
+    .bind(&req.body)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    notifications::watch_card(&mut *conn, card.id, &req.author_id).await?;
+    let watchers = notifications::get_watchers(&mut *conn, card.id).await?;
+    let notification = NewNotification {
+        kind: NotificationKind::Commented,
+        card_id: Some(card.id),
+        actor_id: Some(&req.author_id),
+        message: format!("{} commented on {}: {}", req.author_id, card.card_key, excerpt(&req.body)),
+        dedup_key: format!("commented:{}", comment.id),
+    };
+    notifications::notify(&mut *conn, &watchers, notification).await?;
+
+    let mentioned = mentions::sync_mentions(&mut *conn, card.id, card.list_id, Some(comment.id), &comment.body).await?;
+    mentions::notify_mentioned(&mut *conn, card, Some(&comment), &mentioned).await?;
+    comment.mentions = Json(mentioned);
+
+    Ok(comment)
+}
+
+/// Edit a comment's body, notifying users it newly mentions
+pub async fn update_comment(pool: &DbPool, id: i32, req: UpdateCommentRequest) -> Result<CardComment, AppError> {
+    validate_body(&req.body)?;
+
+    let mut tx = pool.begin().await?;
+
+    let comment = sqlx::query_as::<_, CardComment>(&format!(
+        "UPDATE card_comment SET body = $1, updated_at = NOW() WHERE id = $2 RETURNING {COMMENT_COLUMNS}"
+    ))
+    .bind(&req.body)
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let mut comment = comment.ok_or_else(|| AppError::NotFound(format!("Comment with id {} not found", id)))?;
+
+    let card = sqlx::query_as::<_, Card>(&format!("SELECT {CARD_COLUMNS} FROM card WHERE id = $1"))
+        .bind(comment.card_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let mentioned = mentions::sync_mentions(&mut tx, card.id, card.list_id, Some(comment.id), &comment.body).await?;
+    mentions::notify_mentioned(&mut tx, &card, Some(&comment), &mentioned).await?;
+    comment.mentions = Json(mentioned);
+
+    tx.commit().await?;
+
+    Ok(comment)
+}
+
+/// Delete a comment
+pub async fn delete_comment(pool: &DbPool, id: i32) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM card_comment WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Comment with id {} not found", id)));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::automation::RULE_COLUMNS;
+use crate::handlers::boards::{get_board_by_id, BOARD_COLUMNS};
+use crate::handlers::card_lifecycle::{get_column_category, stamps_on_enter};
+use crate::handlers::cards::{get_card_by_id, CARD_COLUMNS};
+use crate::handlers::columns::{get_column_by_id, COLUMN_COLUMNS};
+use crate::handlers::{automation, card_keys, lanes, mentions, ranks, webhooks, wip};
+use crate::models::{
+    AutomationRule, Board, BoardColumn, Card, CopyBoardRequest, CopyCardRequest, CopyColumnRequest,
+    RuleTrigger, WebhookEvent,
+};
+
+fn validate_copy_title(title: Option<&str>) -> Result<(), AppError> {
+    if title.is_some_and(|t| t.trim().is_empty()) {
+        return Err(AppError::ValidationError("Title cannot be empty".to_string()));
+    }
+    Ok(())
+}
+
+/// How the lanes of copied cards map onto the target board
+enum LaneMapping<'a> {
+    /// Same board: cards stay in their lanes
+    Keep,
+    /// Another board: every card goes into this lane
+    Single(i32),
+    /// Deep board copy: `(source lane, target lane)` pairs
+    Remap(&'a [(i32, i32)]),
+}
+
+/// Copy every card of one list into another, keeping their order. The copies
+/// get fresh keys from the target board and mention its members; comments and
+/// time entries are not copied. The cards must fit the target list's strict
+/// WIP limit; an advisory limit may be exceeded, as it may be in the source.
+async fn copy_cards_between_lists(
+    conn: &mut PgConnection,
+    source_list_id: i32,
+    target_list_id: i32,
+    target_board_id: i32,
+    lanes: LaneMapping<'_>,
+) -> Result<(), AppError> {
+    let count: i32 = sqlx::query_scalar("SELECT COUNT(*)::INT FROM card WHERE list_id = $1")
+        .bind(source_list_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    if count == 0 {
+        return Ok(());
+    }
+
+    wip::check_wip_limit(&mut *conn, target_list_id, i64::from(count)).await?;
+
+    let (first_number, prefix) =
+        card_keys::allocate_card_numbers(&mut *conn, target_board_id, count).await?;
+
+    let (single_lane, source_lanes, target_lanes): (Option<i32>, Vec<i32>, Vec<i32>) = match lanes {
+        LaneMapping::Keep => (None, Vec::new(), Vec::new()),
+        LaneMapping::Single(lane_id) => (Some(lane_id), Vec::new(), Vec::new()),
+        LaneMapping::Remap(pairs) => (
+            None,
+            pairs.iter().map(|(source, _)| *source).collect(),
+            pairs.iter().map(|(_, target)| *target).collect(),
+        ),
+    };
+
+    let copies: Vec<(i32, Option<String>)> = sqlx::query_as(
+        "WITH mapped AS (
+             SELECT c.*,
+                    COALESCE(
+                        $5,
+                        (SELECT m.target FROM unnest($6::INT[], $7::INT[]) AS m(source, target)
+                         WHERE m.source = c.lane_id),
+                        c.lane_id
+                    ) AS target_lane_id
+             FROM card c
+             WHERE c.list_id = $1
+         ),
+         ordered AS (
+             SELECT *,
+                    ROW_NUMBER() OVER (ORDER BY card_number ASC, id ASC) - 1 AS idx,
+                    ROW_NUMBER() OVER (PARTITION BY target_lane_id ORDER BY rank ASC, id ASC) AS cell_index
+             FROM mapped
+         )
+         INSERT INTO card (card_number, card_key, title, description, list_id, lane_id, rank,
+                           assignee_id, due_date, labels, priority, estimate, estimate_unit,
+                           started_at, completed_at)
+         SELECT $3 + idx, $4 || '-' || ($3 + idx), title, description, $2, target_lane_id,
+                ($8::TEXT[])[cell_index], assignee_id, due_date, labels, priority, estimate,
+                estimate_unit, started_at, completed_at
+         FROM ordered
+         RETURNING id, description"
+    )
+    .bind(source_list_id)
+    .bind(target_list_id)
+    .bind(first_number)
+    .bind(&prefix)
+    .bind(single_lane)
+    .bind(&source_lanes)
+    .bind(&target_lanes)
+    .bind(ranks::spread_ranks(count as usize))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (card_id, description) in copies {
+        if let Some(description) = description {
+            mentions::sync_mentions(&mut *conn, card_id, target_list_id, None, &description).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy the comments of a card onto its copy, keeping their authors and
+/// times, with the mentions they make on the copy's board
+async fn copy_comments(conn: &mut PgConnection, source_card_id: i32, card: &Card) -> Result<(), AppError> {
+    let comments: Vec<(i32, String)> = sqlx::query_as(
+        "INSERT INTO card_comment (card_id, author_id, body, created_at, updated_at)
+         SELECT $2, author_id, body, created_at, updated_at
+         FROM card_comment
+         WHERE card_id = $1
+         ORDER BY created_at ASC, id ASC
+         RETURNING id, body"
+    )
+    .bind(source_card_id)
+    .bind(card.id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (comment_id, body) in comments {
+        mentions::sync_mentions(&mut *conn, card.id, card.list_id, Some(comment_id), &body).await?;
+    }
+
+    Ok(())
+}
+
+/// Copy a card to any list and position. Returns a warning when the copy
+/// exceeds an advisory WIP limit. Mentions carry over to the copy, as far as
+/// the target board's members allow, without notifying anyone again.
+pub async fn copy_card(
+    pool: &DbPool,
+    id: i32,
+    req: CopyCardRequest,
+) -> Result<(Card, Option<String>), AppError> {
+    validate_copy_title(req.title.as_deref())?;
+
+    let source = get_card_by_id(pool, id).await?;
+    let list_id = req.list_id.unwrap_or(source.list_id);
+    let title = req.title.unwrap_or(source.title);
+    let labels = if req.keep_labels { source.labels } else { Vec::new() };
+
+    let mut tx = pool.begin().await?;
+
+    let board_id = card_keys::get_board_id_for_list(&mut tx, list_id).await?;
+    let source_board_id = card_keys::get_board_id_for_list(&mut tx, source.list_id).await?;
+    let lane_id = if board_id == source_board_id {
+        source.lane_id
+    } else {
+        lanes::get_default_lane_id(&mut tx, board_id).await?
+    };
+    let wip_warning = wip::check_wip_limit(&mut tx, list_id, 1).await?;
+    let category = get_column_category(&mut tx, list_id).await?;
+    let (started_at, completed_at) = stamps_on_enter(category, (None, None), Utc::now());
+    let rank = ranks::card_rank_at(&mut tx, list_id, lane_id, req.position, None).await?;
+    let (card_number, card_key) = card_keys::allocate_card_key(&mut tx, board_id).await?;
+
+    let mut card = sqlx::query_as::<_, Card>(&format!(
+        "INSERT INTO card (card_number, card_key, title, description, list_id, lane_id, rank,
+                           assignee_id, due_date, labels, priority, estimate, estimate_unit,
+                           started_at, completed_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+         RETURNING {CARD_COLUMNS}"
+    ))
+    .bind(card_number)
+    .bind(&card_key)
+    .bind(&title)
+    .bind(&source.description)
+    .bind(list_id)
+    .bind(lane_id)
+    .bind(&rank)
+    .bind(&source.assignee_id)
+    .bind(source.due_date)
+    .bind(&labels)
+    .bind(source.priority)
+    .bind(source.estimate)
+    .bind(source.estimate_unit)
+    .bind(started_at)
+    .bind(completed_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if let Some(description) = card.description.as_deref() {
+        let mentioned = mentions::sync_mentions(&mut tx, card.id, card.list_id, None, description).await?;
+        card.mentions = Json(mentioned);
+    }
+    if req.keep_comments {
+        copy_comments(&mut tx, source.id, &card).await?;
+    }
+
+    let created = RuleTrigger::CardCreated { list_id: Some(card.list_id) };
+    let card = automation::apply_rules_to_card(&mut tx, card, vec![created]).await?;
+    webhooks::emit_card(&mut tx, WebhookEvent::CardCreated, &card).await?;
+
+    tx.commit().await?;
+
+    Ok((card, wip_warning))
+}
+
+/// Copy a column with all its cards into the same or another board
+pub async fn copy_column(pool: &DbPool, id: i32, req: CopyColumnRequest) -> Result<BoardColumn, AppError> {
+    validate_copy_title(req.title.as_deref())?;
+
+    let source = get_column_by_id(pool, id).await?;
+    let board_id = req.board_id.unwrap_or(source.board_id);
+    let title = req.title.unwrap_or(source.title);
+
+    let mut tx = pool.begin().await?;
+
+    let board_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM board WHERE id = $1)"
+    )
+    .bind(board_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if !board_exists {
+        return Err(AppError::NotFound(format!("Board with id {} not found", board_id)));
+    }
+
+    let rank = ranks::column_rank_at(&mut tx, board_id, req.position, None).await?;
+
+    let column = sqlx::query_as::<_, BoardColumn>(&format!(
+        "INSERT INTO board_column (title, board_id, rank, category, wip_limit, wip_mode)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING {COLUMN_COLUMNS}"
+    ))
+    .bind(&title)
+    .bind(board_id)
+    .bind(&rank)
+    .bind(source.category)
+    .bind(source.wip_limit)
+    .bind(source.wip_mode)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let lane_mapping = if board_id == source.board_id {
+        LaneMapping::Keep
+    } else {
+        LaneMapping::Single(lanes::get_default_lane_id(&mut tx, board_id).await?)
+    };
+    copy_cards_between_lists(&mut tx, source.id, column.id, board_id, lane_mapping).await?;
+    webhooks::emit(&mut tx, board_id, WebhookEvent::ColumnCreated, &column).await?;
+
+    tx.commit().await?;
+
+    Ok(column)
+}
+
+/// Copy the automation rules of a board onto its copy, with their lists
+/// mapped through `column_ids`
+async fn copy_rules(
+    conn: &mut PgConnection,
+    source_board_id: i32,
+    board_id: i32,
+    column_ids: &HashMap<i32, i32>,
+) -> Result<(), AppError> {
+    let rules = sqlx::query_as::<_, AutomationRule>(&format!(
+        "SELECT {RULE_COLUMNS} FROM automation_rule WHERE board_id = $1 ORDER BY id ASC"
+    ))
+    .bind(source_board_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for rule in rules {
+        let Some((trigger, actions)) = automation::remap_lists(&rule.trigger, &rule.actions, column_ids) else {
+            continue;
+        };
+        sqlx::query(
+            "INSERT INTO automation_rule (board_id, name, enabled, trigger, conditions, actions)
+             VALUES ($1, $2, $3, $4, $5, $6)"
+        )
+        .bind(board_id)
+        .bind(&rule.name)
+        .bind(rule.enabled)
+        .bind(Json(&trigger))
+        .bind(&rule.conditions)
+        .bind(Json(&actions))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Deep-copy a board with its lanes, columns, cards and automation rules in
+/// one transaction. Rules refer to the copied lists; a rule referring to a
+/// list no longer on the board is left out.
+pub async fn copy_board(pool: &DbPool, id: i32, req: CopyBoardRequest) -> Result<Board, AppError> {
+    validate_copy_title(req.title.as_deref())?;
+
+    let source = get_board_by_id(pool, id).await?;
+    let title = req.title.unwrap_or_else(|| format!("{} (copy)", source.title));
+    let key_prefix =
+        card_keys::choose_key_prefix(pool, req.key_prefix.as_deref(), &title).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let board = sqlx::query_as::<_, Board>(&format!(
+        "INSERT INTO board (title, description, key_prefix, owner_id, members)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING {BOARD_COLUMNS}"
+    ))
+    .bind(&title)
+    .bind(&source.description)
+    .bind(&key_prefix)
+    .bind(&source.owner_id)
+    .bind(&source.members)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| card_keys::key_prefix_conflict(err, &key_prefix))?;
+
+    let mut lane_pairs = Vec::new();
+    for source_lane in lanes::get_lanes_for_board(&mut tx, source.id).await? {
+        let lane_id: i32 = sqlx::query_scalar(
+            "INSERT INTO swimlane (board_id, title, position, is_default)
+             VALUES ($1, $2, $3, $4)
+             RETURNING id"
+        )
+        .bind(board.id)
+        .bind(&source_lane.title)
+        .bind(source_lane.position)
+        .bind(source_lane.is_default)
+        .fetch_one(&mut *tx)
+        .await?;
+        lane_pairs.push((source_lane.id, lane_id));
+    }
+
+    let source_columns = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS}
+         FROM board_column
+         WHERE board_id = $1
+         ORDER BY rank ASC"
+    ))
+    .bind(source.id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut column_ids = HashMap::new();
+    let column_ranks = ranks::spread_ranks(source_columns.len());
+    for (source_column, rank) in source_columns.iter().zip(&column_ranks) {
+        let column_id: i32 = sqlx::query_scalar(
+            "INSERT INTO board_column (title, board_id, rank, category, wip_limit, wip_mode)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id"
+        )
+        .bind(&source_column.title)
+        .bind(board.id)
+        .bind(rank)
+        .bind(source_column.category)
+        .bind(source_column.wip_limit)
+        .bind(source_column.wip_mode)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        copy_cards_between_lists(
+            &mut tx,
+            source_column.id,
+            column_id,
+            board.id,
+            LaneMapping::Remap(&lane_pairs),
+        )
+        .await?;
+        column_ids.insert(source_column.id, column_id);
+    }
+
+    copy_rules(&mut tx, source.id, board.id, &column_ids).await?;
+
+    tx.commit().await?;
+
+    Ok(board)
+}
@@ -0,0 +1,678 @@
+//! Board export and import.
+//!
+//! An export is a versioned JSON document holding a board with its lanes,
+//! lists, cards, comments, time entries and automation rules. Importing one recreates the
+//! board in one transaction with fresh IDs; cards keep their numbers under
+//! the new board's key prefix, and timestamps are kept.
+//!
+//! Users are only known by ID. An exported user is replaced through the
+//! request's `userMap`, or kept when they have a profile or are on a board
+//! here; anyone else is reported as unknown and loses what the board would
+//! have given them: the importing user owns the board in place of an unknown
+//! owner, and unknown members and assignees are dropped. Comments and time
+//! entries keep their authors, known or not.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::automation::{self, RULE_COLUMNS};
+use crate::handlers::boards::BOARD_COLUMNS;
+use crate::handlers::cards::CARD_COLUMNS;
+use crate::handlers::columns::COLUMN_COLUMNS;
+use crate::handlers::comments::COMMENT_COLUMNS;
+use crate::handlers::time_entries::TIME_ENTRY_COLUMNS;
+use crate::handlers::{card_keys, lanes, mentions, ranks};
+use crate::models::{
+    AutomationRule, Board, BoardColumn, BoardExport, BoardImportResult, Card, CardComment, ImportBoardRequest,
+    ImportedIds, TimeEntry, BOARD_EXPORT_VERSION,
+};
+
+/// Highest card number an import accepts, leaving room for the board's
+/// next cards
+const MAX_CARD_NUMBER: i32 = 1_000_000_000;
+
+/// Export a board with everything on it, read from one snapshot
+pub async fn export_board(pool: &DbPool, id: i32) -> Result<BoardExport, AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    let board = sqlx::query_as::<_, Board>(&format!("SELECT {BOARD_COLUMNS} FROM board WHERE id = $1"))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Board with id {} not found", id)))?;
+
+    let lanes = lanes::get_lanes_for_board(&mut tx, id).await?;
+
+    let columns = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS} FROM board_column WHERE board_id = $1 ORDER BY rank ASC"
+    ))
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let cards = sqlx::query_as::<_, Card>(&format!(
+        "SELECT {CARD_COLUMNS} FROM card
+         WHERE list_id IN (SELECT id FROM board_column WHERE board_id = $1)
+         ORDER BY card_number ASC"
+    ))
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let comments = sqlx::query_as::<_, CardComment>(&format!(
+        "SELECT {COMMENT_COLUMNS} FROM card_comment
+         WHERE card_id IN (SELECT c.id FROM card c JOIN board_column bc ON bc.id = c.list_id WHERE bc.board_id = $1)
+         ORDER BY id ASC"
+    ))
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let time_entries = sqlx::query_as::<_, TimeEntry>(&format!(
+        "SELECT {TIME_ENTRY_COLUMNS} FROM time_entry
+         WHERE card_id IN (SELECT c.id FROM card c JOIN board_column bc ON bc.id = c.list_id WHERE bc.board_id = $1)
+         ORDER BY id ASC"
+    ))
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let rules = sqlx::query_as::<_, AutomationRule>(&format!(
+        "SELECT {RULE_COLUMNS} FROM automation_rule WHERE board_id = $1 ORDER BY id ASC"
+    ))
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(BoardExport {
+        version: BOARD_EXPORT_VERSION,
+        exported_at: Utc::now(),
+        board,
+        lanes,
+        columns,
+        cards,
+        comments,
+        time_entries,
+        rules,
+    })
+}
+
+/// Check the document's version before reading it as an export
+fn parse_export(document: Value) -> Result<BoardExport, AppError> {
+    let version = document
+        .get("version")
+        .and_then(Value::as_i64)
+        .ok_or_else(|| AppError::ValidationError("The document has no export version".to_string()))?;
+    if version != i64::from(BOARD_EXPORT_VERSION) {
+        return Err(AppError::ValidationError(format!(
+            "Unsupported export version {}; this server reads version {}",
+            version, BOARD_EXPORT_VERSION
+        )));
+    }
+
+    serde_json::from_value(document).map_err(|err| AppError::ValidationError(format!("Invalid export: {}", err)))
+}
+
+fn invalid_export(message: String) -> AppError {
+    AppError::ValidationError(format!("Invalid export: {}", message))
+}
+
+fn unique_ids(ids: impl Iterator<Item = i32>, entity: &str) -> Result<HashSet<i32>, AppError> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            return Err(invalid_export(format!("duplicate {} {}", entity, id)));
+        }
+    }
+    Ok(seen)
+}
+
+/// Check that the export is consistent: IDs are unique and lists, cards and
+/// comments refer to entities in the document
+fn validate_export(export: &BoardExport) -> Result<(), AppError> {
+    if export.board.title.trim().is_empty() {
+        return Err(invalid_export("the board has no title".to_string()));
+    }
+
+    unique_ids(export.lanes.iter().map(|lane| lane.id), "lane id")?;
+    if export.lanes.iter().filter(|lane| lane.is_default).count() != 1 {
+        return Err(invalid_export("the board must have exactly one default lane".to_string()));
+    }
+
+    let columns = unique_ids(export.columns.iter().map(|column| column.id), "list id")?;
+    let cards = unique_ids(export.cards.iter().map(|card| card.id), "card id")?;
+    unique_ids(export.cards.iter().map(|card| card.card_number), "card number")?;
+    for card in &export.cards {
+        if !columns.contains(&card.list_id) {
+            return Err(invalid_export(format!("card {} is in unknown list {}", card.id, card.list_id)));
+        }
+        if !(1..=MAX_CARD_NUMBER).contains(&card.card_number) {
+            return Err(invalid_export(format!("card {} has number {}", card.id, card.card_number)));
+        }
+    }
+
+    unique_ids(export.comments.iter().map(|comment| comment.id), "comment id")?;
+    for comment in &export.comments {
+        if !cards.contains(&comment.card_id) {
+            return Err(invalid_export(format!("comment {} is on unknown card {}", comment.id, comment.card_id)));
+        }
+    }
+    for entry in &export.time_entries {
+        if !cards.contains(&entry.card_id) {
+            return Err(invalid_export(format!("time entry {} is on unknown card {}", entry.id, entry.card_id)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Exported user IDs and the users they become here; None for unknown users
+struct UserMapping(BTreeMap<String, Option<String>>);
+
+impl UserMapping {
+    async fn resolve(
+        pool: &DbPool,
+        export: &BoardExport,
+        importer: &str,
+        user_map: HashMap<String, String>,
+    ) -> Result<Self, AppError> {
+        let mut exported: BTreeSet<&str> = export.board.members.iter().map(String::as_str).collect();
+        exported.extend(export.board.owner_id.as_deref());
+        exported.extend(export.cards.iter().filter_map(|card| card.assignee_id.as_deref()));
+        exported.extend(export.comments.iter().map(|comment| comment.author_id.as_str()));
+        exported.extend(export.time_entries.iter().map(|entry| entry.user_id.as_str()));
+
+        let unmapped: Vec<&str> = exported.iter().copied().filter(|id| !user_map.contains_key(*id)).collect();
+        let known: HashSet<String> = sqlx::query_scalar(
+            "SELECT u FROM unnest($1::TEXT[]) AS u
+             WHERE u = $2
+                OR EXISTS (SELECT 1 FROM user_profile WHERE user_id = u)
+                OR EXISTS (SELECT 1 FROM board WHERE owner_id = u OR u = ANY(members))"
+        )
+        .bind(&unmapped)
+        .bind(importer)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        let users = exported
+            .into_iter()
+            .map(|id| {
+                let target = match user_map.get(id) {
+                    Some(target) => Some(target.clone()),
+                    None => known.contains(id).then(|| id.to_string()),
+                };
+                (id.to_string(), target)
+            })
+            .collect();
+
+        Ok(Self(users))
+    }
+
+    /// The user an exported user becomes, if known
+    fn get(&self, id: &str) -> Option<&str> {
+        self.0.get(id).and_then(|target| target.as_deref())
+    }
+
+    /// The user an exported author becomes; unknown authors are kept
+    fn author<'a>(&'a self, id: &'a str) -> &'a str {
+        self.get(id).unwrap_or(id)
+    }
+
+    fn unknown(&self) -> Vec<String> {
+        self.0.iter().filter(|(_, target)| target.is_none()).map(|(id, _)| id.clone()).collect()
+    }
+}
+
+/// Choose the key prefix of an imported board: the requested one, else the
+/// exported one, else one derived from the title
+async fn import_key_prefix(
+    pool: &DbPool,
+    requested: Option<&str>,
+    exported: &str,
+    title: &str,
+    warnings: &mut Vec<String>,
+) -> Result<String, AppError> {
+    if requested.is_some() {
+        return card_keys::choose_key_prefix(pool, requested, title).await;
+    }
+    match card_keys::choose_key_prefix(pool, Some(exported), title).await {
+        Ok(prefix) => Ok(prefix),
+        Err(AppError::Conflict(_) | AppError::ValidationError(_)) => {
+            let prefix = card_keys::choose_key_prefix(pool, None, title).await?;
+            warnings.push(format!("Key prefix {} is in use; cards have keys starting with {}", exported, prefix));
+            Ok(prefix)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Import an exported board as a new board, in one transaction
+pub async fn import_board(pool: &DbPool, req: ImportBoardRequest) -> Result<BoardImportResult, AppError> {
+    if req.user_id.trim().is_empty() {
+        return Err(AppError::ValidationError("userId is required".to_string()));
+    }
+    let export = parse_export(req.document)?;
+    validate_export(&export)?;
+
+    let title = req.title.unwrap_or_else(|| export.board.title.clone());
+    if title.trim().is_empty() {
+        return Err(AppError::ValidationError("Title cannot be empty".to_string()));
+    }
+
+    let mut warnings = Vec::new();
+    let key_prefix =
+        import_key_prefix(pool, req.key_prefix.as_deref(), &export.board.key_prefix, &title, &mut warnings).await?;
+    let users = UserMapping::resolve(pool, &export, &req.user_id, req.user_map).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let owner_id = export.board.owner_id.as_deref().map(|owner| match users.get(owner) {
+        Some(owner) => owner.to_string(),
+        None => {
+            warnings.push(format!("Owner {} is unknown; the board is owned by {}", owner, req.user_id));
+            req.user_id.clone()
+        }
+    });
+    let mut members: Vec<String> = Vec::new();
+    for member in &export.board.members {
+        match users.get(member) {
+            Some(member) if !members.iter().any(|m| m == member) => members.push(member.to_string()),
+            Some(_) => {}
+            None => warnings.push(format!("Member {} is unknown and was left out", member)),
+        }
+    }
+    let next_card_number = export
+        .cards
+        .iter()
+        .map(|card| card.card_number)
+        .max()
+        .unwrap_or(0)
+        .checked_add(1)
+        .ok_or_else(|| invalid_export("card numbers are out of range".to_string()))?;
+
+    let board = sqlx::query_as::<_, Board>(&format!(
+        "INSERT INTO board (title, description, key_prefix, owner_id, members, next_card_number,
+                            created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING {BOARD_COLUMNS}"
+    ))
+    .bind(&title)
+    .bind(&export.board.description)
+    .bind(&key_prefix)
+    .bind(&owner_id)
+    .bind(&members)
+    .bind(next_card_number)
+    .bind(export.board.created_at)
+    .bind(export.board.updated_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| card_keys::key_prefix_conflict(err, &key_prefix))?;
+
+    let mut ids = ImportedIds::default();
+    import_structure(&mut tx, board.id, &export, &mut ids).await?;
+    import_cards(&mut tx, &key_prefix, &export, &users, &mut ids, &mut warnings).await?;
+    import_activity(&mut tx, &export, &users, &mut ids, &mut warnings).await?;
+    import_rules(&mut tx, board.id, &export, &mut ids, &mut warnings).await?;
+
+    tx.commit().await?;
+
+    Ok(BoardImportResult { board, ids, unknown_users: users.unknown(), warnings })
+}
+
+/// Recreate the lanes and lists, in their exported order
+async fn import_structure(
+    conn: &mut PgConnection,
+    board_id: i32,
+    export: &BoardExport,
+    ids: &mut ImportedIds,
+) -> Result<(), AppError> {
+    let mut lanes: Vec<_> = export.lanes.iter().collect();
+    lanes.sort_by_key(|lane| (lane.position, lane.id));
+    for (position, lane) in lanes.into_iter().enumerate() {
+        let id = sqlx::query_scalar(
+            "INSERT INTO swimlane (board_id, title, position, is_default, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id"
+        )
+        .bind(board_id)
+        .bind(&lane.title)
+        .bind(position as i32)
+        .bind(lane.is_default)
+        .bind(lane.created_at)
+        .bind(lane.updated_at)
+        .fetch_one(&mut *conn)
+        .await?;
+        ids.lanes.insert(lane.id, id);
+    }
+
+    let mut columns: Vec<_> = export.columns.iter().collect();
+    columns.sort_by_key(|column| (column.position, column.id));
+    let column_ranks = ranks::spread_ranks(columns.len());
+    for (column, rank) in columns.into_iter().zip(&column_ranks) {
+        let id = sqlx::query_scalar(
+            "INSERT INTO board_column (title, board_id, rank, category, wip_limit, wip_mode, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             RETURNING id"
+        )
+        .bind(&column.title)
+        .bind(board_id)
+        .bind(rank)
+        .bind(column.category)
+        .bind(column.wip_limit)
+        .bind(column.wip_mode)
+        .bind(column.created_at)
+        .bind(column.updated_at)
+        .fetch_one(&mut *conn)
+        .await?;
+        ids.columns.insert(column.id, id);
+    }
+
+    Ok(())
+}
+
+/// Recreate the cards in their exported cells and order, keeping their
+/// numbers. Mentions in descriptions are resolved without notifying anyone.
+async fn import_cards(
+    conn: &mut PgConnection,
+    key_prefix: &str,
+    export: &BoardExport,
+    users: &UserMapping,
+    ids: &mut ImportedIds,
+    warnings: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let default_lane = export.lanes.iter().find(|lane| lane.is_default).map(|lane| ids.lanes[&lane.id]);
+
+    let mut placed = Vec::with_capacity(export.cards.len());
+    for card in &export.cards {
+        let list_id = ids.columns[&card.list_id];
+        let lane_id = match ids.lanes.get(&card.lane_id) {
+            Some(lane_id) => *lane_id,
+            None => {
+                warnings.push(format!("Card {} was in unknown lane {}; it is in the default lane", card.card_key, card.lane_id));
+                default_lane.ok_or_else(|| invalid_export("the board has no default lane".to_string()))?
+            }
+        };
+        placed.push((list_id, lane_id, card));
+    }
+    placed.sort_by_key(|(list_id, lane_id, card)| (*list_id, *lane_id, card.position, card.card_number));
+
+    let mut index = 0;
+    while index < placed.len() {
+        let cell = (placed[index].0, placed[index].1);
+        let cell_len = placed[index..].iter().take_while(|(list_id, lane_id, _)| (*list_id, *lane_id) == cell).count();
+        let cell_ranks = ranks::spread_ranks(cell_len);
+
+        for ((list_id, lane_id, card), rank) in placed[index..index + cell_len].iter().zip(&cell_ranks) {
+            let assignee_id = card.assignee_id.as_deref().and_then(|assignee| {
+                let target = users.get(assignee);
+                if target.is_none() {
+                    warnings.push(format!("Card {} was assigned to unknown user {}; it is unassigned", card.card_key, assignee));
+                }
+                target
+            });
+
+            let id: i32 = sqlx::query_scalar(
+                "INSERT INTO card (card_number, card_key, title, description, list_id, lane_id, rank,
+                                   assignee_id, due_date, labels, priority, estimate, estimate_unit,
+                                   started_at, completed_at, created_at, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+                 RETURNING id"
+            )
+            .bind(card.card_number)
+            .bind(format!("{}-{}", key_prefix, card.card_number))
+            .bind(&card.title)
+            .bind(&card.description)
+            .bind(list_id)
+            .bind(lane_id)
+            .bind(rank)
+            .bind(assignee_id)
+            .bind(card.due_date)
+            .bind(&card.labels)
+            .bind(card.priority)
+            .bind(card.estimate)
+            .bind(card.estimate_unit)
+            .bind(card.started_at)
+            .bind(card.completed_at)
+            .bind(card.created_at)
+            .bind(card.updated_at)
+            .fetch_one(&mut *conn)
+            .await?;
+            ids.cards.insert(card.id, id);
+
+            if let Some(description) = card.description.as_deref() {
+                mentions::sync_mentions(&mut *conn, id, *list_id, None, description).await?;
+            }
+        }
+
+        index += cell_len;
+    }
+
+    Ok(())
+}
+
+/// Recreate the comments and finished time entries. Running timers are
+/// left out.
+async fn import_activity(
+    conn: &mut PgConnection,
+    export: &BoardExport,
+    users: &UserMapping,
+    ids: &mut ImportedIds,
+    warnings: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let list_ids: HashMap<i32, i32> = export.cards.iter().map(|card| (card.id, ids.columns[&card.list_id])).collect();
+
+    let mut comments: Vec<_> = export.comments.iter().collect();
+    comments.sort_by_key(|comment| (comment.created_at, comment.id));
+    for comment in comments {
+        let card_id = ids.cards[&comment.card_id];
+        let id = sqlx::query_scalar(
+            "INSERT INTO card_comment (card_id, author_id, body, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id"
+        )
+        .bind(card_id)
+        .bind(users.author(&comment.author_id))
+        .bind(&comment.body)
+        .bind(comment.created_at)
+        .bind(comment.updated_at)
+        .fetch_one(&mut *conn)
+        .await?;
+        ids.comments.insert(comment.id, id);
+
+        mentions::sync_mentions(&mut *conn, card_id, list_ids[&comment.card_id], Some(id), &comment.body).await?;
+    }
+
+    for entry in &export.time_entries {
+        if entry.ended_at.is_none() {
+            warnings.push(format!("The running timer of {} on card {} was left out", entry.user_id, entry.card_id));
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO time_entry (card_id, user_id, started_at, ended_at, duration_seconds, note, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        )
+        .bind(ids.cards[&entry.card_id])
+        .bind(users.author(&entry.user_id))
+        .bind(entry.started_at)
+        .bind(entry.ended_at)
+        .bind(entry.duration_seconds)
+        .bind(&entry.note)
+        .bind(entry.created_at)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Recreate the automation rules on the imported lists. Rules referring to
+/// lists not in the document are left out.
+async fn import_rules(
+    conn: &mut PgConnection,
+    board_id: i32,
+    export: &BoardExport,
+    ids: &mut ImportedIds,
+    warnings: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let columns: HashMap<i32, i32> = ids.columns.iter().map(|(exported, id)| (*exported, *id)).collect();
+
+    for rule in &export.rules {
+        let Some((trigger, actions)) = automation::remap_lists(&rule.trigger, &rule.actions, &columns) else {
+            warnings.push(format!("Rule \"{}\" refers to a list not in the export and was left out", rule.name));
+            continue;
+        };
+        let id = sqlx::query_scalar(
+            "INSERT INTO automation_rule (board_id, name, enabled, trigger, conditions, actions, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             RETURNING id"
+        )
+        .bind(board_id)
+        .bind(&rule.name)
+        .bind(rule.enabled)
+        .bind(Json(&trigger))
+        .bind(&rule.conditions)
+        .bind(Json(&actions))
+        .bind(rule.created_at)
+        .bind(rule.updated_at)
+        .fetch_one(&mut *conn)
+        .await?;
+        ids.rules.insert(rule.id, id);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "version": 1,
+            "exportedAt": "2024-03-01T12:00:00Z",
+            "board": {
+                "id": 5, "title": "Launch", "keyPrefix": "LAU", "members": ["bob"],
+                "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z"
+            },
+            "lanes": [
+                { "id": 8, "boardId": 5, "title": "Default", "position": 0, "isDefault": true,
+                  "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z" }
+            ],
+            "columns": [
+                { "id": 13, "title": "Todo", "boardId": 5, "position": 0, "category": "todo",
+                  "wipMode": "advisory", "cardCount": 1,
+                  "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z" },
+                { "id": 14, "title": "Done", "boardId": 5, "position": 1, "category": "done",
+                  "wipMode": "advisory", "cardCount": 1,
+                  "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z" }
+            ],
+            "cards": [
+                { "id": 24, "number": 1, "key": "LAU-1", "title": "Plan", "mentions": [], "listId": 13,
+                  "laneId": 8, "position": 0, "labels": [], "trackedSeconds": 0,
+                  "createdAt": "2024-01-02T00:00:00Z", "updatedAt": "2024-01-02T00:00:00Z" },
+                { "id": 25, "number": 2, "key": "LAU-2", "title": "Ship", "mentions": [], "listId": 14,
+                  "laneId": 8, "position": 0, "labels": ["release"], "trackedSeconds": 0,
+                  "createdAt": "2024-01-03T00:00:00Z", "updatedAt": "2024-01-03T00:00:00Z" }
+            ],
+            "comments": [
+                { "id": 1, "cardId": 25, "authorId": "carol", "body": "Done", "mentions": [],
+                  "createdAt": "2024-01-04T00:00:00Z", "updatedAt": "2024-01-04T00:00:00Z" }
+            ],
+            "timeEntries": [
+                { "id": 3, "cardId": 24, "userId": "bob", "startedAt": "2024-01-02T09:00:00Z",
+                  "endedAt": "2024-01-02T10:00:00Z", "durationSeconds": 3600,
+                  "createdAt": "2024-01-02T10:00:00Z" }
+            ]
+        })
+    }
+
+    fn validate(document: Value) -> Result<(), String> {
+        let export = parse_export(document).map_err(failure)?;
+        validate_export(&export).map_err(failure)
+    }
+
+    fn failure(err: AppError) -> String {
+        match err {
+            AppError::ValidationError(message) => message,
+            err => panic!("expected a validation error, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn accepts_a_consistent_export() {
+        assert_eq!(validate(document()), Ok(()));
+    }
+
+    #[test]
+    fn checks_the_version_first() {
+        let mut doc = document();
+        doc["version"] = json!(2);
+        assert!(validate(doc).unwrap_err().starts_with("Unsupported export version 2"));
+
+        let mut doc = document();
+        doc.as_object_mut().unwrap().remove("version");
+        assert_eq!(validate(doc).unwrap_err(), "The document has no export version");
+
+        assert!(validate(json!({ "version": 1 })).unwrap_err().starts_with("Invalid export: missing field"));
+    }
+
+    #[test]
+    fn rejects_dangling_references() {
+        let mut doc = document();
+        doc["cards"][0]["listId"] = json!(99);
+        assert_eq!(validate(doc).unwrap_err(), "Invalid export: card 24 is in unknown list 99");
+
+        let mut doc = document();
+        doc["comments"][0]["cardId"] = json!(99);
+        assert_eq!(validate(doc).unwrap_err(), "Invalid export: comment 1 is on unknown card 99");
+
+        let mut doc = document();
+        doc["timeEntries"][0]["cardId"] = json!(99);
+        assert_eq!(validate(doc).unwrap_err(), "Invalid export: time entry 3 is on unknown card 99");
+    }
+
+    #[test]
+    fn rejects_duplicates() {
+        let mut doc = document();
+        doc["cards"][1]["id"] = json!(24);
+        assert_eq!(validate(doc).unwrap_err(), "Invalid export: duplicate card id 24");
+
+        let mut doc = document();
+        doc["cards"][1]["number"] = json!(1);
+        assert_eq!(validate(doc).unwrap_err(), "Invalid export: duplicate card number 1");
+    }
+
+    #[test]
+    fn needs_one_default_lane() {
+        let mut doc = document();
+        doc["lanes"][0]["isDefault"] = json!(false);
+        assert!(validate(doc).unwrap_err().contains("exactly one default lane"));
+    }
+
+    #[test]
+    fn bounds_card_numbers() {
+        for number in [0, -1, MAX_CARD_NUMBER + 1, i32::MAX] {
+            let mut doc = document();
+            doc["cards"][0]["number"] = json!(number);
+            assert_eq!(validate(doc).unwrap_err(), format!("Invalid export: card 24 has number {}", number));
+        }
+
+        let mut doc = document();
+        doc["cards"][0]["number"] = json!(MAX_CARD_NUMBER);
+        assert_eq!(validate(doc), Ok(()));
+    }
+}
@@ -0,0 +1,539 @@
+//! Inbound git push webhook.
+//!
+//! GitHub, GitLab and Gitea push events are authenticated with the shared
+//! secret in `GIT_WEBHOOK_SECRET`. Every card key in a commit message links
+//! the commit to the card and comments on it. A key that follows a closing
+//! keyword ("fixes OPS-42") also moves the card to its board's done list
+//! through the regular card update, once the commit is pushed to the
+//! default branch; a commit first pushed to another branch closes its cards
+//! when it is merged.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::card_keys::{self, resolve_card_ref};
+use crate::handlers::card_lifecycle::get_column_category;
+use crate::handlers::cards::{get_card_by_id, update_card_in, CARD_COLUMNS};
+use crate::handlers::comments::create_comment_in;
+use crate::models::{
+    Card, CardCommit, ColumnCategory, CreateCommentRequest, GitCardLink, GitCommit, GitPushPayload, GitPushResult,
+    UpdateCardRequest,
+};
+
+const COMMIT_COLUMNS: &str = "card_id, sha, repository, branch, message, url, author_name, author_username, \
+                              committed_at, closes, closed_at, created_at";
+
+/// Words that make the card keys after them close the card
+const CLOSING_KEYWORDS: &[&str] = &["close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved"];
+
+/// Length of the abbreviated SHA in comments
+const SHORT_SHA_LEN: usize = 8;
+
+/// Credentials sent with a push; each provider uses one of them
+#[derive(Debug, Default)]
+pub struct PushCredentials {
+    /// GitHub `X-Hub-Signature-256`: `sha256=` and the hex HMAC of the body
+    pub hub_signature: Option<String>,
+    /// Gitea `X-Gitea-Signature`: the hex HMAC of the body
+    pub gitea_signature: Option<String>,
+    /// GitLab `X-Gitlab-Token`: the secret itself
+    pub gitlab_token: Option<String>,
+}
+
+fn verify_hmac(secret: &str, body: &[u8], hex_signature: &str) -> bool {
+    let Ok(signature) = hex::decode(hex_signature) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Compare without leaking where the strings differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn authenticate(secret: &str, credentials: &PushCredentials, body: &[u8]) -> Result<(), AppError> {
+    let valid = if let Some(signature) = &credentials.hub_signature {
+        signature
+            .strip_prefix("sha256=")
+            .is_some_and(|signature| verify_hmac(secret, body, signature))
+    } else if let Some(signature) = &credentials.gitea_signature {
+        verify_hmac(secret, body, signature)
+    } else if let Some(token) = &credentials.gitlab_token {
+        constant_time_eq(token.as_bytes(), secret.as_bytes())
+    } else {
+        false
+    };
+
+    if !valid {
+        return Err(AppError::Unauthorized("Invalid or missing webhook signature".to_string()));
+    }
+    Ok(())
+}
+
+/// A card key such as `OPS-42`: a valid key prefix, a dash and a number
+fn as_card_key(word: &str) -> Option<&str> {
+    let (prefix, number) = word.rsplit_once('-')?;
+    let is_number = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+    (is_number && card_keys::validate_key_prefix(prefix).is_ok()).then_some(word)
+}
+
+/// Card keys in a commit message, in order of appearance, each with whether
+/// the message closes it. Keys close when they follow a closing keyword,
+/// also in a list: "fixes OPS-1, OPS-2 and OPS-3".
+pub fn parse_card_refs(message: &str) -> Vec<(String, bool)> {
+    let mut refs: Vec<(String, bool)> = Vec::new();
+    let mut closing = false;
+
+    for word in message.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-')) {
+        if word.is_empty() {
+            continue;
+        }
+        if let Some(key) = as_card_key(word) {
+            match refs.iter_mut().find(|(existing, _)| existing == key) {
+                Some((_, closes)) => *closes |= closing,
+                None => refs.push((key.to_string(), closing)),
+            }
+        } else if !word.eq_ignore_ascii_case("and") {
+            closing = CLOSING_KEYWORDS.iter().any(|keyword| word.eq_ignore_ascii_case(keyword));
+        }
+    }
+
+    refs
+}
+
+/// Where a push went
+struct PushTarget {
+    repository: String,
+    branch: Option<String>,
+    on_default_branch: bool,
+}
+
+fn push_target(payload: &GitPushPayload) -> PushTarget {
+    let repository = payload
+        .repository
+        .as_ref()
+        .and_then(|repository| repository.full_name.clone().or_else(|| repository.name.clone()))
+        .or_else(|| payload.project.as_ref().and_then(|project| project.path_with_namespace.clone()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let default_branch = payload
+        .project
+        .as_ref()
+        .and_then(|project| project.default_branch.clone())
+        .or_else(|| payload.repository.as_ref().and_then(|repository| repository.default_branch.clone()));
+    let branch = payload
+        .git_ref
+        .as_deref()
+        .and_then(|git_ref| git_ref.strip_prefix("refs/heads/"))
+        .map(str::to_string);
+
+    // Without a known default branch every branch push may close cards
+    let on_default_branch = match (&branch, &default_branch) {
+        (Some(branch), Some(default_branch)) => branch == default_branch,
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+
+    PushTarget { repository, branch, on_default_branch }
+}
+
+/// A commit's link to a card
+struct Link {
+    /// False when an earlier push linked them already
+    newly_linked: bool,
+    /// The commit has closed the card already
+    closed: bool,
+}
+
+/// Record that a commit references a card, once however often it is pushed.
+/// Locks the link until the transaction ends.
+async fn link_commit(
+    conn: &mut PgConnection,
+    card_id: i32,
+    target: &PushTarget,
+    commit: &GitCommit,
+    closes: bool,
+) -> Result<Link, AppError> {
+    let author = commit.author.as_ref();
+
+    let inserted: Option<i32> = sqlx::query_scalar(
+        "INSERT INTO card_commit (card_id, sha, repository, branch, message, url, author_name, author_username,
+                                  committed_at, closes)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         ON CONFLICT (card_id, sha) DO NOTHING
+         RETURNING card_id"
+    )
+    .bind(card_id)
+    .bind(&commit.id)
+    .bind(&target.repository)
+    .bind(&target.branch)
+    .bind(&commit.message)
+    .bind(&commit.url)
+    .bind(author.and_then(|author| author.name.as_deref()))
+    .bind(author.and_then(|author| author.username.as_deref()))
+    .bind(commit.timestamp)
+    .bind(closes)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let closed: bool = sqlx::query_scalar(
+        "SELECT closed_at IS NOT NULL FROM card_commit WHERE card_id = $1 AND sha = $2 FOR UPDATE"
+    )
+    .bind(card_id)
+    .bind(&commit.id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(Link { newly_linked: inserted.is_some(), closed })
+}
+
+async fn mark_closed(conn: &mut PgConnection, card_id: i32, sha: &str) -> Result<(), AppError> {
+    sqlx::query("UPDATE card_commit SET closed_at = NOW() WHERE card_id = $1 AND sha = $2")
+        .bind(card_id)
+        .bind(sha)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn comment_on_card(
+    conn: &mut PgConnection,
+    card: &Card,
+    target: &PushTarget,
+    commit: &GitCommit,
+    closes: bool,
+) -> Result<(), AppError> {
+    let author = commit.author.as_ref();
+    let author_id = author
+        .and_then(|author| author.username.clone().or_else(|| author.name.clone()))
+        .filter(|author| !author.trim().is_empty())
+        .unwrap_or_else(|| "git".to_string());
+
+    let short_sha: String = commit.id.chars().take(SHORT_SHA_LEN).collect();
+    let summary = commit.message.lines().next().unwrap_or_default().trim();
+    let location = match &target.branch {
+        Some(branch) => format!("{}@{}", target.repository, branch),
+        None => target.repository.clone(),
+    };
+    let mut body = format!(
+        "{} commit {} in {}: {}",
+        if closes { "Closed by" } else { "Referenced in" },
+        short_sha,
+        location,
+        summary
+    );
+    if let Some(url) = &commit.url {
+        body.push('\n');
+        body.push_str(url);
+    }
+
+    create_comment_in(conn, card, CreateCommentRequest { author_id, body }).await?;
+    Ok(())
+}
+
+/// Why a closing commit leaves a card where it is
+enum NotMoved {
+    /// The card is in a done list already, so the commit has closed it
+    AlreadyDone,
+    /// The card could not be moved: no done list, or a strict WIP limit
+    Refused(String),
+}
+
+/// Move a card to the first done list of its board through the regular
+/// update path
+async fn move_to_done(conn: &mut PgConnection, card: Card) -> Result<Option<NotMoved>, AppError> {
+    if get_column_category(&mut *conn, card.list_id).await? == ColumnCategory::Done {
+        return Ok(Some(NotMoved::AlreadyDone));
+    }
+
+    let board_id = card_keys::get_board_id_for_list(&mut *conn, card.list_id).await?;
+    let done_list: Option<i32> = sqlx::query_scalar(
+        "SELECT id FROM board_column WHERE board_id = $1 AND category = $2 ORDER BY rank ASC LIMIT 1"
+    )
+    .bind(board_id)
+    .bind(ColumnCategory::Done)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(done_list) = done_list else {
+        return Ok(Some(NotMoved::Refused("The board has no done list".to_string())));
+    };
+
+    let req = UpdateCardRequest { list_id: Some(done_list), ..Default::default() };
+    match update_card_in(conn, card, req).await {
+        Ok(_) => Ok(None),
+        Err(AppError::WipLimitExceeded(message)) => Ok(Some(NotMoved::Refused(message))),
+        Err(err) => Err(err),
+    }
+}
+
+/// Link one commit to one card, comment on it when newly linked and close
+/// it when the commit closes it and is on the default branch
+async fn apply_ref(
+    conn: &mut PgConnection,
+    card_id: i32,
+    target: &PushTarget,
+    commit: &GitCommit,
+    closes: bool,
+) -> Result<GitCardLink, AppError> {
+    let link = link_commit(&mut *conn, card_id, target, commit, closes).await?;
+    let card = sqlx::query_as::<_, Card>(&format!("SELECT {CARD_COLUMNS} FROM card WHERE id = $1 FOR UPDATE"))
+        .bind(card_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let mut result = GitCardLink {
+        card_id,
+        card_key: card.card_key.clone(),
+        sha: commit.id.clone(),
+        closes,
+        newly_linked: link.newly_linked,
+        moved: false,
+        move_skipped: None,
+    };
+
+    let mut closed_now = false;
+    if closes && link.closed {
+        result.move_skipped = Some("The commit has closed the card already".to_string());
+    } else if closes && !target.on_default_branch {
+        result.move_skipped = Some(format!(
+            "Pushed to {}, not the default branch",
+            target.branch.as_deref().unwrap_or("a tag")
+        ));
+    } else if closes {
+        match move_to_done(&mut *conn, card.clone()).await? {
+            None => {
+                result.moved = true;
+                closed_now = true;
+            }
+            Some(NotMoved::AlreadyDone) => {
+                result.move_skipped = Some("The card is already done".to_string());
+                closed_now = true;
+            }
+            // Left open, so pushing the commit again tries again
+            Some(NotMoved::Refused(reason)) => result.move_skipped = Some(reason),
+        }
+        if closed_now {
+            mark_closed(&mut *conn, card_id, &commit.id).await?;
+        }
+    }
+
+    if link.newly_linked || closed_now {
+        comment_on_card(&mut *conn, &card, target, commit, closed_now).await?;
+    }
+
+    Ok(result)
+}
+
+/// Handle a push event: link, comment on and close the cards its commits
+/// reference, each card in one transaction. A commit linked by an earlier
+/// push is not commented on again, but closes its cards once it reaches
+/// the default branch.
+pub async fn handle_push(
+    pool: &DbPool,
+    credentials: &PushCredentials,
+    body: &[u8],
+) -> Result<GitPushResult, AppError> {
+    let secret = std::env::var("GIT_WEBHOOK_SECRET")
+        .map_err(|_| AppError::NotFound("The git webhook is not configured".to_string()))?;
+    authenticate(&secret, credentials, body)?;
+
+    let payload: GitPushPayload = serde_json::from_slice(body)
+        .map_err(|err| AppError::ValidationError(format!("Invalid push payload: {}", err)))?;
+    let target = push_target(&payload);
+
+    let mut result = GitPushResult { links: Vec::new(), unknown_refs: Vec::new() };
+    for commit in &payload.commits {
+        for (key, closes) in parse_card_refs(&commit.message) {
+            let card_id = match resolve_card_ref(pool, &key).await {
+                Ok(resolved) => resolved.id,
+                Err(AppError::NotFound(_)) => {
+                    if !result.unknown_refs.contains(&key) {
+                        result.unknown_refs.push(key);
+                    }
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+
+            let mut tx = pool.begin().await?;
+            let link = apply_ref(&mut tx, card_id, &target, commit, closes).await?;
+            tx.commit().await?;
+
+            result.links.push(link);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Get the commits that reference a card, newest first
+pub async fn get_commits_for_card(pool: &DbPool, card_id: i32) -> Result<Vec<CardCommit>, AppError> {
+    get_card_by_id(pool, card_id).await?;
+
+    let commits = sqlx::query_as::<_, CardCommit>(&format!(
+        "SELECT {COMMIT_COLUMNS} FROM card_commit WHERE card_id = $1
+         ORDER BY COALESCE(committed_at, created_at) DESC, created_at DESC"
+    ))
+    .bind(card_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "Jefe";
+    const BODY: &[u8] = b"what do ya want for nothing?";
+    // RFC 4231 test case 2
+    const HMAC_HEX: &str = "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+
+    fn refs(message: &str) -> Vec<(String, bool)> {
+        parse_card_refs(message)
+    }
+
+    fn key(key: &str, closes: bool) -> (String, bool) {
+        (key.to_string(), closes)
+    }
+
+    fn payload(value: serde_json::Value) -> GitPushPayload {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn card_keys_are_found_in_order() {
+        assert_eq!(
+            refs("OPS-2: tidy up, see WEB-10 and OPS-1"),
+            vec![key("OPS-2", false), key("WEB-10", false), key("OPS-1", false)]
+        );
+    }
+
+    #[test]
+    fn closing_keywords_close_the_keys_after_them() {
+        assert_eq!(refs("Fixes OPS-42"), vec![key("OPS-42", true)]);
+        assert_eq!(refs("resolved: OPS-42"), vec![key("OPS-42", true)]);
+        assert_eq!(
+            refs("fixes OPS-1, OPS-2 and OPS-3"),
+            vec![key("OPS-1", true), key("OPS-2", true), key("OPS-3", true)]
+        );
+        assert_eq!(
+            refs("closes OPS-1, refs OPS-2"),
+            vec![key("OPS-1", true), key("OPS-2", false)]
+        );
+    }
+
+    #[test]
+    fn a_repeated_key_closes_if_any_mention_does() {
+        assert_eq!(refs("OPS-7 groundwork; fixes OPS-7"), vec![key("OPS-7", true)]);
+        assert_eq!(refs("fixes OPS-7, then more on OPS-7"), vec![key("OPS-7", true)]);
+    }
+
+    #[test]
+    fn words_that_are_not_card_keys_are_ignored() {
+        assert!(refs("ops-1 x-1 OPS- -1 OPS-1a 3D-1 ABCDEFGHIJK-1").is_empty());
+        assert!(refs("").is_empty());
+    }
+
+    #[test]
+    fn hmac_signatures_are_verified() {
+        assert!(verify_hmac(SECRET, BODY, HMAC_HEX));
+        assert!(verify_hmac(SECRET, BODY, &HMAC_HEX.to_uppercase()));
+        assert!(!verify_hmac("other", BODY, HMAC_HEX));
+        assert!(!verify_hmac(SECRET, b"tampered", HMAC_HEX));
+        assert!(!verify_hmac(SECRET, BODY, "not hex"));
+        assert!(!verify_hmac(SECRET, BODY, &HMAC_HEX[..32]));
+    }
+
+    #[test]
+    fn constant_time_eq_compares_whole_strings() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
+    #[test]
+    fn each_provider_authenticates_with_its_credential() {
+        let github = PushCredentials {
+            hub_signature: Some(format!("sha256={}", HMAC_HEX)),
+            ..Default::default()
+        };
+        let gitea = PushCredentials {
+            gitea_signature: Some(HMAC_HEX.to_string()),
+            ..Default::default()
+        };
+        let gitlab = PushCredentials {
+            gitlab_token: Some(SECRET.to_string()),
+            ..Default::default()
+        };
+
+        assert!(authenticate(SECRET, &github, BODY).is_ok());
+        assert!(authenticate(SECRET, &gitea, BODY).is_ok());
+        assert!(authenticate(SECRET, &gitlab, BODY).is_ok());
+        assert!(authenticate("other", &github, BODY).is_err());
+        assert!(authenticate("other", &gitea, BODY).is_err());
+        assert!(authenticate("other", &gitlab, BODY).is_err());
+    }
+
+    #[test]
+    fn missing_or_malformed_credentials_are_rejected() {
+        let unprefixed = PushCredentials {
+            hub_signature: Some(HMAC_HEX.to_string()),
+            ..Default::default()
+        };
+        let sha1 = PushCredentials {
+            hub_signature: Some(format!("sha1={}", HMAC_HEX)),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            authenticate(SECRET, &PushCredentials::default(), BODY),
+            Err(AppError::Unauthorized(_))
+        ));
+        assert!(authenticate(SECRET, &unprefixed, BODY).is_err());
+        assert!(authenticate(SECRET, &sha1, BODY).is_err());
+    }
+
+    #[test]
+    fn github_push_target() {
+        let target = push_target(&payload(serde_json::json!({
+            "ref": "refs/heads/main",
+            "commits": [],
+            "repository": { "full_name": "acme/api", "name": "api", "default_branch": "main" }
+        })));
+        assert_eq!(target.repository, "acme/api");
+        assert_eq!(target.branch.as_deref(), Some("main"));
+        assert!(target.on_default_branch);
+    }
+
+    #[test]
+    fn gitlab_push_target() {
+        let target = push_target(&payload(serde_json::json!({
+            "ref": "refs/heads/feature/x",
+            "commits": [],
+            "project": { "path_with_namespace": "acme/api", "default_branch": "main" }
+        })));
+        assert_eq!(target.repository, "acme/api");
+        assert_eq!(target.branch.as_deref(), Some("feature/x"));
+        assert!(!target.on_default_branch);
+    }
+
+    #[test]
+    fn push_target_defaults() {
+        // Without a known default branch any branch push may close cards
+        let target = push_target(&payload(serde_json::json!({ "ref": "refs/heads/dev", "commits": [] })));
+        assert_eq!(target.repository, "unknown");
+        assert!(target.on_default_branch);
+
+        let tag = push_target(&payload(serde_json::json!({ "ref": "refs/tags/v1", "commits": [] })));
+        assert_eq!(tag.branch, None);
+        assert!(!tag.on_default_branch);
+    }
+}
@@ -0,0 +1,445 @@
+//! Per-user undo and redo of board operations.
+//!
+//! Card moves within the board, whether dragged or made by editing the card,
+//! card renames and deletions and column renames and reorders made by a
+//! known user are recorded on that user's stack for the board. Undo
+//! reverts the user's latest applied operation and redo re-applies the
+//! earliest undone one, each in a single transaction through the regular
+//! handlers. Either is refused with a conflict when what the operation
+//! changed has been changed again since, by anyone. A deleted card comes
+//! back with its comments, time entries, watchers, commit links, revision
+//! history and collaborative description.
+
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::cards::{delete_card_in, move_card_in, update_card_in, CARD_COLUMNS};
+use crate::handlers::columns::COLUMN_COLUMNS;
+use crate::handlers::{mentions, ranks, revisions, webhooks, wip};
+use crate::models::{
+    BoardColumn, BoardOperation, Card, CardDependents, MoveCardRequest, OperationState, UndoableOperation,
+    UpdateCardRequest, WebhookEvent,
+};
+
+const OPERATION_COLUMNS: &str = "id, board_id, user_id, operation, state, created_at, updated_at";
+
+/// Operations kept per user and board; older ones can no longer be undone
+const HISTORY_LIMIT: i64 = 50;
+
+/// Record an operation on a user's stack for a board. Recording drops the
+/// user's undone operations, so they can no longer be redone.
+pub(crate) async fn record_operation(
+    conn: &mut PgConnection,
+    board_id: i32,
+    user_id: &str,
+    operation: UndoableOperation,
+) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM board_operation WHERE board_id = $1 AND user_id = $2 AND state = $3")
+        .bind(board_id)
+        .bind(user_id)
+        .bind(OperationState::Undone)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        "INSERT INTO board_operation (board_id, user_id, operation)
+         VALUES ($1, $2, $3)"
+    )
+    .bind(board_id)
+    .bind(user_id)
+    .bind(sqlx::types::Json(&operation))
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM board_operation
+         WHERE board_id = $1 AND user_id = $2 AND id NOT IN (
+             SELECT id FROM board_operation WHERE board_id = $1 AND user_id = $2
+             ORDER BY id DESC LIMIT $3)"
+    )
+    .bind(board_id)
+    .bind(user_id)
+    .bind(HISTORY_LIMIT)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Get a user's operations on a board, newest first
+pub async fn get_history(pool: &DbPool, board_id: i32, user_id: &str) -> Result<Vec<BoardOperation>, AppError> {
+    let operations = sqlx::query_as::<_, BoardOperation>(&format!(
+        "SELECT {OPERATION_COLUMNS} FROM board_operation
+         WHERE board_id = $1 AND user_id = $2
+         ORDER BY id DESC"
+    ))
+    .bind(board_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(operations)
+}
+
+/// Revert the user's latest applied operation on a board
+pub async fn undo(pool: &DbPool, board_id: i32, user_id: &str) -> Result<BoardOperation, AppError> {
+    step(pool, board_id, user_id, true).await
+}
+
+/// Re-apply the user's earliest undone operation on a board
+pub async fn redo(pool: &DbPool, board_id: i32, user_id: &str) -> Result<BoardOperation, AppError> {
+    step(pool, board_id, user_id, false).await
+}
+
+async fn step(pool: &DbPool, board_id: i32, user_id: &str, undo: bool) -> Result<BoardOperation, AppError> {
+    if user_id.trim().is_empty() {
+        return Err(AppError::ValidationError("userId is required".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let (state, order) = if undo { (OperationState::Done, "DESC") } else { (OperationState::Undone, "ASC") };
+    let entry = sqlx::query_as::<_, BoardOperation>(&format!(
+        "SELECT {OPERATION_COLUMNS} FROM board_operation
+         WHERE board_id = $1 AND user_id = $2 AND state = $3
+         ORDER BY id {order}
+         LIMIT 1
+         FOR UPDATE"
+    ))
+    .bind(board_id)
+    .bind(user_id)
+    .bind(state)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(entry) = entry else {
+        let what = if undo { "undo" } else { "redo" };
+        return Err(AppError::NotFound(format!("Nothing to {} on this board", what)));
+    };
+
+    let refreshed = apply(&mut tx, board_id, user_id, &entry.operation, undo).await?;
+
+    let operation = sqlx::query_as::<_, BoardOperation>(&format!(
+        "UPDATE board_operation SET state = $1, operation = COALESCE($3, operation), updated_at = NOW()
+         WHERE id = $2
+         RETURNING {OPERATION_COLUMNS}"
+    ))
+    .bind(if undo { OperationState::Undone } else { OperationState::Done })
+    .bind(entry.id)
+    .bind(refreshed.map(Json))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(operation)
+}
+
+/// Revert (`undo`) or re-apply an operation, refusing when what it changed
+/// is no longer as the operation left it. Returns the operation to store in
+/// place of the applied one when re-applying it captured newer state.
+async fn apply(
+    conn: &mut PgConnection,
+    board_id: i32,
+    user_id: &str,
+    operation: &UndoableOperation,
+    undo: bool,
+) -> Result<Option<UndoableOperation>, AppError> {
+    match operation {
+        UndoableOperation::CardMoved { card_id, from, to } => {
+            let (current, target) = if undo { (to, from) } else { (from, to) };
+            let card = locked_card(conn, board_id, *card_id).await?;
+            // Other cards moving around it shift its position, which is fine
+            if (card.list_id, card.lane_id) != (current.list_id, current.lane_id) {
+                return Err(AppError::Conflict(format!("Card {} has been moved since", card.card_key)));
+            }
+            // Moving it back must not take it to another board, where it
+            // would get a new key
+            let target_on_board: bool =
+                sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM board_column WHERE id = $1 AND board_id = $2)")
+                    .bind(target.list_id)
+                    .bind(board_id)
+                    .fetch_one(&mut *conn)
+                    .await?;
+            if !target_on_board {
+                return Err(AppError::Conflict(format!(
+                    "List {} has been deleted or moved off the board since",
+                    target.list_id
+                )));
+            }
+            let req = MoveCardRequest {
+                list_id: Some(target.list_id),
+                lane_id: Some(target.lane_id),
+                position: target.position,
+                user_id: None,
+            };
+            move_card_in(conn, &card, req).await?;
+        }
+        UndoableOperation::CardRenamed { card_id, from, to } => {
+            let (current, target) = if undo { (to, from) } else { (from, to) };
+            let card = locked_card(conn, board_id, *card_id).await?;
+            if card.title != *current {
+                return Err(AppError::Conflict(format!("Card {} has been renamed since", card.card_key)));
+            }
+            let req = UpdateCardRequest {
+                title: Some(target.clone()),
+                edited_by: Some(user_id.to_string()),
+                ..Default::default()
+            };
+            update_card_in(conn, card, req).await?;
+        }
+        UndoableOperation::CardDeleted { card, dependents } if undo => {
+            restore_card(conn, board_id, card, dependents).await?;
+        }
+        UndoableOperation::CardDeleted { card, .. } => {
+            // Whatever was added to the card since it was restored is kept
+            // for the next undo
+            locked_card(conn, board_id, card.id).await?;
+            let dependents = snapshot_dependents(conn, card.id).await?;
+            let card = delete_card_in(conn, card.id).await?;
+            return Ok(Some(UndoableOperation::CardDeleted { card: Box::new(card), dependents }));
+        }
+        UndoableOperation::ColumnRenamed { column_id, from, to } => {
+            let (current, target) = if undo { (to, from) } else { (from, to) };
+            rename_column(conn, board_id, *column_id, current, target).await?;
+        }
+        UndoableOperation::ColumnsReordered { from, to } => {
+            let (current, target) = if undo { (to, from) } else { (from, to) };
+            reorder_columns(conn, board_id, current, target).await?;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Lock a card, refusing when it has been deleted or moved off the board
+async fn locked_card(conn: &mut PgConnection, board_id: i32, card_id: i32) -> Result<Card, AppError> {
+    let card = sqlx::query_as::<_, Card>(&format!(
+        "SELECT {CARD_COLUMNS} FROM card
+         WHERE id = $1 AND list_id IN (SELECT id FROM board_column WHERE board_id = $2)
+         FOR UPDATE"
+    ))
+    .bind(card_id)
+    .bind(board_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    card.ok_or_else(|| AppError::Conflict(format!("Card {} has been deleted or moved off the board since", card_id)))
+}
+
+/// Rows of `table` that belong to a card and match `condition`, as JSON
+async fn snapshot_rows(
+    conn: &mut PgConnection,
+    table: &str,
+    card_id: i32,
+    condition: &str,
+) -> Result<Vec<serde_json::Value>, AppError> {
+    let rows: Json<Vec<serde_json::Value>> = sqlx::query_scalar(&format!(
+        "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM {table} t WHERE card_id = $1 {condition}"
+    ))
+    .bind(card_id)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(rows.0)
+}
+
+/// Capture the rows that deleting a card deletes with it
+pub(crate) async fn snapshot_dependents(conn: &mut PgConnection, card_id: i32) -> Result<CardDependents, AppError> {
+    Ok(CardDependents {
+        comments: snapshot_rows(conn, "card_comment", card_id, "").await?,
+        mentions: snapshot_rows(conn, "mention", card_id, "AND comment_id IS NOT NULL").await?,
+        time_entries: snapshot_rows(conn, "time_entry", card_id, "").await?,
+        watchers: snapshot_rows(conn, "card_watcher", card_id, "").await?,
+        commits: snapshot_rows(conn, "card_commit", card_id, "").await?,
+        revisions: snapshot_rows(conn, "card_revision", card_id, "").await?,
+        description_doc: snapshot_rows(conn, "card_description_doc", card_id, "").await?,
+        key_aliases: snapshot_rows(conn, "card_key_alias", card_id, "").await?,
+    })
+}
+
+/// Put captured rows back into `table` as they were. Rows that clash with
+/// ones created since, such as a second running timer, are left out.
+async fn restore_rows(conn: &mut PgConnection, table: &str, rows: &[serde_json::Value]) -> Result<(), AppError> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(&format!(
+        "INSERT INTO {table}
+         SELECT * FROM jsonb_populate_recordset(NULL::{table}, $1)
+         ON CONFLICT DO NOTHING"
+    ))
+    .bind(Json(rows))
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Recreate a deleted card with its ID, key and fields at its old place,
+/// together with the rows deleted with it
+async fn restore_card(
+    conn: &mut PgConnection,
+    board_id: i32,
+    card: &Card,
+    dependents: &CardDependents,
+) -> Result<Card, AppError> {
+    let taken: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM card WHERE id = $1 OR card_key = $2)")
+        .bind(card.id)
+        .bind(&card.card_key)
+        .fetch_one(&mut *conn)
+        .await?;
+    if taken {
+        return Err(AppError::Conflict(format!("Card {} exists again", card.card_key)));
+    }
+
+    let place_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM board_column WHERE id = $1 AND board_id = $3)
+            AND EXISTS(SELECT 1 FROM swimlane WHERE id = $2 AND board_id = $3)"
+    )
+    .bind(card.list_id)
+    .bind(card.lane_id)
+    .bind(board_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if !place_exists {
+        return Err(AppError::Conflict(format!(
+            "The list or lane of card {} has been deleted since",
+            card.card_key
+        )));
+    }
+
+    // A strict limit holds for restored cards too; an advisory one is only
+    // reported on moves and creation
+    wip::check_wip_limit(&mut *conn, card.list_id, 1).await.map_err(|err| match err {
+        AppError::WipLimitExceeded(msg) => {
+            AppError::Conflict(format!("Card {} cannot be restored: {}", card.card_key, msg))
+        }
+        err => err,
+    })?;
+
+    let rank = ranks::card_rank_at(&mut *conn, card.list_id, card.lane_id, Some(card.position), None).await?;
+
+    let mut restored = sqlx::query_as::<_, Card>(&format!(
+        "INSERT INTO card (id, card_number, card_key, title, description, list_id, lane_id, rank,
+                           assignee_id, due_date, labels, priority, estimate, estimate_unit,
+                           started_at, completed_at, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+         RETURNING {CARD_COLUMNS}"
+    ))
+    .bind(card.id)
+    .bind(card.card_number)
+    .bind(&card.card_key)
+    .bind(&card.title)
+    .bind(&card.description)
+    .bind(card.list_id)
+    .bind(card.lane_id)
+    .bind(&rank)
+    .bind(&card.assignee_id)
+    .bind(card.due_date)
+    .bind(&card.labels)
+    .bind(card.priority)
+    .bind(card.estimate)
+    .bind(card.estimate_unit)
+    .bind(card.started_at)
+    .bind(card.completed_at)
+    .bind(card.created_at)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // Comments before the mentions in them
+    restore_rows(&mut *conn, "card_comment", &dependents.comments).await?;
+    restore_rows(&mut *conn, "mention", &dependents.mentions).await?;
+    restore_rows(&mut *conn, "time_entry", &dependents.time_entries).await?;
+    restore_rows(&mut *conn, "card_watcher", &dependents.watchers).await?;
+    restore_rows(&mut *conn, "card_commit", &dependents.commits).await?;
+    restore_rows(&mut *conn, "card_revision", &dependents.revisions).await?;
+    restore_rows(&mut *conn, "card_description_doc", &dependents.description_doc).await?;
+    restore_rows(&mut *conn, "card_key_alias", &dependents.key_aliases).await?;
+
+    // Mentions are restored without notifying anyone a second time
+    if let Some(description) = restored.description.as_deref() {
+        let mentioned = mentions::sync_mentions(&mut *conn, restored.id, restored.list_id, None, description).await?;
+        restored.mentions = Json(mentioned);
+    }
+    // The restored history ends with the card as it was; cards made by
+    // copying or importing have none yet and get their first revision here
+    if dependents.revisions.is_empty() {
+        revisions::record_revision(&mut *conn, None, &restored, None, None).await?;
+    }
+    webhooks::emit_card(&mut *conn, WebhookEvent::CardCreated, &restored).await?;
+
+    Ok(restored)
+}
+
+/// Rename a column from `current` to `title`, refusing unless it is still
+/// called `current`
+async fn rename_column(
+    conn: &mut PgConnection,
+    board_id: i32,
+    column_id: i32,
+    current: &str,
+    title: &str,
+) -> Result<BoardColumn, AppError> {
+    let existing: Option<String> =
+        sqlx::query_scalar("SELECT title FROM board_column WHERE id = $1 AND board_id = $2 FOR UPDATE")
+            .bind(column_id)
+            .bind(board_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+    match existing {
+        Some(existing) if existing == current => {}
+        Some(_) => return Err(AppError::Conflict(format!("List {} has been renamed since", column_id))),
+        None => {
+            return Err(AppError::Conflict(format!(
+                "List {} has been deleted or moved off the board since",
+                column_id
+            )))
+        }
+    }
+
+    let column = sqlx::query_as::<_, BoardColumn>(&format!(
+        "UPDATE board_column SET title = $1, updated_at = NOW() WHERE id = $2 RETURNING {COLUMN_COLUMNS}"
+    ))
+    .bind(title)
+    .bind(column_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    webhooks::emit(&mut *conn, board_id, WebhookEvent::ColumnUpdated, &column).await?;
+
+    Ok(column)
+}
+
+/// Reorder a board's columns to `target`, refusing unless they are still
+/// in the `current` order
+async fn reorder_columns(
+    conn: &mut PgConnection,
+    board_id: i32,
+    current: &[i32],
+    target: &[i32],
+) -> Result<(), AppError> {
+    let order: Vec<i32> =
+        sqlx::query_scalar("SELECT id FROM board_column WHERE board_id = $1 ORDER BY rank ASC FOR UPDATE")
+            .bind(board_id)
+            .fetch_all(&mut *conn)
+            .await?;
+
+    if order != current {
+        return Err(AppError::Conflict(
+            "The board's lists have been added, removed or reordered since".to_string(),
+        ));
+    }
+
+    ranks::apply_column_ranks(&mut *conn, target, &ranks::spread_ranks(target.len())).await?;
+    sqlx::query("UPDATE board_column SET updated_at = NOW() WHERE board_id = $1")
+        .bind(board_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
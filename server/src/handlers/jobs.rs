@@ -0,0 +1,351 @@
+//! Scheduled background jobs.
+//!
+//! Each [`Job`] has a row in `scheduled_job` holding its cron schedule and
+//! next run time. Every instance runs [`run_scheduler`], which polls for due
+//! jobs and leases one at a time: the row is picked with `FOR UPDATE SKIP
+//! LOCKED` and marked with `locked_until`, so concurrent instances never run
+//! the same job twice. A lease left behind by a crashed instance expires
+//! after `LEASE_SECS`. Failed runs are retried with exponential backoff.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use tokio::sync::watch;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::{collab, email, notifications, presence, ranks, recurrence, webhooks};
+use crate::models::{ScheduledJob, UpdateScheduledJobRequest};
+
+const JOB_COLUMNS: &str = "name, schedule, enabled, next_run_at, attempts, locked_by, locked_until, \
+                           last_started_at, last_finished_at, last_error";
+
+/// How often each instance looks for due jobs
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A job still running after this long is considered abandoned
+const LEASE_SECS: f64 = 900.0;
+
+/// Failed runs are retried this many times before waiting for the next
+/// scheduled run
+const MAX_ATTEMPTS: i32 = 5;
+
+/// Delay before the first retry; doubles with each further attempt
+const RETRY_BASE_SECS: i64 = 30;
+
+/// Periodic work run by the scheduler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    /// Rewrite cells whose fractional ranks grew too long
+    RebalanceRanks,
+    /// Create the next cards of recurring series that are due
+    RecurringCards,
+    /// Notify users of cards that are due soon or overdue
+    DueDateReminders,
+    /// Queue emails for new notifications of users in immediate mode and send
+    /// the outbox
+    EmailNotifications,
+    /// Queue the daily digest of users in digest mode
+    EmailDigest,
+    /// Send pending webhook deliveries
+    WebhookDeliveries,
+    /// Write collaboratively edited descriptions back to their cards
+    DescriptionSnapshots,
+    /// Remove board presence that has not been refreshed
+    PresenceExpiry,
+}
+
+impl Job {
+    pub const ALL: &'static [Job] = &[
+        Job::RebalanceRanks,
+        Job::RecurringCards,
+        Job::DueDateReminders,
+        Job::EmailNotifications,
+        Job::EmailDigest,
+        Job::WebhookDeliveries,
+        Job::DescriptionSnapshots,
+        Job::PresenceExpiry,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Job::RebalanceRanks => "rebalance_ranks",
+            Job::RecurringCards => "recurring_cards",
+            Job::DueDateReminders => "due_date_reminders",
+            Job::EmailNotifications => "email_notifications",
+            Job::EmailDigest => "email_digest",
+            Job::WebhookDeliveries => "webhook_deliveries",
+            Job::DescriptionSnapshots => "description_snapshots",
+            Job::PresenceExpiry => "presence_expiry",
+        }
+    }
+
+    /// Cron expression (sec min hour day-of-month month day-of-week), in UTC
+    fn schedule(self) -> &'static str {
+        match self {
+            Job::RebalanceRanks => "0 */10 * * * *",
+            Job::RecurringCards => "0 * * * * *",
+            Job::DueDateReminders => "0 */5 * * * *",
+            Job::EmailNotifications => "30 * * * * *",
+            Job::EmailDigest => "0 0 7 * * *",
+            Job::WebhookDeliveries => "*/15 * * * * *",
+            Job::DescriptionSnapshots => "*/30 * * * * *",
+            Job::PresenceExpiry => "*/15 * * * * *",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Job> {
+        Job::ALL.iter().copied().find(|job| job.name() == name)
+    }
+
+    async fn run(self, pool: &DbPool) -> Result<(), AppError> {
+        match self {
+            Job::RebalanceRanks => {
+                let cells = ranks::rebalance_long_ranks(pool).await?;
+                if cells > 0 {
+                    tracing::info!("Rebalanced ranks in {} cells", cells);
+                }
+            }
+            Job::RecurringCards => {
+                let cards = recurrence::materialise_due_cards(pool).await?;
+                if cards > 0 {
+                    tracing::info!("Created {} recurring cards", cards);
+                }
+            }
+            Job::DueDateReminders => {
+                let sent = notifications::send_due_date_reminders(pool).await?;
+                if sent > 0 {
+                    tracing::info!("Sent {} due date reminders", sent);
+                }
+            }
+            Job::EmailNotifications => {
+                let queued = email::queue_immediate_emails(pool).await?;
+                let sent = email::deliver_outbox(pool).await?;
+                if queued > 0 || sent > 0 {
+                    tracing::info!("Queued {} and sent {} emails", queued, sent);
+                }
+            }
+            Job::EmailDigest => {
+                let queued = email::queue_digests(pool).await?;
+                if queued > 0 {
+                    tracing::info!("Queued {} email digests", queued);
+                }
+            }
+            Job::WebhookDeliveries => {
+                let delivered = webhooks::deliver_pending(pool).await?;
+                if delivered > 0 {
+                    tracing::info!("Delivered {} webhook payloads", delivered);
+                }
+            }
+            Job::DescriptionSnapshots => {
+                let changed = collab::snapshot_descriptions(pool).await?;
+                if changed > 0 {
+                    tracing::info!("Wrote back {} collaboratively edited descriptions", changed);
+                }
+            }
+            Job::PresenceExpiry => {
+                let expired = presence::expire_stale(pool).await?;
+                if expired > 0 {
+                    tracing::info!("Expired {} board presence connections", expired);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_schedule(expression: &str) -> Result<Schedule, AppError> {
+    Schedule::from_str(expression)
+        .map_err(|err| AppError::InternalError(format!("Invalid job schedule \"{}\": {}", expression, err)))
+}
+
+/// The first time after `after` matching a cron expression
+fn next_occurrence(expression: &str, after: DateTime<Utc>) -> Result<DateTime<Utc>, AppError> {
+    parse_schedule(expression)?
+        .after(&after)
+        .next()
+        .ok_or_else(|| AppError::InternalError(format!("Job schedule \"{}\" never fires", expression)))
+}
+
+/// Register every job, updating the schedule of existing rows. A job whose
+/// schedule changed gets a fresh next run time.
+pub async fn sync_jobs(pool: &DbPool) -> Result<(), AppError> {
+    let now = Utc::now();
+
+    for job in Job::ALL {
+        let next_run_at = next_occurrence(job.schedule(), now)?;
+
+        sqlx::query(
+            "INSERT INTO scheduled_job (name, schedule, next_run_at)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (name) DO UPDATE
+             SET schedule = EXCLUDED.schedule,
+                 next_run_at = CASE
+                     WHEN scheduled_job.schedule = EXCLUDED.schedule THEN scheduled_job.next_run_at
+                     ELSE EXCLUDED.next_run_at
+                 END"
+        )
+        .bind(job.name())
+        .bind(job.schedule())
+        .bind(next_run_at)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Poll for due jobs until `shutdown` flips to true. A job that is running
+/// when shutdown is requested is allowed to finish.
+pub async fn run_scheduler(pool: DbPool, mut shutdown: watch::Receiver<bool>) {
+    let worker = format!(
+        "{}:{}",
+        std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string()),
+        std::process::id()
+    );
+    tracing::info!("Job scheduler started as {}", worker);
+
+    while !*shutdown.borrow() {
+        loop {
+            match run_next_due_job(&pool, &worker).await {
+                Ok(true) if !*shutdown.borrow() => continue,
+                Ok(_) => break,
+                Err(err) => {
+                    tracing::warn!("Job scheduler failed to lease a job: {:?}", err);
+                    break;
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            changed = shutdown.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    tracing::info!("Job scheduler stopped");
+}
+
+/// Lease and run one due job. Returns whether a job was run.
+async fn run_next_due_job(pool: &DbPool, worker: &str) -> Result<bool, AppError> {
+    let names: Vec<&str> = Job::ALL.iter().map(|job| job.name()).collect();
+
+    let leased: Option<(String, String, i32)> = sqlx::query_as(
+        "UPDATE scheduled_job
+         SET locked_by = $1,
+             locked_until = NOW() + make_interval(secs => $2),
+             last_started_at = NOW()
+         WHERE name = (
+             SELECT name FROM scheduled_job
+             WHERE enabled
+               AND name = ANY($3)
+               AND next_run_at <= NOW()
+               AND (locked_until IS NULL OR locked_until < NOW())
+             ORDER BY next_run_at ASC
+             LIMIT 1
+             FOR UPDATE SKIP LOCKED
+         )
+         RETURNING name, schedule, attempts"
+    )
+    .bind(worker)
+    .bind(LEASE_SECS)
+    .bind(&names)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some((name, schedule, attempts)) = leased else {
+        return Ok(false);
+    };
+    let Some(job) = Job::from_name(&name) else {
+        return Ok(false);
+    };
+
+    let outcome = job.run(pool).await;
+    let now = Utc::now();
+    let next_run_at = next_occurrence(&schedule, now)?;
+
+    match outcome {
+        Ok(()) => {
+            sqlx::query(
+                "UPDATE scheduled_job
+                 SET next_run_at = $2, attempts = 0, locked_by = NULL, locked_until = NULL,
+                     last_finished_at = NOW(), last_error = NULL
+                 WHERE name = $1 AND locked_by = $3"
+            )
+            .bind(&name)
+            .bind(next_run_at)
+            .bind(worker)
+            .execute(pool)
+            .await?;
+        }
+        Err(err) => {
+            let attempts = attempts + 1;
+            let (attempts, retry_at) = if attempts < MAX_ATTEMPTS {
+                let backoff = chrono::Duration::seconds(RETRY_BASE_SECS << (attempts - 1));
+                (attempts, (now + backoff).min(next_run_at))
+            } else {
+                (0, next_run_at)
+            };
+            tracing::warn!("Job {} failed, next attempt at {}: {:?}", name, retry_at, err);
+
+            sqlx::query(
+                "UPDATE scheduled_job
+                 SET next_run_at = $2, attempts = $3, locked_by = NULL, locked_until = NULL,
+                     last_finished_at = NOW(), last_error = $4
+                 WHERE name = $1 AND locked_by = $5"
+            )
+            .bind(&name)
+            .bind(retry_at)
+            .bind(attempts)
+            .bind(format!("{:?}", err))
+            .bind(worker)
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Get all registered jobs
+pub async fn get_all_jobs(pool: &DbPool) -> Result<Vec<ScheduledJob>, AppError> {
+    let jobs = sqlx::query_as::<_, ScheduledJob>(&format!(
+        "SELECT {JOB_COLUMNS} FROM scheduled_job ORDER BY name ASC"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(jobs)
+}
+
+/// Enable or disable a job
+pub async fn update_job(pool: &DbPool, name: &str, req: UpdateScheduledJobRequest) -> Result<ScheduledJob, AppError> {
+    let job = sqlx::query_as::<_, ScheduledJob>(&format!(
+        "UPDATE scheduled_job SET enabled = COALESCE($2, enabled) WHERE name = $1 RETURNING {JOB_COLUMNS}"
+    ))
+    .bind(name)
+    .bind(req.enabled)
+    .fetch_optional(pool)
+    .await?;
+
+    job.ok_or_else(|| AppError::NotFound(format!("Job {} not found", name)))
+}
+
+/// Make a job due now; the next poll of any instance picks it up
+pub async fn trigger_job(pool: &DbPool, name: &str) -> Result<ScheduledJob, AppError> {
+    let job = sqlx::query_as::<_, ScheduledJob>(&format!(
+        "UPDATE scheduled_job SET next_run_at = NOW(), attempts = 0 WHERE name = $1 RETURNING {JOB_COLUMNS}"
+    ))
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    job.ok_or_else(|| AppError::NotFound(format!("Job {} not found", name)))
+}
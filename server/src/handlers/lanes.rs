@@ -0,0 +1,299 @@
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::boards::get_board_by_id;
+use crate::handlers::cards::CARD_COLUMNS;
+use crate::handlers::columns::COLUMN_COLUMNS;
+use crate::handlers::ranks;
+use crate::models::{
+    BoardColumn, BoardDetails, Card, ColumnDetails, CreateSwimlaneRequest, LaneCards, Swimlane,
+    UpdateSwimlaneRequest,
+};
+
+const LANE_COLUMNS: &str = "id, board_id, title, position, is_default, created_at, updated_at";
+
+const DEFAULT_LANE_TITLE: &str = "Default";
+
+/// Get the lanes of a board in order
+pub async fn get_lanes_for_board(conn: &mut PgConnection, board_id: i32) -> Result<Vec<Swimlane>, AppError> {
+    let lanes = sqlx::query_as::<_, Swimlane>(&format!(
+        "SELECT {LANE_COLUMNS}
+         FROM swimlane
+         WHERE board_id = $1
+         ORDER BY position ASC, id ASC"
+    ))
+    .bind(board_id)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(lanes)
+}
+
+/// Get the lanes of an existing board
+pub async fn get_board_lanes(pool: &DbPool, board_id: i32) -> Result<Vec<Swimlane>, AppError> {
+    get_board_by_id(pool, board_id).await?;
+    let mut conn = pool.acquire().await?;
+    get_lanes_for_board(&mut conn, board_id).await
+}
+
+/// Get lane by ID
+pub async fn get_lane_by_id(pool: &DbPool, id: i32) -> Result<Swimlane, AppError> {
+    let lane = sqlx::query_as::<_, Swimlane>(&format!(
+        "SELECT {LANE_COLUMNS} FROM swimlane WHERE id = $1"
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    lane.ok_or_else(|| AppError::NotFound(format!("Lane with id {} not found", id)))
+}
+
+/// Create the default lane of a new board
+pub async fn create_default_lane(conn: &mut PgConnection, board_id: i32) -> Result<i32, AppError> {
+    let id = sqlx::query_scalar(
+        "INSERT INTO swimlane (board_id, title, position, is_default)
+         VALUES ($1, $2, 0, TRUE)
+         RETURNING id"
+    )
+    .bind(board_id)
+    .bind(DEFAULT_LANE_TITLE)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(id)
+}
+
+/// Get the default lane of a board
+pub async fn get_default_lane_id(conn: &mut PgConnection, board_id: i32) -> Result<i32, AppError> {
+    let id: Option<i32> = sqlx::query_scalar(
+        "SELECT id FROM swimlane WHERE board_id = $1 AND is_default"
+    )
+    .bind(board_id)
+    .fetch_optional(conn)
+    .await?;
+
+    id.ok_or_else(|| AppError::InternalError(format!("Board {} has no default lane", board_id)))
+}
+
+/// Check that a requested lane belongs to a board, falling back to the
+/// board's default lane when none is requested
+pub async fn resolve_lane_id(
+    conn: &mut PgConnection,
+    board_id: i32,
+    lane_id: Option<i32>,
+) -> Result<i32, AppError> {
+    let Some(lane_id) = lane_id else {
+        return get_default_lane_id(conn, board_id).await;
+    };
+
+    let lane_board_id: Option<i32> = sqlx::query_scalar("SELECT board_id FROM swimlane WHERE id = $1")
+        .bind(lane_id)
+        .fetch_optional(conn)
+        .await?;
+
+    match lane_board_id {
+        Some(id) if id == board_id => Ok(lane_id),
+        Some(_) => Err(AppError::ValidationError(format!(
+            "Lane {} does not belong to board {}",
+            lane_id, board_id
+        ))),
+        None => Err(AppError::NotFound(format!("Lane with id {} not found", lane_id))),
+    }
+}
+
+/// Get a board with its lanes, and each column's cards grouped by lane.
+/// Every column lists every lane, in lane order, even when it is empty.
+pub async fn get_board_details(pool: &DbPool, board_id: i32) -> Result<BoardDetails, AppError> {
+    let board = get_board_by_id(pool, board_id).await?;
+
+    let mut conn = pool.acquire().await?;
+    let lanes = get_lanes_for_board(&mut conn, board_id).await?;
+
+    let columns = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS}
+         FROM board_column
+         WHERE board_id = $1
+         ORDER BY rank ASC"
+    ))
+    .bind(board_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let cards = sqlx::query_as::<_, Card>(&format!(
+        "SELECT {CARD_COLUMNS}
+         FROM card
+         WHERE list_id IN (SELECT id FROM board_column WHERE board_id = $1)
+         ORDER BY rank ASC"
+    ))
+    .bind(board_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let columns = columns
+        .into_iter()
+        .map(|column| {
+            let lanes = lanes
+                .iter()
+                .map(|lane| LaneCards {
+                    lane_id: lane.id,
+                    cards: cards
+                        .iter()
+                        .filter(|card| card.list_id == column.id && card.lane_id == lane.id)
+                        .cloned()
+                        .collect(),
+                })
+                .collect();
+            ColumnDetails { column, lanes }
+        })
+        .collect();
+
+    Ok(BoardDetails { board, lanes, columns })
+}
+
+/// Create a lane at the bottom of a board
+pub async fn create_lane(pool: &DbPool, board_id: i32, req: CreateSwimlaneRequest) -> Result<Swimlane, AppError> {
+    if req.title.trim().is_empty() {
+        return Err(AppError::ValidationError("Title is required".to_string()));
+    }
+
+    let board_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM board WHERE id = $1)"
+    )
+    .bind(board_id)
+    .fetch_one(pool)
+    .await?;
+
+    if !board_exists {
+        return Err(AppError::NotFound(format!("Board with id {} not found", board_id)));
+    }
+
+    let lane = sqlx::query_as::<_, Swimlane>(&format!(
+        "INSERT INTO swimlane (board_id, title, position)
+         VALUES ($1, $2, (SELECT COALESCE(MAX(position), -1) + 1 FROM swimlane WHERE board_id = $1))
+         RETURNING {LANE_COLUMNS}"
+    ))
+    .bind(board_id)
+    .bind(&req.title)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(lane)
+}
+
+/// Rename a lane and/or move it to another index among the board's lanes
+pub async fn update_lane(pool: &DbPool, id: i32, req: UpdateSwimlaneRequest) -> Result<Swimlane, AppError> {
+    if req.title.as_deref().is_some_and(|t| t.trim().is_empty()) {
+        return Err(AppError::ValidationError("Title cannot be empty".to_string()));
+    }
+
+    let current = get_lane_by_id(pool, id).await?;
+    let mut tx = pool.begin().await?;
+
+    if let Some(position) = req.position {
+        let mut lane_ids: Vec<i32> = get_lanes_for_board(&mut tx, current.board_id)
+            .await?
+            .into_iter()
+            .map(|lane| lane.id)
+            .filter(|lane_id| *lane_id != id)
+            .collect();
+        let index = usize::try_from(position.max(0)).unwrap_or(0).min(lane_ids.len());
+        lane_ids.insert(index, id);
+
+        sqlx::query(
+            "UPDATE swimlane AS s
+             SET position = v.ord - 1, updated_at = NOW()
+             FROM unnest($1::INT[]) WITH ORDINALITY AS v(id, ord)
+             WHERE s.id = v.id"
+        )
+        .bind(&lane_ids)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let lane = sqlx::query_as::<_, Swimlane>(&format!(
+        "UPDATE swimlane
+         SET title = $1, updated_at = NOW()
+         WHERE id = $2
+         RETURNING {LANE_COLUMNS}"
+    ))
+    .bind(req.title.unwrap_or(current.title))
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(lane)
+}
+
+/// Delete a lane, moving its cards to the end of the default lane of each column
+pub async fn delete_lane(pool: &DbPool, id: i32) -> Result<(), AppError> {
+    let lane = get_lane_by_id(pool, id).await?;
+    if lane.is_default {
+        return Err(AppError::ValidationError(
+            "The default lane cannot be deleted".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+    let default_lane_id = get_default_lane_id(&mut tx, lane.board_id).await?;
+
+    // Append the lane's cards after the default lane's cards in each column
+    let list_ids: Vec<i32> = sqlx::query_scalar("SELECT DISTINCT list_id FROM card WHERE lane_id = $1")
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    for list_id in list_ids {
+        let last: Option<String> = sqlx::query_scalar(
+            "SELECT MAX(rank) FROM card WHERE list_id = $1 AND lane_id = $2"
+        )
+        .bind(list_id)
+        .bind(default_lane_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let card_ids: Vec<i32> = sqlx::query_scalar(
+            "SELECT id FROM card WHERE list_id = $1 AND lane_id = $2 ORDER BY rank ASC"
+        )
+        .bind(list_id)
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let new_ranks = ranks::ranks_between(last.as_deref(), None, card_ids.len());
+        ranks::apply_card_ranks(&mut tx, &card_ids, &new_ranks).await?;
+    }
+
+    sqlx::query("UPDATE card SET lane_id = $2, updated_at = NOW() WHERE lane_id = $1")
+        .bind(id)
+        .bind(default_lane_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM swimlane WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Close the gap left in the lane order
+    sqlx::query(
+        "WITH ordered AS (
+             SELECT id, ROW_NUMBER() OVER (ORDER BY position ASC, id ASC) - 1 AS new_position
+             FROM swimlane
+             WHERE board_id = $1
+         )
+         UPDATE swimlane AS s
+         SET position = o.new_position
+         FROM ordered AS o
+         WHERE s.id = o.id AND s.position <> o.new_position"
+    )
+    .bind(lane.board_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
@@ -0,0 +1,311 @@
+//! Markdown rendering of card and board descriptions: CommonMark with tables
+//! and task lists, sanitised against a strict allowlist of tags and
+//! attributes. Raw HTML in the source is shown as text rather than passed
+//! through. Rendered HTML is cached in the row's `description_html` column,
+//! which `update_card` and `update_board` clear when the description changes.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use ammonia::Builder;
+use pulldown_cmark::{html, Event, Options, Parser};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{Board, Card};
+
+const ALLOWED_TAGS: &[&str] = &[
+    "p", "br", "hr", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "pre", "code", "em", "strong",
+    "ul", "ol", "li", "a", "img", "table", "thead", "tbody", "tr", "th", "td", "input",
+];
+
+const ALLOWED_URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+fn sanitizer() -> &'static Builder<'static> {
+    static SANITIZER: OnceLock<Builder<'static>> = OnceLock::new();
+    SANITIZER.get_or_init(|| {
+        let tag_attributes = HashMap::from([
+            ("a", HashSet::from(["href", "title"])),
+            ("img", HashSet::from(["src", "alt", "title"])),
+            ("ol", HashSet::from(["start"])),
+            ("th", HashSet::from(["style"])),
+            ("td", HashSet::from(["style"])),
+            ("input", HashSet::from(["type", "checked", "disabled"])),
+        ]);
+
+        let mut builder = Builder::empty();
+        builder
+            .tags(ALLOWED_TAGS.iter().copied().collect())
+            .tag_attributes(tag_attributes)
+            .generic_attributes(HashSet::new())
+            .url_schemes(ALLOWED_URL_SCHEMES.iter().copied().collect())
+            .link_rel(Some("noopener noreferrer nofollow"))
+            .attribute_filter(|element, attribute, value| match (element, attribute) {
+                // Column alignment is the only inline style tables get
+                ("th" | "td", "style") => {
+                    matches!(value, "text-align: left" | "text-align: center" | "text-align: right")
+                        .then_some(value.into())
+                }
+                // Task list items; their checkboxes are read-only
+                ("input", "type") => (value == "checkbox").then_some(value.into()),
+                _ => Some(value.into()),
+            });
+        builder
+    })
+}
+
+/// Render Markdown to sanitised HTML
+pub fn render_markdown(source: &str) -> String {
+    let parser = Parser::new_ext(source, Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        event => event,
+    });
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+    sanitizer().clean(&unsafe_html).to_string()
+}
+
+/// Rendered HTML of the descriptions of rows of `table` (`card` or `board`),
+/// from the cache where it is fresh. Descriptions rendered here are cached,
+/// unless the description changed in the meantime.
+async fn description_html(
+    pool: &DbPool,
+    table: &str,
+    descriptions: &[(i32, &str)],
+) -> Result<HashMap<i32, String>, AppError> {
+    let ids: Vec<i32> = descriptions.iter().map(|(id, _)| *id).collect();
+    let cached: Vec<(i32, String)> = sqlx::query_as(&format!(
+        "SELECT id, description_html FROM {table} WHERE id = ANY($1) AND description_html IS NOT NULL"
+    ))
+    .bind(&ids)
+    .fetch_all(pool)
+    .await?;
+    let mut rendered: HashMap<i32, String> = cached.into_iter().collect();
+
+    let mut stale_ids = Vec::new();
+    let mut stale_sources = Vec::new();
+    let mut stale_html = Vec::new();
+    for (id, source) in descriptions {
+        if !rendered.contains_key(id) {
+            let html = render_markdown(source);
+            stale_ids.push(*id);
+            stale_sources.push(source.to_string());
+            stale_html.push(html.clone());
+            rendered.insert(*id, html);
+        }
+    }
+
+    if !stale_ids.is_empty() {
+        sqlx::query(&format!(
+            "UPDATE {table} t SET description_html = r.html
+             FROM unnest($1::INT[], $2::TEXT[], $3::TEXT[]) AS r(id, source, html)
+             WHERE t.id = r.id AND t.description = r.source"
+        ))
+        .bind(&stale_ids)
+        .bind(&stale_sources)
+        .bind(&stale_html)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(rendered)
+}
+
+/// Fill in the `descriptionHtml` of cards that have a description
+pub async fn attach_card_html(pool: &DbPool, cards: &mut [Card]) -> Result<(), AppError> {
+    let descriptions: Vec<(i32, &str)> = cards
+        .iter()
+        .filter_map(|card| card.description.as_deref().map(|description| (card.id, description)))
+        .collect();
+    if descriptions.is_empty() {
+        return Ok(());
+    }
+
+    let mut rendered = description_html(pool, "card", &descriptions).await?;
+    for card in cards.iter_mut() {
+        card.description_html = rendered.remove(&card.id);
+    }
+    Ok(())
+}
+
+/// Fill in the `descriptionHtml` of boards that have a description
+pub async fn attach_board_html(pool: &DbPool, boards: &mut [Board]) -> Result<(), AppError> {
+    let descriptions: Vec<(i32, &str)> = boards
+        .iter()
+        .filter_map(|board| board.description.as_deref().map(|description| (board.id, description)))
+        .collect();
+    if descriptions.is_empty() {
+        return Ok(());
+    }
+
+    let mut rendered = description_html(pool, "board", &descriptions).await?;
+    for board in boards.iter_mut() {
+        board.description_html = rendered.remove(&board.id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{boards, cards, columns};
+    use crate::models::{CreateBoardRequest, CreateCardRequest, CreateColumnRequest, UpdateBoardRequest, UpdateCardRequest};
+
+    #[test]
+    fn renders_commonmark() {
+        assert_eq!(
+            render_markdown("# Title\n\nSome *emphasis* and `code`."),
+            "<h1>Title</h1>\n<p>Some <em>emphasis</em> and <code>code</code>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn raw_html_is_shown_as_text() {
+        let html = render_markdown("<script>alert(1)</script>\n\nInline <b onclick=\"x()\">bold</b>");
+        assert!(!html.contains("<script"), "{}", html);
+        assert!(!html.contains("<b"), "{}", html);
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"), "{}", html);
+        assert!(html.contains("&lt;b onclick"), "{}", html);
+    }
+
+    #[test]
+    fn unsafe_links_are_dropped() {
+        let html = render_markdown("[click](javascript:alert(1)) ![x](data:image/png;base64,AAAA)");
+        assert!(!html.contains("javascript:"), "{}", html);
+        assert!(!html.contains("data:"), "{}", html);
+
+        let html = render_markdown("[docs](https://example.com/docs)");
+        assert_eq!(
+            html,
+            "<p><a href=\"https://example.com/docs\" rel=\"noopener noreferrer nofollow\">docs</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_task_lists_as_read_only_checkboxes() {
+        let html = render_markdown("- [x] done\n- [ ] todo");
+        assert_eq!(
+            html,
+            "<ul>\n<li><input disabled=\"\" type=\"checkbox\" checked=\"\">\ndone</li>\n\
+             <li><input disabled=\"\" type=\"checkbox\">\ntodo</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn renders_tables_with_alignment() {
+        let html = render_markdown("| a | b |\n|:--|--:|\n| 1 | 2 |");
+        assert!(html.contains("<table>"), "{}", html);
+        assert!(html.contains("<th style=\"text-align: left\">a</th>"), "{}", html);
+        assert!(html.contains("<td style=\"text-align: right\">2</td>"), "{}", html);
+    }
+
+    async fn cached_html(pool: &DbPool, table: &str, id: i32) -> Option<String> {
+        sqlx::query_scalar(&format!("SELECT description_html FROM {table} WHERE id = $1"))
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    /// Deletes the test board when dropped, also when an assertion fails
+    struct BoardCleanup(i32);
+
+    impl Drop for BoardCleanup {
+        fn drop(&mut self) {
+            let id = self.0;
+            // The test's runtime may be unwinding, so clean up on a fresh one
+            let cleanup = std::thread::spawn(move || {
+                tokio::runtime::Runtime::new().unwrap().block_on(async {
+                    let pool = crate::db::create_pool().await.unwrap();
+                    boards::delete_board(&pool, id).await.unwrap();
+                })
+            });
+            let _ = cleanup.join();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a migrated DATABASE_URL"]
+    async fn updates_clear_the_cache_when_the_description_changes() {
+        let pool = crate::db::create_pool().await.unwrap();
+
+        let board = boards::create_board(
+            &pool,
+            CreateBoardRequest {
+                title: "Markdown cache test".to_string(),
+                description: Some("*board*".to_string()),
+                key_prefix: None,
+                template_id: None,
+            },
+        )
+        .await
+        .unwrap();
+        let _cleanup = BoardCleanup(board.id);
+        let list = columns::create_column(
+            &pool,
+            CreateColumnRequest {
+                title: "Todo".to_string(),
+                board_id: board.id,
+                category: None,
+                wip_limit: None,
+                wip_mode: None,
+            },
+        )
+        .await
+        .unwrap();
+        let (card, _) = cards::create_card(
+            &pool,
+            serde_json::from_value::<CreateCardRequest>(serde_json::json!({
+                "title": "Card",
+                "description": "*card*",
+                "listId": list.id,
+            }))
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let mut boards_found = vec![board.clone()];
+        attach_board_html(&pool, &mut boards_found).await.unwrap();
+        let mut cards_found = vec![card.clone()];
+        attach_card_html(&pool, &mut cards_found).await.unwrap();
+        assert_eq!(cached_html(&pool, "board", board.id).await.as_deref(), Some("<p><em>board</em></p>\n"));
+        assert_eq!(cached_html(&pool, "card", card.id).await.as_deref(), Some("<p><em>card</em></p>\n"));
+
+        // Saving the same description keeps the cache
+        let same_board = UpdateBoardRequest {
+            title: None,
+            description: Some("*board*".to_string()),
+        };
+        boards::update_board(&pool, board.id, same_board).await.unwrap();
+        let same_card = UpdateCardRequest {
+            description: Some("*card*".to_string()),
+            ..Default::default()
+        };
+        cards::update_card(&pool, card.id, same_card).await.unwrap();
+        assert!(cached_html(&pool, "board", board.id).await.is_some());
+        assert!(cached_html(&pool, "card", card.id).await.is_some());
+
+        // A new description clears it, and the next read renders the new text
+        let new_board = UpdateBoardRequest {
+            title: None,
+            description: Some("**board**".to_string()),
+        };
+        let board = boards::update_board(&pool, board.id, new_board).await.unwrap();
+        let new_card = UpdateCardRequest {
+            description: Some("**card**".to_string()),
+            ..Default::default()
+        };
+        let (card, _) = cards::update_card(&pool, card.id, new_card).await.unwrap();
+        assert_eq!(cached_html(&pool, "board", board.id).await, None);
+        assert_eq!(cached_html(&pool, "card", card.id).await, None);
+
+        let mut boards_found = vec![board.clone()];
+        attach_board_html(&pool, &mut boards_found).await.unwrap();
+        let mut cards_found = vec![card];
+        attach_card_html(&pool, &mut cards_found).await.unwrap();
+        assert_eq!(boards_found[0].description_html.as_deref(), Some("<p><strong>board</strong></p>\n"));
+        assert_eq!(cards_found[0].description_html.as_deref(), Some("<p><strong>card</strong></p>\n"));
+    }
+}
@@ -0,0 +1,204 @@
+//! @mentions in card descriptions and comments. Only the owner and members
+//! of the card's board can be mentioned; any other `@name` stays plain text.
+
+use sqlx::PgConnection;
+
+use crate::errors::AppError;
+use crate::handlers::card_keys;
+use crate::handlers::notifications::{self, NewNotification};
+use crate::models::{Card, CardComment, Mention, NotificationKind};
+
+fn is_username_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-')
+}
+
+/// `@name` tokens in a text with their character spans, end exclusive. An
+/// `@` inside a word, as in an email address, does not start a mention, and
+/// trailing dots and dashes are punctuation rather than part of the name.
+pub fn parse_mentions(text: &str) -> Vec<(String, usize, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut mentions = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '@' || (i > 0 && is_username_char(chars[i - 1])) {
+            i += 1;
+            continue;
+        }
+
+        let mut end = i + 1;
+        while end < chars.len() && is_username_char(chars[end]) {
+            end += 1;
+        }
+        while end > i + 1 && matches!(chars[end - 1], '.' | '-') {
+            end -= 1;
+        }
+
+        if end > i + 1 {
+            mentions.push((chars[i + 1..end].iter().collect(), i, end));
+        }
+        i = end.max(i + 1);
+    }
+
+    mentions
+}
+
+/// Users who can be mentioned on a board: its owner and members
+async fn board_members(conn: &mut PgConnection, board_id: i32) -> Result<Vec<String>, AppError> {
+    let board: Option<(Option<String>, Vec<String>)> =
+        sqlx::query_as("SELECT owner_id, members FROM board WHERE id = $1")
+            .bind(board_id)
+            .fetch_optional(conn)
+            .await?;
+
+    let (owner_id, members) =
+        board.ok_or_else(|| AppError::NotFound(format!("Board with id {} not found", board_id)))?;
+    Ok(owner_id.into_iter().chain(members).collect())
+}
+
+/// The member a name refers to, matching case-insensitively when no member
+/// has exactly that ID
+fn find_member<'a>(members: &'a [String], name: &str) -> Option<&'a String> {
+    members
+        .iter()
+        .find(|member| *member == name)
+        .or_else(|| members.iter().find(|member| member.to_lowercase() == name.to_lowercase()))
+}
+
+/// Replace the mentions stored for a card's description (`comment_id` of
+/// None) or for one of its comments with those in `text`, and return them.
+/// `list_id` is the list the card is in, whose board's members can be
+/// mentioned.
+pub async fn sync_mentions(
+    conn: &mut PgConnection,
+    card_id: i32,
+    list_id: i32,
+    comment_id: Option<i32>,
+    text: &str,
+) -> Result<Vec<Mention>, AppError> {
+    sqlx::query("DELETE FROM mention WHERE card_id = $1 AND comment_id IS NOT DISTINCT FROM $2")
+        .bind(card_id)
+        .bind(comment_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let candidates = parse_mentions(text);
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let board_id = card_keys::get_board_id_for_list(&mut *conn, list_id).await?;
+    let members = board_members(&mut *conn, board_id).await?;
+
+    let mut user_ids = Vec::new();
+    let mut starts = Vec::new();
+    let mut ends = Vec::new();
+    for (name, start, end) in candidates {
+        if let Some(user_id) = find_member(&members, &name) {
+            user_ids.push(user_id.clone());
+            starts.push(start as i32);
+            ends.push(end as i32);
+        }
+    }
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mentions = sqlx::query_as::<_, Mention>(
+        "WITH inserted AS (
+             INSERT INTO mention (card_id, comment_id, user_id, span_start, span_end)
+             SELECT $1, $2, m.user_id, m.span_start, m.span_end
+             FROM unnest($3::TEXT[], $4::INT[], $5::INT[]) AS m(user_id, span_start, span_end)
+             RETURNING user_id, span_start, span_end
+         )
+         SELECT i.user_id, COALESCE(p.display_name, i.user_id) AS display_name,
+                i.span_start AS start, i.span_end AS \"end\"
+         FROM inserted i LEFT JOIN user_profile p ON p.user_id = i.user_id
+         ORDER BY i.span_start ASC"
+    )
+    .bind(card_id)
+    .bind(comment_id)
+    .bind(&user_ids)
+    .bind(&starts)
+    .bind(&ends)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(mentions)
+}
+
+/// Notify the users mentioned in a card's description, or in `comment`.
+/// A user is notified once per description or comment, however often it is
+/// edited.
+pub async fn notify_mentioned(
+    conn: &mut PgConnection,
+    card: &Card,
+    comment: Option<&CardComment>,
+    mentions: &[Mention],
+) -> Result<(), AppError> {
+    let recipients: Vec<String> = mentions.iter().map(|mention| mention.user_id.clone()).collect();
+
+    let notification = match comment {
+        Some(comment) => NewNotification {
+            kind: NotificationKind::Mentioned,
+            card_id: Some(card.id),
+            actor_id: Some(&comment.author_id),
+            message: format!(
+                "{} mentioned you in a comment on {}: {}",
+                comment.author_id, card.card_key, card.title
+            ),
+            dedup_key: format!("mentioned:comment:{}", comment.id),
+        },
+        None => NewNotification {
+            kind: NotificationKind::Mentioned,
+            card_id: Some(card.id),
+            actor_id: None,
+            message: format!("You were mentioned in {}: {}", card.card_key, card.title),
+            dedup_key: format!("mentioned:card:{}", card.id),
+        },
+    };
+    notifications::notify(conn, &recipients, notification).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(text: &str) -> Vec<String> {
+        parse_mentions(text).into_iter().map(|(name, _, _)| name).collect()
+    }
+
+    #[test]
+    fn mentions_have_character_spans() {
+        assert_eq!(
+            parse_mentions("@alice and @bob.smith"),
+            vec![("alice".to_string(), 0, 6), ("bob.smith".to_string(), 11, 21)]
+        );
+        // Spans count characters, not bytes
+        assert_eq!(parse_mentions("héllo @zoë"), vec![("zoë".to_string(), 6, 10)]);
+    }
+
+    #[test]
+    fn trailing_punctuation_is_not_part_of_the_name() {
+        assert_eq!(names("thanks @alice."), vec!["alice"]);
+        assert_eq!(names("@bob-- see above"), vec!["bob"]);
+        assert_eq!(names("(@carol), @dave!"), vec!["carol", "dave"]);
+        assert_eq!(parse_mentions("ask @alice..."), vec![("alice".to_string(), 4, 10)]);
+    }
+
+    #[test]
+    fn at_signs_inside_words_or_alone_are_not_mentions() {
+        assert!(names("mail alice@example.com").is_empty());
+        assert!(names("@ @. @- a @").is_empty());
+        assert_eq!(names("@@alice"), vec!["alice"]);
+    }
+
+    #[test]
+    fn members_match_exactly_before_ignoring_case() {
+        let members = vec!["Alice".to_string(), "alice".to_string(), "Bob".to_string()];
+        assert_eq!(find_member(&members, "alice").map(String::as_str), Some("alice"));
+        assert_eq!(find_member(&members, "ALICE").map(String::as_str), Some("Alice"));
+        assert_eq!(find_member(&members, "bob").map(String::as_str), Some("Bob"));
+        assert_eq!(find_member(&members, "carol"), None);
+    }
+}
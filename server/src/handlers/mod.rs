@@ -1,4 +1,31 @@
+pub mod automation;
 pub mod boards;
+pub mod card_keys;
+pub mod card_lifecycle;
 pub mod cards;
+pub mod collab;
 pub mod columns;
+pub mod comments;
 pub mod columns_bulk;
+pub mod columns_move;
+pub mod copy;
+pub mod email;
+pub mod export;
+pub mod git;
+pub mod history;
+pub mod jobs;
+pub mod lanes;
+pub mod markdown;
+pub mod mentions;
+pub mod notifications;
+pub mod presence;
+pub mod ranks;
+pub mod recurrence;
+pub mod revisions;
+pub mod rollups;
+pub mod sync;
+pub mod templates;
+pub mod time_entries;
+pub mod users;
+pub mod webhooks;
+pub mod wip;
@@ -0,0 +1,279 @@
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::cards::get_card_by_id;
+use crate::models::{
+    Card, MarkAllReadRequest, Notification, NotificationKind, NotificationPreference, NotificationQuery,
+    UpdateNotificationPreferencesRequest,
+};
+
+const NOTIFICATION_COLUMNS: &str = "id, user_id, kind, card_id, actor_id, message, read_at, created_at";
+
+/// Inbox page size when the client does not ask for one
+const DEFAULT_INBOX_LIMIT: i64 = 50;
+
+const MAX_INBOX_LIMIT: i64 = 200;
+
+/// Cards due within this many hours count as due soon
+const DUE_SOON_HOURS: i32 = 24;
+
+/// Cards overdue for longer than this are not reported any more
+const OVERDUE_WINDOW_DAYS: i32 = 7;
+
+/// A notification to deliver to several users
+pub struct NewNotification<'a> {
+    pub kind: NotificationKind,
+    pub card_id: Option<i32>,
+    /// Never notified about their own action
+    pub actor_id: Option<&'a str>,
+    pub message: String,
+    /// Identifies the change; a user gets one notification per key
+    pub dedup_key: String,
+}
+
+/// Deliver a notification to each recipient that has not opted out of its
+/// kind and has not been notified about the same change
+pub async fn notify(
+    conn: &mut PgConnection,
+    recipients: &[String],
+    notification: NewNotification<'_>,
+) -> Result<(), AppError> {
+    if recipients.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "INSERT INTO notification (user_id, kind, card_id, actor_id, message, dedup_key)
+         SELECT DISTINCT r.user_id, $2, $3, $4, $5, $6
+         FROM unnest($1::TEXT[]) AS r(user_id)
+         WHERE r.user_id <> COALESCE($4, '')
+           AND NOT EXISTS (
+               SELECT 1 FROM notification_preference p
+               WHERE p.user_id = r.user_id AND p.kind = $2 AND NOT p.enabled
+           )
+         ON CONFLICT (user_id, dedup_key) DO NOTHING"
+    )
+    .bind(recipients)
+    .bind(notification.kind)
+    .bind(notification.card_id)
+    .bind(notification.actor_id)
+    .bind(&notification.message)
+    .bind(&notification.dedup_key)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Notify a card's assignee that they were assigned, and make them watch it
+pub async fn notify_assignment(conn: &mut PgConnection, card: &Card) -> Result<(), AppError> {
+    let Some(assignee_id) = card.assignee_id.clone() else {
+        return Ok(());
+    };
+
+    watch_card(&mut *conn, card.id, &assignee_id).await?;
+
+    let notification = NewNotification {
+        kind: NotificationKind::Assigned,
+        card_id: Some(card.id),
+        actor_id: None,
+        message: format!("You were assigned to {}: {}", card.card_key, card.title),
+        dedup_key: format!("assigned:{}:{}", card.id, card.updated_at.timestamp_micros()),
+    };
+    notify(conn, &[assignee_id], notification).await
+}
+
+/// Get the users watching a card
+pub async fn get_watchers(conn: &mut PgConnection, card_id: i32) -> Result<Vec<String>, AppError> {
+    let watchers: Vec<String> = sqlx::query_scalar(
+        "SELECT user_id FROM card_watcher WHERE card_id = $1 ORDER BY created_at ASC, user_id ASC"
+    )
+    .bind(card_id)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(watchers)
+}
+
+/// Make a user watch a card; watching twice is a no-op
+pub async fn watch_card(conn: &mut PgConnection, card_id: i32, user_id: &str) -> Result<(), AppError> {
+    if user_id.trim().is_empty() {
+        return Err(AppError::ValidationError("userId is required".to_string()));
+    }
+
+    sqlx::query("INSERT INTO card_watcher (card_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(card_id)
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Get the users watching a card
+pub async fn get_card_watchers(pool: &DbPool, card_id: i32) -> Result<Vec<String>, AppError> {
+    get_card_by_id(pool, card_id).await?;
+    let mut conn = pool.acquire().await?;
+    get_watchers(&mut conn, card_id).await
+}
+
+/// Make a user watch a card, returning the card's watchers
+pub async fn add_watcher(pool: &DbPool, card_id: i32, user_id: &str) -> Result<Vec<String>, AppError> {
+    get_card_by_id(pool, card_id).await?;
+    let mut conn = pool.acquire().await?;
+    watch_card(&mut conn, card_id, user_id).await?;
+    get_watchers(&mut conn, card_id).await
+}
+
+/// Stop a user watching a card
+pub async fn unwatch_card(pool: &DbPool, card_id: i32, user_id: &str) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM card_watcher WHERE card_id = $1 AND user_id = $2")
+        .bind(card_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("{} is not watching card {}", user_id, card_id)));
+    }
+
+    Ok(())
+}
+
+/// Get a user's notifications, newest first
+pub async fn get_notifications(pool: &DbPool, query: &NotificationQuery) -> Result<Vec<Notification>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_INBOX_LIMIT).clamp(1, MAX_INBOX_LIMIT);
+
+    let notifications = sqlx::query_as::<_, Notification>(&format!(
+        "SELECT {NOTIFICATION_COLUMNS}
+         FROM notification
+         WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3"
+    ))
+    .bind(&query.user_id)
+    .bind(query.unread_only)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(notifications)
+}
+
+/// Mark a notification read or unread
+pub async fn set_read(pool: &DbPool, id: i32, read: bool) -> Result<Notification, AppError> {
+    let notification = sqlx::query_as::<_, Notification>(&format!(
+        "UPDATE notification
+         SET read_at = CASE WHEN $2 THEN COALESCE(read_at, NOW()) END
+         WHERE id = $1
+         RETURNING {NOTIFICATION_COLUMNS}"
+    ))
+    .bind(id)
+    .bind(read)
+    .fetch_optional(pool)
+    .await?;
+
+    notification.ok_or_else(|| AppError::NotFound(format!("Notification with id {} not found", id)))
+}
+
+/// Mark all of a user's notifications read. Returns how many changed.
+pub async fn mark_all_read(pool: &DbPool, req: MarkAllReadRequest) -> Result<u64, AppError> {
+    let result = sqlx::query("UPDATE notification SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL")
+        .bind(&req.user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Get a user's setting for every notification kind
+pub async fn get_preferences(pool: &DbPool, user_id: &str) -> Result<Vec<NotificationPreference>, AppError> {
+    let stored: Vec<NotificationPreference> = sqlx::query_as(
+        "SELECT kind, enabled FROM notification_preference WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let preferences = NotificationKind::ALL
+        .iter()
+        .map(|kind| NotificationPreference {
+            kind: *kind,
+            enabled: stored
+                .iter()
+                .find(|preference| preference.kind == *kind)
+                .is_none_or(|preference| preference.enabled),
+        })
+        .collect();
+
+    Ok(preferences)
+}
+
+/// Change which notification kinds a user receives
+pub async fn update_preferences(
+    pool: &DbPool,
+    req: UpdateNotificationPreferencesRequest,
+) -> Result<Vec<NotificationPreference>, AppError> {
+    if req.user_id.trim().is_empty() {
+        return Err(AppError::ValidationError("userId is required".to_string()));
+    }
+
+    let kinds: Vec<NotificationKind> = req.preferences.iter().map(|preference| preference.kind).collect();
+    let enabled: Vec<bool> = req.preferences.iter().map(|preference| preference.enabled).collect();
+
+    sqlx::query(
+        "INSERT INTO notification_preference (user_id, kind, enabled)
+         SELECT $1, p.kind, p.enabled
+         FROM unnest($2::notification_kind[], $3::BOOLEAN[]) AS p(kind, enabled)
+         ON CONFLICT (user_id, kind) DO UPDATE SET enabled = EXCLUDED.enabled"
+    )
+    .bind(&req.user_id)
+    .bind(&kinds)
+    .bind(&enabled)
+    .execute(pool)
+    .await?;
+
+    get_preferences(pool, &req.user_id).await
+}
+
+/// Notify assignees and watchers of open cards that are due soon or have
+/// recently become overdue. Each due date is reported once per kind.
+/// Run by the scheduler; returns the number of notifications created.
+pub async fn send_due_date_reminders(pool: &DbPool) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        "WITH due AS (
+             SELECT c.id, c.card_key, c.title, c.due_date,
+                    CASE WHEN c.due_date <= NOW() THEN 'overdue' ELSE 'due_soon' END::notification_kind AS kind
+             FROM card c
+             WHERE c.completed_at IS NULL
+               AND c.due_date > NOW() - make_interval(days => $2)
+               AND c.due_date <= NOW() + make_interval(hours => $1)
+         ),
+         recipients AS (
+             SELECT due.*, c.assignee_id AS user_id FROM due JOIN card c ON c.id = due.id
+             WHERE c.assignee_id IS NOT NULL
+             UNION
+             SELECT due.*, w.user_id FROM due JOIN card_watcher w ON w.card_id = due.id
+         )
+         INSERT INTO notification (user_id, kind, card_id, message, dedup_key)
+         SELECT r.user_id, r.kind, r.id,
+                CASE r.kind
+                    WHEN 'overdue' THEN r.card_key || ' is overdue: ' || r.title
+                    ELSE r.card_key || ' is due soon: ' || r.title
+                END,
+                r.kind::TEXT || ':' || r.id || ':' || EXTRACT(EPOCH FROM r.due_date)::BIGINT
+         FROM recipients r
+         WHERE NOT EXISTS (
+             SELECT 1 FROM notification_preference p
+             WHERE p.user_id = r.user_id AND p.kind = r.kind AND NOT p.enabled
+         )
+         ON CONFLICT (user_id, dedup_key) DO NOTHING"
+    )
+    .bind(DUE_SOON_HOURS)
+    .bind(OVERDUE_WINDOW_DAYS)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
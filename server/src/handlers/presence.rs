@@ -0,0 +1,324 @@
+//! Who is viewing each board, and which card they have open.
+//!
+//! Viewers connect to a board's presence channel, a WebSocket carrying JSON
+//! messages. Each connection has a row in `board_presence` for as long as it
+//! is open and keeps sending heartbeats; a user with several connections is
+//! shown once. Rows not heard from within `PRESENCE_TTL_SECS` are ignored and
+//! removed by the `presence_expiry` job, so viewers of a crashed instance or
+//! a silent client disappear.
+//!
+//! Every change to a board's viewers is announced with a `board_presence`
+//! notification carrying the board ID. Each instance runs [`run_listener`],
+//! which sends the board's current viewers to the clients connected to it,
+//! so viewers see each other whichever instance serves them.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket};
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::sync::failure_message;
+use crate::models::{BoardViewer, PresenceClientMessage, PresenceServerMessage};
+
+/// Notification channel announcing changes to a board's viewers
+const CHANNEL: &str = "board_presence";
+
+/// A connection not heard from for this long no longer counts as viewing
+const PRESENCE_TTL_SECS: f64 = 60.0;
+
+/// Delay before listening again after the notification connection fails
+const RELISTEN_DELAY: Duration = Duration::from_secs(5);
+
+/// Channels to the clients connected to this instance, by board. Each
+/// message is a serialised `PresenceServerMessage`.
+type Channels = HashMap<i32, broadcast::Sender<String>>;
+
+fn channels() -> &'static Mutex<Channels> {
+    static CHANNELS: OnceLock<Mutex<Channels>> = OnceLock::new();
+    CHANNELS.get_or_init(Default::default)
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn subscribe(board_id: i32) -> broadcast::Receiver<String> {
+    lock(channels())
+        .entry(board_id)
+        .or_insert_with(|| broadcast::channel(16).0)
+        .subscribe()
+}
+
+/// Drop a board's channel once its last client has gone
+fn unsubscribe(board_id: i32, receiver: broadcast::Receiver<String>) {
+    drop(receiver);
+    let mut channels = lock(channels());
+    if channels.get(&board_id).is_some_and(|sender| sender.receiver_count() == 0) {
+        channels.remove(&board_id);
+    }
+}
+
+/// The users viewing a board, longest viewing first
+pub async fn get_presence(pool: &DbPool, board_id: i32) -> Result<Vec<BoardViewer>, AppError> {
+    let viewers = sqlx::query_as::<_, BoardViewer>(
+        "SELECT * FROM (
+             SELECT DISTINCT ON (p.user_id)
+                    p.user_id, COALESCE(up.display_name, p.user_id) AS display_name,
+                    c.id AS card_id, c.card_key,
+                    MIN(p.connected_at) OVER (PARTITION BY p.user_id) AS since,
+                    MAX(p.last_seen_at) OVER (PARTITION BY p.user_id) AS last_seen_at
+             FROM board_presence p
+             LEFT JOIN user_profile up ON up.user_id = p.user_id
+             LEFT JOIN card c ON c.id = p.card_id
+                 AND c.list_id IN (SELECT id FROM board_column WHERE board_id = p.board_id)
+             WHERE p.board_id = $1 AND p.last_seen_at > NOW() - make_interval(secs => $2)
+             ORDER BY p.user_id, p.focused_at DESC
+         ) viewers
+         ORDER BY since, user_id"
+    )
+    .bind(board_id)
+    .bind(PRESENCE_TTL_SECS)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(viewers)
+}
+
+async fn announce(pool: &DbPool, board_id: i32) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_notify($1, $2::TEXT)")
+        .bind(CHANNEL)
+        .bind(board_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Record a connection viewing a board. Returns its ID.
+async fn join(pool: &DbPool, board_id: i32, user_id: &str) -> Result<i64, AppError> {
+    let id = sqlx::query_scalar("INSERT INTO board_presence (board_id, user_id) VALUES ($1, $2) RETURNING id")
+        .bind(board_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    announce(pool, board_id).await?;
+
+    Ok(id)
+}
+
+async fn leave(pool: &DbPool, board_id: i32, id: i64) -> Result<(), AppError> {
+    let removed = sqlx::query("DELETE FROM board_presence WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    if removed > 0 {
+        announce(pool, board_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Keep a connection's presence fresh. A connection whose presence has
+/// already expired joins again; returns its (possibly new) ID.
+async fn heartbeat(pool: &DbPool, board_id: i32, user_id: &str, id: i64) -> Result<i64, AppError> {
+    let updated = sqlx::query(
+        "UPDATE board_presence SET last_seen_at = NOW()
+         WHERE id = $1 AND last_seen_at > NOW() - make_interval(secs => $2)"
+    )
+    .bind(id)
+    .bind(PRESENCE_TTL_SECS)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    if updated > 0 {
+        return Ok(id);
+    }
+
+    leave(pool, board_id, id).await?;
+    join(pool, board_id, user_id).await
+}
+
+/// Set the card a connection has open, which must be on its board
+async fn focus(pool: &DbPool, board_id: i32, id: i64, card_id: Option<i32>) -> Result<(), AppError> {
+    if let Some(card_id) = card_id {
+        let on_board: bool = sqlx::query_scalar(
+            "SELECT EXISTS(
+                 SELECT 1 FROM card c JOIN board_column bc ON bc.id = c.list_id
+                 WHERE c.id = $1 AND bc.board_id = $2
+             )"
+        )
+        .bind(card_id)
+        .bind(board_id)
+        .fetch_one(pool)
+        .await?;
+        if !on_board {
+            return Err(AppError::ValidationError(format!("Card {} is not on board {}", card_id, board_id)));
+        }
+    }
+
+    // focused_at only moves when the card changes
+    let changed = sqlx::query_scalar(
+        "UPDATE board_presence
+         SET card_id = $2,
+             focused_at = CASE WHEN card_id IS DISTINCT FROM $2 THEN NOW() ELSE focused_at END,
+             last_seen_at = NOW()
+         WHERE id = $1
+         RETURNING focused_at = NOW()"
+    )
+    .bind(id)
+    .bind(card_id)
+    .fetch_optional(pool)
+    .await?;
+    if changed == Some(true) {
+        announce(pool, board_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Remove expired presence. Returns the number of connections removed.
+pub async fn expire_stale(pool: &DbPool) -> Result<usize, AppError> {
+    let board_ids: Vec<i32> = sqlx::query_scalar(
+        "DELETE FROM board_presence WHERE last_seen_at <= NOW() - make_interval(secs => $1) RETURNING board_id"
+    )
+    .bind(PRESENCE_TTL_SECS)
+    .fetch_all(pool)
+    .await?;
+
+    let mut boards = board_ids.clone();
+    boards.sort_unstable();
+    boards.dedup();
+    for board_id in boards {
+        announce(pool, board_id).await?;
+    }
+
+    Ok(board_ids.len())
+}
+
+async fn presence_message(pool: &DbPool, board_id: i32) -> Result<String, AppError> {
+    let viewers = get_presence(pool, board_id).await?;
+    let message = PresenceServerMessage::Presence { board_id, viewers };
+    serde_json::to_string(&message).map_err(|err| AppError::InternalError(err.to_string()))
+}
+
+/// Send the current viewers of a board to its clients on this instance
+/// whenever any instance announces a change, until the process exits
+pub async fn run_listener(pool: DbPool) {
+    loop {
+        if let Err(err) = relay_announcements(&pool).await {
+            tracing::warn!("Presence listener failed: {:?}", err);
+        }
+        tokio::time::sleep(RELISTEN_DELAY).await;
+    }
+}
+
+async fn relay_announcements(pool: &DbPool) -> Result<(), AppError> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CHANNEL).await?;
+
+    loop {
+        let notification = listener.recv().await?;
+        let Ok(board_id) = notification.payload().parse::<i32>() else {
+            continue;
+        };
+        let sender = lock(channels()).get(&board_id).cloned();
+        if let Some(sender) = sender {
+            let _ = sender.send(presence_message(pool, board_id).await?);
+        }
+    }
+}
+
+/// Serve a client on a board's presence channel until it leaves or
+/// disconnects
+pub async fn watch_board(pool: DbPool, board_id: i32, user_id: String, mut socket: WebSocket) {
+    let mut updates = subscribe(board_id);
+    let result = match join(&pool, board_id, &user_id).await {
+        Ok(mut id) => {
+            let result = serve(&pool, board_id, &user_id, &mut id, &mut updates, &mut socket).await;
+            if let Err(err) = leave(&pool, board_id, id).await {
+                tracing::warn!("Failed to remove presence of {} on board {}: {:?}", user_id, board_id, err);
+            }
+            result
+        }
+        Err(err) => Err(err),
+    };
+    unsubscribe(board_id, updates);
+
+    if let Err(err) = result {
+        let _ = send(&mut socket, &PresenceServerMessage::Error { message: failure_message(err) }).await;
+        let _ = socket.send(Message::Close(None)).await;
+    }
+}
+
+/// Handle a client's messages and relay presence changes to it; the
+/// announcement of its own join brings the first list of viewers
+async fn serve(
+    pool: &DbPool,
+    board_id: i32,
+    user_id: &str,
+    id: &mut i64,
+    updates: &mut broadcast::Receiver<String>,
+    socket: &mut WebSocket,
+) -> Result<(), AppError> {
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let message = serde_json::from_str::<PresenceClientMessage>(&text)
+                        .map_err(|err| AppError::ValidationError(format!("Invalid presence message: {}", err)));
+                    let result = match message {
+                        Ok(PresenceClientMessage::Leave) => return Ok(()),
+                        Ok(PresenceClientMessage::Heartbeat) => {
+                            heartbeat(pool, board_id, user_id, *id).await.map(|current| *id = current)
+                        }
+                        Ok(PresenceClientMessage::Focus { card_id }) => {
+                            match heartbeat(pool, board_id, user_id, *id).await {
+                                Ok(current) => {
+                                    *id = current;
+                                    focus(pool, board_id, *id, card_id).await
+                                }
+                                Err(err) => Err(err),
+                            }
+                        }
+                        Err(err) => Err(err),
+                    };
+                    match result {
+                        Ok(()) => {}
+                        Err(AppError::ValidationError(message)) => {
+                            send(socket, &PresenceServerMessage::Error { message }).await?;
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+                Some(Ok(Message::Binary(_))) => {
+                    let message = "Presence messages must be JSON text".to_string();
+                    send(socket, &PresenceServerMessage::Error { message }).await?;
+                }
+                Some(Ok(Message::Ping(_) | Message::Pong(_))) => {}
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return Ok(()),
+            },
+            update = updates.recv() => match update {
+                Ok(text) => socket
+                    .send(Message::Text(text))
+                    .await
+                    .map_err(|err| AppError::InternalError(format!("Failed to send presence: {}", err)))?,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &PresenceServerMessage) -> Result<(), AppError> {
+    let text = serde_json::to_string(message).map_err(|err| AppError::InternalError(err.to_string()))?;
+    socket
+        .send(Message::Text(text))
+        .await
+        .map_err(|err| AppError::InternalError(format!("Failed to send presence: {}", err)))
+}
@@ -0,0 +1,473 @@
+//! Fractional ranks for ordering cards and columns.
+//!
+//! A rank is a base-62 string read as the digits of a fraction in `(0, 1)`,
+//! compared bytewise (`COLLATE "C"` in Postgres). A key strictly between any
+//! two ranks always exists, so moving one item rewrites only that item's
+//! rank. Ranks never end in the zero digit, which keeps them unique per value.
+//! Adding at either end steps by one unit of the neighbour's last digit and
+//! doubles the length once that runs out, so appending `n` cards gives keys of
+//! about `2 * log62(n)` digits. Repeated inserts at the same spot between two
+//! cards make ranks longer; [`rebalance_long_ranks`] periodically rewrites such
+//! cells with short, evenly spaced ranks.
+
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Cells with a rank longer than this are rewritten by the rebalancer
+const MAX_RANK_LEN: i32 = 12;
+
+fn digit_value(digit: u8) -> usize {
+    DIGITS.iter().position(|d| *d == digit).unwrap_or(0)
+}
+
+/// Midpoint of two digit strings, `b = None` meaning the upper bound 1
+fn midpoint(a: &[u8], b: Option<&[u8]>) -> Vec<u8> {
+    if let Some(b) = b {
+        let mut n = 0;
+        while n < b.len() && a.get(n).copied().unwrap_or(DIGITS[0]) == b[n] {
+            n += 1;
+        }
+        if n > 0 {
+            let mut key = b[..n].to_vec();
+            key.extend(midpoint(a.get(n..).unwrap_or_default(), Some(&b[n..])));
+            return key;
+        }
+    }
+
+    let digit_a = a.first().map_or(0, |d| digit_value(*d));
+    let digit_b = b.and_then(|b| b.first()).map_or(DIGITS.len(), |d| digit_value(*d));
+
+    if digit_b - digit_a > 1 {
+        vec![DIGITS[(digit_a + digit_b).div_ceil(2)]]
+    } else if let Some(b) = b.filter(|b| b.len() > 1) {
+        b[..1].to_vec()
+    } else {
+        let mut key = vec![DIGITS[digit_a]];
+        key.extend(midpoint(a.get(1..).unwrap_or_default(), None));
+        key
+    }
+}
+
+/// `a` plus one unit of its last digit, skipping a trailing zero digit.
+/// None when `a` is all top digits.
+fn increment(a: &[u8]) -> Option<Vec<u8>> {
+    let mut values: Vec<usize> = a.iter().map(|d| digit_value(*d)).collect();
+    loop {
+        let position = values.iter().rposition(|v| *v + 1 < DIGITS.len())?;
+        values[position] += 1;
+        values[position + 1..].fill(0);
+        if values.last() != Some(&0) {
+            return Some(values.into_iter().map(|v| DIGITS[v]).collect());
+        }
+    }
+}
+
+/// `b` minus one unit of its last digit, skipping a trailing zero digit.
+/// None when nothing above zero is left at `b`'s length.
+fn decrement(b: &[u8]) -> Option<Vec<u8>> {
+    let mut values: Vec<usize> = b.iter().map(|d| digit_value(*d)).collect();
+    loop {
+        let position = values.iter().rposition(|v| *v > 0)?;
+        values[position] -= 1;
+        values[position + 1..].fill(DIGITS.len() - 1);
+        if values.iter().all(|v| *v == 0) {
+            return None;
+        }
+        if values.last() != Some(&0) {
+            return Some(values.into_iter().map(|v| DIGITS[v]).collect());
+        }
+    }
+}
+
+/// A rank after `a` that leaves room for many more: the next key of the same
+/// length, or once those are used up, the first key of twice the length
+fn rank_after(a: &[u8]) -> Vec<u8> {
+    increment(a).unwrap_or_else(|| {
+        let mut key = a.to_vec();
+        key.resize(2 * a.len() - 1, DIGITS[0]);
+        key.push(DIGITS[1]);
+        key
+    })
+}
+
+/// A rank before `b`, mirroring [`rank_after`]
+fn rank_before(b: &[u8]) -> Vec<u8> {
+    decrement(b).unwrap_or_else(|| {
+        let mut key = vec![DIGITS[0]; b.len()];
+        key.resize(2 * b.len(), DIGITS[DIGITS.len() - 1]);
+        key
+    })
+}
+
+/// A rank strictly between `before` and `after`; `None` means unbounded
+pub fn rank_between(before: Option<&str>, after: Option<&str>) -> String {
+    let a = before.unwrap_or_default();
+    // Out-of-order bounds can only come from corrupted data; rank after `a`
+    let b = after.filter(|b| *b > a);
+    let key = match b {
+        None if !a.is_empty() => rank_after(a.as_bytes()),
+        Some(b) if a.is_empty() => rank_before(b.as_bytes()),
+        _ => midpoint(a.as_bytes(), b.map(str::as_bytes)),
+    };
+    String::from_utf8(key).expect("rank digits are ASCII")
+}
+
+/// `count` ascending ranks strictly between `before` and `after`
+pub fn ranks_between(before: Option<&str>, after: Option<&str>, count: usize) -> Vec<String> {
+    match count {
+        0 => Vec::new(),
+        1 => vec![rank_between(before, after)],
+        _ if after.is_none() => {
+            let mut ranks: Vec<String> = Vec::with_capacity(count);
+            for _ in 0..count {
+                let previous = ranks.last().map(String::as_str).or(before);
+                ranks.push(rank_between(previous, None));
+            }
+            ranks
+        }
+        _ => {
+            let middle = rank_between(before, after);
+            let left = count / 2;
+            let mut ranks = ranks_between(before, Some(&middle), left);
+            ranks.push(middle.clone());
+            ranks.extend(ranks_between(Some(&middle), after, count - left - 1));
+            ranks
+        }
+    }
+}
+
+/// `count` short, evenly spaced ranks, for filling an empty cell
+pub fn spread_ranks(count: usize) -> Vec<String> {
+    let base = DIGITS.len() as u128;
+    let mut width = 1;
+    while base.pow(width) <= count as u128 + 1 {
+        width += 1;
+    }
+    let span = base.pow(width);
+
+    (1..=count as u128)
+        .map(|i| {
+            let mut value = i * span / (count as u128 + 1);
+            let mut digits = vec![DIGITS[0]; width as usize];
+            for slot in digits.iter_mut().rev() {
+                *slot = DIGITS[(value % base) as usize];
+                value /= base;
+            }
+            while digits.last() == Some(&DIGITS[0]) {
+                digits.pop();
+            }
+            String::from_utf8(digits).expect("rank digits are ASCII")
+        })
+        .collect()
+}
+
+/// Pick the neighbours of `index` from up to two ranks read at offset `index - 1`
+fn rank_at(index: i32, neighbours: &[String], last: Option<&str>) -> String {
+    match (index, neighbours) {
+        (0, [first, ..]) => rank_between(None, Some(first)),
+        (_, [before, after]) => rank_between(Some(before), Some(after)),
+        _ => rank_between(last, None),
+    }
+}
+
+/// Compute the rank for a card inserted at `index` in a (list, lane) cell,
+/// or at its end. `moving` is left out of the cell so a card can be
+/// repositioned within it. Locks the list so concurrent inserts into it
+/// cannot pick the same rank.
+pub async fn card_rank_at(
+    conn: &mut PgConnection,
+    list_id: i32,
+    lane_id: i32,
+    index: Option<i32>,
+    moving: Option<i32>,
+) -> Result<String, AppError> {
+    sqlx::query("SELECT id FROM board_column WHERE id = $1 FOR UPDATE")
+        .bind(list_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let last: Option<String> = sqlx::query_scalar(
+        "SELECT MAX(rank) FROM card WHERE list_id = $1 AND lane_id = $2 AND id IS DISTINCT FROM $3"
+    )
+    .bind(list_id)
+    .bind(lane_id)
+    .bind(moving)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let Some(index) = index.map(|i| i.max(0)) else {
+        return Ok(rank_between(last.as_deref(), None));
+    };
+
+    let neighbours: Vec<String> = sqlx::query_scalar(
+        "SELECT rank FROM card
+         WHERE list_id = $1 AND lane_id = $2 AND id IS DISTINCT FROM $3
+         ORDER BY rank ASC
+         OFFSET $4 LIMIT $5"
+    )
+    .bind(list_id)
+    .bind(lane_id)
+    .bind(moving)
+    .bind(i64::from(index.max(1) - 1))
+    .bind(if index == 0 { 1_i64 } else { 2 })
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rank_at(index, &neighbours, last.as_deref()))
+}
+
+/// Compute the rank for a column inserted at `index` on a board, or at its
+/// end. `moving` is left out so a column can be repositioned. Locks the board
+/// so concurrent inserts cannot pick the same rank.
+pub async fn column_rank_at(
+    conn: &mut PgConnection,
+    board_id: i32,
+    index: Option<i32>,
+    moving: Option<i32>,
+) -> Result<String, AppError> {
+    sqlx::query("SELECT id FROM board WHERE id = $1 FOR UPDATE")
+        .bind(board_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let last: Option<String> = sqlx::query_scalar(
+        "SELECT MAX(rank) FROM board_column WHERE board_id = $1 AND id IS DISTINCT FROM $2"
+    )
+    .bind(board_id)
+    .bind(moving)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let Some(index) = index.map(|i| i.max(0)) else {
+        return Ok(rank_between(last.as_deref(), None));
+    };
+
+    let neighbours: Vec<String> = sqlx::query_scalar(
+        "SELECT rank FROM board_column
+         WHERE board_id = $1 AND id IS DISTINCT FROM $2
+         ORDER BY rank ASC
+         OFFSET $3 LIMIT $4"
+    )
+    .bind(board_id)
+    .bind(moving)
+    .bind(i64::from(index.max(1) - 1))
+    .bind(if index == 0 { 1_i64 } else { 2 })
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rank_at(index, &neighbours, last.as_deref()))
+}
+
+/// Give cards new ranks, pairing `card_ids` and `ranks` by index
+pub async fn apply_card_ranks(conn: &mut PgConnection, card_ids: &[i32], ranks: &[String]) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE card AS c
+         SET rank = v.rank
+         FROM unnest($1::INT[], $2::TEXT[]) AS v(id, rank)
+         WHERE c.id = v.id"
+    )
+    .bind(card_ids)
+    .bind(ranks)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Give columns new ranks, pairing `column_ids` and `ranks` by index
+pub async fn apply_column_ranks(conn: &mut PgConnection, column_ids: &[i32], ranks: &[String]) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE board_column AS bc
+         SET rank = v.rank
+         FROM unnest($1::INT[], $2::TEXT[]) AS v(id, rank)
+         WHERE bc.id = v.id"
+    )
+    .bind(column_ids)
+    .bind(ranks)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Rewrite every cell holding an overly long rank with evenly spaced ranks.
+/// Order is preserved, so the derived positions do not change. Returns the
+/// number of cells rebalanced.
+pub async fn rebalance_long_ranks(pool: &DbPool) -> Result<usize, AppError> {
+    let card_cells: Vec<(i32, i32)> = sqlx::query_as(
+        "SELECT DISTINCT list_id, lane_id FROM card WHERE LENGTH(rank) > $1"
+    )
+    .bind(MAX_RANK_LEN)
+    .fetch_all(pool)
+    .await?;
+
+    for (list_id, lane_id) in &card_cells {
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT id FROM board_column WHERE id = $1 FOR UPDATE")
+            .bind(list_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let card_ids: Vec<i32> = sqlx::query_scalar(
+            "SELECT id FROM card WHERE list_id = $1 AND lane_id = $2 ORDER BY rank ASC"
+        )
+        .bind(list_id)
+        .bind(lane_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        apply_card_ranks(&mut tx, &card_ids, &spread_ranks(card_ids.len())).await?;
+        tx.commit().await?;
+    }
+
+    let column_boards: Vec<i32> = sqlx::query_scalar(
+        "SELECT DISTINCT board_id FROM board_column WHERE LENGTH(rank) > $1"
+    )
+    .bind(MAX_RANK_LEN)
+    .fetch_all(pool)
+    .await?;
+
+    for board_id in &column_boards {
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT id FROM board WHERE id = $1 FOR UPDATE")
+            .bind(board_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let column_ids: Vec<i32> = sqlx::query_scalar(
+            "SELECT id FROM board_column WHERE board_id = $1 ORDER BY rank ASC"
+        )
+        .bind(board_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        apply_column_ranks(&mut tx, &column_ids, &spread_ranks(column_ids.len())).await?;
+        tx.commit().await?;
+    }
+
+    Ok(card_cells.len() + column_boards.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_valid(ranks: &[String]) {
+        for rank in ranks {
+            assert!(!rank.is_empty() && !rank.ends_with('0'), "bad rank {:?}", rank);
+            assert!(rank.bytes().all(|d| DIGITS.contains(&d)), "bad rank {:?}", rank);
+        }
+        for pair in ranks.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} is not before {:?}", pair[0], pair[1]);
+        }
+    }
+
+    fn max_len(ranks: &[String]) -> usize {
+        ranks.iter().map(String::len).max().unwrap_or_default()
+    }
+
+    #[test]
+    fn midpoint_is_strictly_between() {
+        for (a, b) in [("V", "W"), ("1", "2"), ("V", "V1"), ("Vz", "W"), ("zz", "zz1"), ("0001", "01")] {
+            let rank = rank_between(Some(a), Some(b));
+            assert!(a < rank.as_str() && rank.as_str() < b, "{:?} not between {:?} and {:?}", rank, a, b);
+            assert!(!rank.ends_with('0'));
+        }
+        assert_eq!(rank_between(Some("V"), Some("W")), "VV");
+        assert_eq!(rank_between(None, None), "V");
+    }
+
+    #[test]
+    fn out_of_order_bounds_rank_after_before() {
+        let rank = rank_between(Some("k"), Some("V"));
+        assert!(rank.as_str() > "k");
+    }
+
+    #[test]
+    fn appending_grows_keys_logarithmically() {
+        let mut ranks = vec![rank_between(None, None)];
+        for _ in 0..20_000 {
+            let last = ranks.last().map(String::as_str);
+            ranks.push(rank_between(last, None));
+        }
+        assert_valid(&ranks);
+        assert!(max_len(&ranks[..60]) <= 2);
+        assert!(max_len(&ranks[..200]) <= 4);
+        assert!(max_len(&ranks) <= 8);
+        assert!(max_len(&ranks) as i32 <= MAX_RANK_LEN);
+    }
+
+    #[test]
+    fn prepending_grows_keys_logarithmically() {
+        let mut ranks = vec![rank_between(None, None)];
+        for _ in 0..20_000 {
+            let first = ranks.first().map(String::as_str);
+            ranks.insert(0, rank_between(None, first));
+        }
+        assert_valid(&ranks);
+        assert!(max_len(&ranks[ranks.len() - 200..]) <= 4);
+        assert!(max_len(&ranks) <= 8);
+    }
+
+    #[test]
+    fn appending_after_a_long_rank_keeps_its_length() {
+        let mut ranks = vec!["VVVVVVVVVV".to_string()];
+        for _ in 0..500 {
+            ranks.push(rank_between(ranks.last().map(String::as_str), None));
+        }
+        assert_valid(&ranks);
+        assert_eq!(max_len(&ranks), 10);
+    }
+
+    #[test]
+    fn ranks_between_are_ordered() {
+        for (before, after) in [(None, None), (Some("V"), None), (None, Some("V")), (Some("V"), Some("W"))] {
+            let ranks = ranks_between(before, after, 300);
+            assert_eq!(ranks.len(), 300);
+            let mut bounded: Vec<String> = before.into_iter().map(str::to_string).collect();
+            bounded.extend(ranks);
+            bounded.extend(after.map(str::to_string));
+            assert_valid(&bounded);
+        }
+        assert!(max_len(&ranks_between(Some("V"), None, 300)) <= 4);
+    }
+
+    #[test]
+    fn random_inserts_stay_ordered() {
+        let mut ranks: Vec<String> = Vec::new();
+        let mut seed: u64 = 42;
+        for _ in 0..2_000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let index = (seed >> 33) as usize % (ranks.len() + 1);
+            let before = index.checked_sub(1).map(|i| ranks[i].as_str());
+            let after = ranks.get(index).map(String::as_str);
+            let rank = rank_between(before, after);
+            ranks.insert(index, rank);
+        }
+        assert_valid(&ranks);
+    }
+
+    #[test]
+    fn spread_ranks_are_short_and_ordered() {
+        for count in [1, 2, 61, 62, 100, 5_000] {
+            let ranks = spread_ranks(count);
+            assert_eq!(ranks.len(), count);
+            assert_valid(&ranks);
+        }
+        assert_eq!(max_len(&spread_ranks(61)), 1);
+        assert_eq!(max_len(&spread_ranks(100)), 2);
+    }
+
+    #[test]
+    fn rank_at_picks_neighbours() {
+        let neighbours = vec!["V".to_string(), "k".to_string()];
+        assert!(rank_at(0, &neighbours[..1], Some("z")).as_str() < "V");
+        let between = rank_at(3, &neighbours, Some("z"));
+        assert!("V" < between.as_str() && between.as_str() < "k");
+        assert!(rank_at(9, &neighbours[..1], Some("k")).as_str() > "k");
+    }
+}
@@ -0,0 +1,536 @@
+use chrono::{DateTime, Datelike, Days, Duration, Months, NaiveDate, Utc};
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::card_lifecycle::{get_column_category, stamps_on_enter};
+use crate::handlers::cards::{get_card_by_id, CARD_COLUMNS};
+use crate::handlers::{automation, card_keys, lanes, mentions, notifications, ranks, webhooks, wip};
+use crate::models::{
+    Card, CardRecurrence, CreateRecurrenceRequest, RecurrenceRule, RecurrenceStatus, RuleTrigger,
+    UpdateRecurrenceRequest, WebhookEvent,
+};
+
+const RECURRENCE_COLUMNS: &str = "id, template_card_id, list_id, rule, status, starts_at, ends_at, lead_days, \
+                                  next_due_at, last_card_id, occurrences, created_at, updated_at";
+
+/// Default number of days a card is created before its due date
+const DEFAULT_LEAD_DAYS: i32 = 1;
+
+/// Longest daily interval or wait after completion, in days
+const MAX_RECURRENCE_DAYS: u32 = 3650;
+
+/// Most days a card can be created before its due date
+const MAX_LEAD_DAYS: i32 = 365;
+
+fn at_time_of(date: NaiveDate, anchor: DateTime<Utc>) -> DateTime<Utc> {
+    date.and_time(anchor.time()).and_utc()
+}
+
+/// The first due date of a rule at or after `from`, never before `anchor`.
+/// After-completion rules are due at `from` for their first card only; later
+/// cards are scheduled from the completion of the previous one.
+pub fn occurrence_on_or_after(
+    rule: &RecurrenceRule,
+    anchor: DateTime<Utc>,
+    from: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let from = from.max(anchor);
+
+    match rule {
+        RecurrenceRule::Daily { interval } => {
+            let step = Duration::days(i64::from((*interval).max(1)));
+            let elapsed = (from - anchor).num_seconds();
+            let steps = (elapsed + step.num_seconds() - 1) / step.num_seconds();
+            anchor.checked_add_signed(step.checked_mul(i32::try_from(steps).ok()?)?)
+        }
+        RecurrenceRule::Weekly { weekdays } => (0..=7)
+            .filter_map(|offset| from.date_naive().checked_add_days(Days::new(offset)))
+            .map(|date| at_time_of(date, anchor))
+            .find(|due| *due >= from && weekdays.contains(&due.weekday().into())),
+        RecurrenceRule::Monthly { day } => {
+            let first_of_month = from.date_naive().with_day(1)?;
+            (0..=1)
+                .filter_map(|offset| first_of_month.checked_add_months(Months::new(offset)))
+                .filter_map(|month| {
+                    let day = (*day).clamp(1, u32::from(month.num_days_in_month()));
+                    month.with_day(day)
+                })
+                .map(|date| at_time_of(date, anchor))
+                .find(|due| *due >= from)
+        }
+        RecurrenceRule::AfterCompletion { .. } => Some(from),
+    }
+}
+
+/// Due date of the next card of a series whose schedule (re)starts now
+fn next_due_from_now(
+    rule: &RecurrenceRule,
+    starts_at: DateTime<Utc>,
+    occurrences: i32,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    match rule {
+        // Waits for the last card to be completed
+        RecurrenceRule::AfterCompletion { .. } if occurrences > 0 => None,
+        _ => occurrence_on_or_after(rule, starts_at, now),
+    }
+}
+
+fn validate_rule(rule: &RecurrenceRule) -> Result<(), AppError> {
+    match rule {
+        RecurrenceRule::Daily { interval } if !(1..=MAX_RECURRENCE_DAYS).contains(interval) => Err(
+            AppError::ValidationError(format!("Interval must be between 1 and {} days", MAX_RECURRENCE_DAYS)),
+        ),
+        RecurrenceRule::Weekly { weekdays } if weekdays.is_empty() => {
+            Err(AppError::ValidationError("At least one weekday is required".to_string()))
+        }
+        RecurrenceRule::Monthly { day } if !(1..=31).contains(day) => {
+            Err(AppError::ValidationError("Day of month must be between 1 and 31".to_string()))
+        }
+        RecurrenceRule::AfterCompletion { days } if !(1..=MAX_RECURRENCE_DAYS).contains(days) => {
+            Err(AppError::ValidationError(format!(
+                "Days after completion must be between 1 and {}",
+                MAX_RECURRENCE_DAYS
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_lead_days(lead_days: i32) -> Result<(), AppError> {
+    if !(0..=MAX_LEAD_DAYS).contains(&lead_days) {
+        return Err(AppError::ValidationError(format!(
+            "Lead days must be between 0 and {}",
+            MAX_LEAD_DAYS
+        )));
+    }
+    Ok(())
+}
+
+/// Check that the target list is on the template card's board
+async fn validate_list(conn: &mut PgConnection, template: &Card, list_id: i32) -> Result<(), AppError> {
+    let board_id = card_keys::get_board_id_for_list(&mut *conn, list_id).await?;
+    if card_keys::get_board_id_for_list(&mut *conn, template.list_id).await? != board_id {
+        return Err(AppError::ValidationError(format!(
+            "List {} is not on the board of card {}",
+            list_id, template.card_key
+        )));
+    }
+    Ok(())
+}
+
+/// Get the series a card is the template of
+pub async fn get_recurrence(pool: &DbPool, card_id: i32) -> Result<CardRecurrence, AppError> {
+    let recurrence = sqlx::query_as::<_, CardRecurrence>(&format!(
+        "SELECT {RECURRENCE_COLUMNS} FROM card_recurrence WHERE template_card_id = $1"
+    ))
+    .bind(card_id)
+    .fetch_optional(pool)
+    .await?;
+
+    recurrence.ok_or_else(|| AppError::NotFound("Card has no recurrence".to_string()))
+}
+
+/// Make a card the template of a recurring series. A card can have one
+/// series; an ended series is replaced.
+pub async fn create_recurrence(
+    pool: &DbPool,
+    card_id: i32,
+    req: CreateRecurrenceRequest,
+) -> Result<CardRecurrence, AppError> {
+    validate_rule(&req.rule)?;
+    let lead_days = req.lead_days.unwrap_or(DEFAULT_LEAD_DAYS);
+    validate_lead_days(lead_days)?;
+
+    let template = get_card_by_id(pool, card_id).await?;
+    let mut conn = pool.acquire().await?;
+    validate_list(&mut conn, &template, req.list_id).await?;
+
+    let now = Utc::now();
+    let starts_at = req.starts_at.unwrap_or(now);
+    let next_due_at = next_due_from_now(&req.rule, starts_at, 0, now);
+
+    let recurrence = sqlx::query_as::<_, CardRecurrence>(&format!(
+        "INSERT INTO card_recurrence (template_card_id, list_id, rule, starts_at, ends_at, lead_days, next_due_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (template_card_id) DO UPDATE
+         SET list_id = EXCLUDED.list_id, rule = EXCLUDED.rule, status = 'active',
+             starts_at = EXCLUDED.starts_at, ends_at = EXCLUDED.ends_at,
+             lead_days = EXCLUDED.lead_days, next_due_at = EXCLUDED.next_due_at,
+             last_card_id = NULL, occurrences = 0, created_at = NOW(), updated_at = NOW()
+         WHERE card_recurrence.status = 'ended'
+         RETURNING {RECURRENCE_COLUMNS}"
+    ))
+    .bind(card_id)
+    .bind(req.list_id)
+    .bind(Json(&req.rule))
+    .bind(starts_at)
+    .bind(req.ends_at)
+    .bind(lead_days)
+    .bind(next_due_at)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    recurrence.ok_or_else(|| AppError::Conflict(format!("Card {} already has a recurrence", template.card_key)))
+}
+
+/// Edit a series that has not ended. Changing the rule or start reschedules
+/// the next card from now.
+pub async fn update_recurrence(
+    pool: &DbPool,
+    card_id: i32,
+    req: UpdateRecurrenceRequest,
+) -> Result<CardRecurrence, AppError> {
+    let current = get_recurrence(pool, card_id).await?;
+    if current.status == RecurrenceStatus::Ended {
+        return Err(AppError::ValidationError("The series has ended".to_string()));
+    }
+
+    if let Some(rule) = &req.rule {
+        validate_rule(rule)?;
+    }
+    if let Some(lead_days) = req.lead_days {
+        validate_lead_days(lead_days)?;
+    }
+
+    let mut conn = pool.acquire().await?;
+    if let Some(list_id) = req.list_id {
+        let template = get_card_by_id(pool, card_id).await?;
+        validate_list(&mut conn, &template, list_id).await?;
+    }
+
+    let reschedule = req.rule.is_some() || req.starts_at.is_some();
+    let rule = req.rule.unwrap_or(current.rule.0);
+    let starts_at = req.starts_at.unwrap_or(current.starts_at);
+    let ends_at = req.ends_at.unwrap_or(current.ends_at);
+    let next_due_at = if reschedule {
+        next_due_from_now(&rule, starts_at, current.occurrences, Utc::now())
+    } else {
+        current.next_due_at
+    };
+
+    let recurrence = sqlx::query_as::<_, CardRecurrence>(&format!(
+        "UPDATE card_recurrence
+         SET list_id = COALESCE($2, list_id), rule = $3, starts_at = $4, ends_at = $5,
+             lead_days = COALESCE($6, lead_days), next_due_at = $7, updated_at = NOW()
+         WHERE id = $1
+         RETURNING {RECURRENCE_COLUMNS}"
+    ))
+    .bind(current.id)
+    .bind(req.list_id)
+    .bind(Json(&rule))
+    .bind(starts_at)
+    .bind(ends_at)
+    .bind(req.lead_days)
+    .bind(next_due_at)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(recurrence)
+}
+
+/// Pause or resume a series. Due dates missed while paused are skipped.
+pub async fn set_recurrence_paused(pool: &DbPool, card_id: i32, paused: bool) -> Result<CardRecurrence, AppError> {
+    let current = get_recurrence(pool, card_id).await?;
+    if current.status == RecurrenceStatus::Ended {
+        return Err(AppError::ValidationError("The series has ended".to_string()));
+    }
+
+    let (status, next_due_at) = if paused {
+        (RecurrenceStatus::Paused, current.next_due_at)
+    } else if current.status == RecurrenceStatus::Paused {
+        let next = match current.next_due_at {
+            Some(_) => next_due_from_now(&current.rule, current.starts_at, current.occurrences, Utc::now()),
+            None => None,
+        };
+        (RecurrenceStatus::Active, next)
+    } else {
+        (RecurrenceStatus::Active, current.next_due_at)
+    };
+
+    let recurrence = sqlx::query_as::<_, CardRecurrence>(&format!(
+        "UPDATE card_recurrence SET status = $2, next_due_at = $3, updated_at = NOW()
+         WHERE id = $1
+         RETURNING {RECURRENCE_COLUMNS}"
+    ))
+    .bind(current.id)
+    .bind(status)
+    .bind(next_due_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(recurrence)
+}
+
+/// End a series. Cards already created are kept.
+pub async fn end_recurrence(pool: &DbPool, card_id: i32) -> Result<CardRecurrence, AppError> {
+    let current = get_recurrence(pool, card_id).await?;
+
+    let recurrence = sqlx::query_as::<_, CardRecurrence>(&format!(
+        "UPDATE card_recurrence SET status = 'ended', next_due_at = NULL, updated_at = NOW()
+         WHERE id = $1
+         RETURNING {RECURRENCE_COLUMNS}"
+    ))
+    .bind(current.id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(recurrence)
+}
+
+/// Create the cards of every active series that is due, in one transaction
+/// per series. Run by the scheduler; returns the number of cards created. A
+/// series that fails is logged and tried again on the next run.
+pub async fn materialise_due_cards(pool: &DbPool) -> Result<usize, AppError> {
+    // After-completion series become due the rule's days after their last
+    // card was completed, or after now when that card is gone. Days and lead
+    // days are clamped to their limits, so one series out of range cannot
+    // fail the statements for every other series.
+    sqlx::query(
+        "UPDATE card_recurrence r
+         SET next_due_at = COALESCE((SELECT c.completed_at FROM card c WHERE c.id = r.last_card_id), NOW())
+                           + make_interval(days => LEAST((r.rule->>'days')::BIGINT, $1)::INT),
+             updated_at = NOW()
+         WHERE r.status = 'active'
+           AND r.next_due_at IS NULL
+           AND r.rule->>'freq' = 'after_completion'
+           AND NOT EXISTS (SELECT 1 FROM card c WHERE c.id = r.last_card_id AND c.completed_at IS NULL)"
+    )
+    .bind(i64::from(MAX_RECURRENCE_DAYS))
+    .execute(pool)
+    .await?;
+
+    let due: Vec<i32> = sqlx::query_scalar(
+        "SELECT id FROM card_recurrence
+         WHERE status = 'active' AND next_due_at - make_interval(days => LEAST(lead_days, $1)) <= NOW()
+         ORDER BY next_due_at ASC"
+    )
+    .bind(MAX_LEAD_DAYS)
+    .fetch_all(pool)
+    .await?;
+
+    let mut created = 0;
+    for id in due {
+        let mut tx = pool.begin().await?;
+
+        // Another instance may be handling the series, or already has
+        let series = sqlx::query_as::<_, CardRecurrence>(&format!(
+            "SELECT {RECURRENCE_COLUMNS} FROM card_recurrence
+             WHERE id = $1 AND status = 'active' AND next_due_at - make_interval(days => LEAST(lead_days, $2)) <= NOW()
+             FOR UPDATE SKIP LOCKED"
+        ))
+        .bind(id)
+        .bind(MAX_LEAD_DAYS)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(series) = series else {
+            continue;
+        };
+
+        match materialise_next_card(&mut tx, &series).await {
+            Ok(card) => {
+                tx.commit().await?;
+                created += usize::from(card.is_some());
+            }
+            Err(AppError::WipLimitExceeded(msg)) => {
+                tracing::warn!("Recurring card of series {} postponed: {}", series.id, msg);
+            }
+            Err(err) => {
+                tracing::warn!("Failed to create the recurring card of series {}: {:?}", series.id, err);
+            }
+        }
+    }
+
+    Ok(created)
+}
+
+/// Copy the template into the series' list with the next due date, then
+/// schedule the following card. Ends the series instead once past `ends_at`.
+async fn materialise_next_card(conn: &mut PgConnection, series: &CardRecurrence) -> Result<Option<Card>, AppError> {
+    let Some(due_at) = series.next_due_at else {
+        return Ok(None);
+    };
+
+    if series.ends_at.is_some_and(|ends_at| due_at > ends_at) {
+        sqlx::query("UPDATE card_recurrence SET status = 'ended', next_due_at = NULL, updated_at = NOW() WHERE id = $1")
+            .bind(series.id)
+            .execute(&mut *conn)
+            .await?;
+        return Ok(None);
+    }
+
+    let template = sqlx::query_as::<_, Card>(&format!("SELECT {CARD_COLUMNS} FROM card WHERE id = $1"))
+        .bind(series.template_card_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let list_id = series.list_id;
+    let board_id = card_keys::get_board_id_for_list(&mut *conn, list_id).await?;
+    let lane_id = lanes::get_default_lane_id(&mut *conn, board_id).await?;
+    wip::check_wip_limit(&mut *conn, list_id, 1).await?;
+    let category = get_column_category(&mut *conn, list_id).await?;
+    let (started_at, completed_at) = stamps_on_enter(category, (None, None), Utc::now());
+    let rank = ranks::card_rank_at(&mut *conn, list_id, lane_id, None, None).await?;
+    let (card_number, card_key) = card_keys::allocate_card_key(&mut *conn, board_id).await?;
+
+    let mut card = sqlx::query_as::<_, Card>(&format!(
+        "INSERT INTO card (card_number, card_key, title, description, list_id, lane_id, rank,
+                           assignee_id, due_date, labels, priority, estimate, estimate_unit,
+                           started_at, completed_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+         RETURNING {CARD_COLUMNS}"
+    ))
+    .bind(card_number)
+    .bind(&card_key)
+    .bind(&template.title)
+    .bind(&template.description)
+    .bind(list_id)
+    .bind(lane_id)
+    .bind(&rank)
+    .bind(&template.assignee_id)
+    .bind(due_at)
+    .bind(&template.labels)
+    .bind(template.priority)
+    .bind(template.estimate)
+    .bind(template.estimate_unit)
+    .bind(started_at)
+    .bind(completed_at)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if let Some(description) = card.description.as_deref() {
+        let mentioned = mentions::sync_mentions(&mut *conn, card.id, card.list_id, None, description).await?;
+        mentions::notify_mentioned(&mut *conn, &card, None, &mentioned).await?;
+        card.mentions = Json(mentioned);
+    }
+    notifications::notify_assignment(&mut *conn, &card).await?;
+    let created = RuleTrigger::CardCreated { list_id: Some(card.list_id) };
+    let card = automation::apply_rules_to_card(&mut *conn, card, vec![created]).await?;
+    webhooks::emit_card(&mut *conn, WebhookEvent::CardCreated, &card).await?;
+
+    // Due dates missed while the server was down are skipped
+    let next_due_at = match &series.rule.0 {
+        RecurrenceRule::AfterCompletion { .. } => None,
+        rule => {
+            let after_due = due_at.checked_add_signed(Duration::seconds(1)).unwrap_or(due_at);
+            occurrence_on_or_after(rule, series.starts_at, after_due.max(Utc::now()))
+        }
+    };
+    let ended = next_due_at.zip(series.ends_at).is_some_and(|(next, ends_at)| next > ends_at);
+
+    sqlx::query(
+        "UPDATE card_recurrence
+         SET next_due_at = $2, status = $3, last_card_id = $4, occurrences = occurrences + 1, updated_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(series.id)
+    .bind(if ended { None } else { next_due_at })
+    .bind(if ended { RecurrenceStatus::Ended } else { RecurrenceStatus::Active })
+    .bind(card.id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(Some(card))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RecurrenceWeekday;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn daily_counts_intervals_from_the_anchor() {
+        let rule = RecurrenceRule::Daily { interval: 3 };
+        let anchor = at("2024-03-01T09:00:00Z");
+        assert_eq!(occurrence_on_or_after(&rule, anchor, at("2024-02-01T00:00:00Z")), Some(anchor));
+        assert_eq!(occurrence_on_or_after(&rule, anchor, anchor), Some(anchor));
+        assert_eq!(
+            occurrence_on_or_after(&rule, anchor, at("2024-03-01T09:00:01Z")),
+            Some(at("2024-03-04T09:00:00Z"))
+        );
+        assert_eq!(
+            occurrence_on_or_after(&rule, anchor, at("2024-03-07T09:00:00Z")),
+            Some(at("2024-03-07T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn daily_out_of_range_is_none() {
+        let rule = RecurrenceRule::Daily { interval: u32::MAX };
+        assert_eq!(occurrence_on_or_after(&rule, at("2024-03-01T09:00:00Z"), DateTime::<Utc>::MAX_UTC), None);
+    }
+
+    #[test]
+    fn weekly_finds_the_next_listed_weekday() {
+        let rule = RecurrenceRule::Weekly { weekdays: vec![RecurrenceWeekday::Mon, RecurrenceWeekday::Thu] };
+        let anchor = at("2024-03-01T09:00:00Z"); // a Friday
+        assert_eq!(occurrence_on_or_after(&rule, anchor, anchor), Some(at("2024-03-04T09:00:00Z")));
+        assert_eq!(
+            occurrence_on_or_after(&rule, anchor, at("2024-03-04T09:00:01Z")),
+            Some(at("2024-03-07T09:00:00Z"))
+        );
+        assert_eq!(
+            occurrence_on_or_after(&rule, anchor, at("2024-03-07T08:00:00Z")),
+            Some(at("2024-03-07T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn monthly_uses_the_last_day_of_short_months() {
+        let rule = RecurrenceRule::Monthly { day: 31 };
+        let anchor = at("2024-01-31T09:00:00Z");
+        assert_eq!(occurrence_on_or_after(&rule, anchor, anchor), Some(anchor));
+        assert_eq!(
+            occurrence_on_or_after(&rule, anchor, at("2024-02-01T00:00:00Z")),
+            Some(at("2024-02-29T09:00:00Z"))
+        );
+        assert_eq!(
+            occurrence_on_or_after(&rule, anchor, at("2024-04-30T10:00:00Z")),
+            Some(at("2024-05-31T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn after_completion_is_due_at_once() {
+        let rule = RecurrenceRule::AfterCompletion { days: 2 };
+        let anchor = at("2024-03-01T09:00:00Z");
+        let now = at("2024-03-05T12:00:00Z");
+        assert_eq!(occurrence_on_or_after(&rule, anchor, now), Some(now));
+        assert_eq!(next_due_from_now(&rule, anchor, 0, now), Some(now));
+        assert_eq!(next_due_from_now(&rule, anchor, 1, now), None);
+    }
+
+    #[test]
+    fn rules_and_lead_days_are_bounded() {
+        assert!(validate_rule(&RecurrenceRule::Daily { interval: 1 }).is_ok());
+        assert!(validate_rule(&RecurrenceRule::Daily { interval: 0 }).is_err());
+        assert!(validate_rule(&RecurrenceRule::Daily { interval: MAX_RECURRENCE_DAYS + 1 }).is_err());
+        assert!(validate_rule(&RecurrenceRule::AfterCompletion { days: MAX_RECURRENCE_DAYS }).is_ok());
+        assert!(validate_rule(&RecurrenceRule::AfterCompletion { days: u32::MAX }).is_err());
+        assert!(validate_rule(&RecurrenceRule::Monthly { day: 32 }).is_err());
+        assert!(validate_rule(&RecurrenceRule::Weekly { weekdays: vec![] }).is_err());
+
+        assert!(validate_lead_days(0).is_ok());
+        assert!(validate_lead_days(MAX_LEAD_DAYS).is_ok());
+        assert!(validate_lead_days(-1).is_err());
+        assert!(validate_lead_days(i32::MAX).is_err());
+    }
+
+    #[test]
+    fn updates_tell_a_cleared_end_date_from_an_omitted_one() {
+        let update = |body| serde_json::from_value::<UpdateRecurrenceRequest>(body).unwrap().ends_at;
+        assert_eq!(update(serde_json::json!({})), None);
+        assert_eq!(update(serde_json::json!({ "endsAt": null })), Some(None));
+        assert_eq!(
+            update(serde_json::json!({ "endsAt": "2024-03-01T09:00:00Z" })),
+            Some(Some(at("2024-03-01T09:00:00Z")))
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! Title and description history of cards. Every edit of either through
+//! `create_card` or `update_card` adds a revision with the full new text.
+
+use similar::TextDiff;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::cards::{get_card_by_id, update_card};
+use crate::models::{Card, CardRevision, CardRevisionDiff, RestoreRevisionRequest, UpdateCardRequest};
+
+const REVISION_COLUMNS: &str = "id, card_id, revision, title, description, author_id, restored_from, created_at";
+
+/// Lines of unchanged text around each change in a diff
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Record a card's title and description as a new revision. `previous` is
+/// the card before an edit, or None for a new card; edits that change
+/// neither field record nothing. A card with no revisions yet, such as a
+/// copied one, first gets its previous text as revision 1.
+pub async fn record_revision(
+    conn: &mut PgConnection,
+    previous: Option<&Card>,
+    card: &Card,
+    author_id: Option<&str>,
+    restored_from: Option<i32>,
+) -> Result<(), AppError> {
+    if let Some(previous) = previous {
+        if previous.title == card.title && previous.description == card.description {
+            return Ok(());
+        }
+
+        sqlx::query(
+            "INSERT INTO card_revision (card_id, revision, title, description, created_at)
+             SELECT $1, 1, $2, $3, $4
+             WHERE NOT EXISTS (SELECT 1 FROM card_revision WHERE card_id = $1)"
+        )
+        .bind(previous.id)
+        .bind(&previous.title)
+        .bind(&previous.description)
+        .bind(previous.updated_at)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query(
+        "INSERT INTO card_revision (card_id, revision, title, description, author_id, restored_from)
+         SELECT $1, COALESCE(MAX(revision), 0) + 1, $2, $3, $4, $5
+         FROM card_revision WHERE card_id = $1"
+    )
+    .bind(card.id)
+    .bind(&card.title)
+    .bind(&card.description)
+    .bind(author_id)
+    .bind(restored_from)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Get a card's revisions, newest first
+pub async fn get_revisions(pool: &DbPool, card_id: i32) -> Result<Vec<CardRevision>, AppError> {
+    get_card_by_id(pool, card_id).await?;
+
+    let revisions = sqlx::query_as::<_, CardRevision>(&format!(
+        "SELECT {REVISION_COLUMNS} FROM card_revision WHERE card_id = $1 ORDER BY revision DESC"
+    ))
+    .bind(card_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(revisions)
+}
+
+/// Get one revision of a card, or its latest when `revision` is None
+async fn get_revision(pool: &DbPool, card_id: i32, revision: Option<i32>) -> Result<CardRevision, AppError> {
+    let found = sqlx::query_as::<_, CardRevision>(&format!(
+        "SELECT {REVISION_COLUMNS} FROM card_revision
+         WHERE card_id = $1 AND ($2::INT IS NULL OR revision = $2)
+         ORDER BY revision DESC
+         LIMIT 1"
+    ))
+    .bind(card_id)
+    .bind(revision)
+    .fetch_optional(pool)
+    .await?;
+
+    found.ok_or_else(|| match revision {
+        Some(revision) => AppError::NotFound(format!("Card {} has no revision {}", card_id, revision)),
+        None => AppError::NotFound(format!("Card {} has no revisions", card_id)),
+    })
+}
+
+fn unified_diff(old: &str, new: &str, old_header: &str, new_header: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT_LINES)
+        .header(old_header, new_header)
+        .to_string()
+}
+
+/// Unified diffs of the title and description between two revisions
+pub async fn diff_revisions(
+    pool: &DbPool,
+    card_id: i32,
+    from: i32,
+    to: Option<i32>,
+) -> Result<CardRevisionDiff, AppError> {
+    get_card_by_id(pool, card_id).await?;
+    let old = get_revision(pool, card_id, Some(from)).await?;
+    let new = get_revision(pool, card_id, to).await?;
+
+    let header = |field: &str, revision: &CardRevision| format!("{} (revision {})", field, revision.revision);
+
+    Ok(CardRevisionDiff {
+        from: old.revision,
+        to: new.revision,
+        title_diff: unified_diff(
+            &format!("{}\n", old.title),
+            &format!("{}\n", new.title),
+            &header("title", &old),
+            &header("title", &new),
+        ),
+        description_diff: unified_diff(
+            old.description.as_deref().unwrap_or_default(),
+            new.description.as_deref().unwrap_or_default(),
+            &header("description", &old),
+            &header("description", &new),
+        ),
+    })
+}
+
+/// Restore the title and description of a revision, as a new edit through
+/// `update_card`. Returns a warning like `update_card` does.
+pub async fn restore_revision(
+    pool: &DbPool,
+    card_id: i32,
+    revision: i32,
+    req: RestoreRevisionRequest,
+) -> Result<(Card, Option<String>), AppError> {
+    let restored = get_revision(pool, card_id, Some(revision)).await?;
+
+    let update = UpdateCardRequest {
+        title: Some(restored.title),
+        clears_description: restored.description.is_none(),
+        description: restored.description,
+        edited_by: req.edited_by,
+        restored_from: Some(restored.revision),
+        ..Default::default()
+    };
+    update_card(pool, card_id, update).await
+}
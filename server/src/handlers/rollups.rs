@@ -0,0 +1,137 @@
+use sqlx::FromRow;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::columns::{get_column_by_id, COLUMN_COLUMNS};
+use crate::models::{
+    BoardColumn, BoardRollup, CardPriority, ColumnCategory, ColumnRollup, EstimateRollup,
+    EstimateUnit,
+};
+
+/// The subset of card fields that feed into a rollup
+#[derive(Debug, FromRow)]
+struct CardEstimateRow {
+    list_id: i32,
+    priority: Option<CardPriority>,
+    estimate: Option<f64>,
+    estimate_unit: Option<EstimateUnit>,
+}
+
+/// Add a single card to a rollup. Cards in a done column count towards the
+/// totals but not towards the remaining work.
+fn accumulate(rollup: &mut EstimateRollup, row: &CardEstimateRow, is_done: bool) {
+    rollup.card_count += 1;
+
+    let counts = &mut rollup.count_by_priority;
+    match row.priority {
+        Some(CardPriority::Low) => counts.low += 1,
+        Some(CardPriority::Medium) => counts.medium += 1,
+        Some(CardPriority::High) => counts.high += 1,
+        Some(CardPriority::Urgent) => counts.urgent += 1,
+        None => counts.none += 1,
+    }
+
+    let Some(estimate) = row.estimate else {
+        return;
+    };
+    rollup.estimated_card_count += 1;
+
+    let remaining = if is_done { 0.0 } else { estimate };
+    match row.estimate_unit.unwrap_or_default() {
+        EstimateUnit::Points => {
+            rollup.total_points += estimate;
+            rollup.remaining_points += remaining;
+        }
+        EstimateUnit::Hours => {
+            rollup.total_hours += estimate;
+            rollup.remaining_hours += remaining;
+        }
+    }
+}
+
+/// Get the estimate rollup for a single column
+pub async fn get_column_rollup(pool: &DbPool, list_id: i32) -> Result<ColumnRollup, AppError> {
+    let column = get_column_by_id(pool, list_id).await?;
+    let is_done = column.category == ColumnCategory::Done;
+
+    let rows = sqlx::query_as::<_, CardEstimateRow>(
+        "SELECT list_id, priority, estimate, estimate_unit FROM card WHERE list_id = $1"
+    )
+    .bind(list_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut rollup = EstimateRollup::default();
+    for row in &rows {
+        accumulate(&mut rollup, row, is_done);
+    }
+
+    Ok(ColumnRollup {
+        list_id: column.id,
+        title: column.title,
+        rollup,
+    })
+}
+
+/// Get the estimate rollup for a board, including a breakdown per column
+pub async fn get_board_rollup(pool: &DbPool, board_id: i32) -> Result<BoardRollup, AppError> {
+    let board_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM board WHERE id = $1)"
+    )
+    .bind(board_id)
+    .fetch_one(pool)
+    .await?;
+
+    if !board_exists {
+        return Err(AppError::NotFound(format!("Board with id {} not found", board_id)));
+    }
+
+    let columns = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS}
+         FROM board_column
+         WHERE board_id = $1
+         ORDER BY rank ASC"
+    ))
+    .bind(board_id)
+    .fetch_all(pool)
+    .await?;
+
+    let rows = sqlx::query_as::<_, CardEstimateRow>(
+        "SELECT c.list_id, c.priority, c.estimate, c.estimate_unit
+         FROM card c
+         JOIN board_column bc ON bc.id = c.list_id
+         WHERE bc.board_id = $1"
+    )
+    .bind(board_id)
+    .fetch_all(pool)
+    .await?;
+
+    let done_column_ids: Vec<i32> = columns
+        .iter()
+        .filter(|c| c.category == ColumnCategory::Done)
+        .map(|c| c.id)
+        .collect();
+    let mut totals = EstimateRollup::default();
+    let mut column_rollups: Vec<ColumnRollup> = columns
+        .into_iter()
+        .map(|c| ColumnRollup {
+            list_id: c.id,
+            title: c.title,
+            rollup: EstimateRollup::default(),
+        })
+        .collect();
+
+    for row in &rows {
+        let is_done = done_column_ids.contains(&row.list_id);
+        accumulate(&mut totals, row, is_done);
+        if let Some(column) = column_rollups.iter_mut().find(|c| c.list_id == row.list_id) {
+            accumulate(&mut column.rollup, row, is_done);
+        }
+    }
+
+    Ok(BoardRollup {
+        board_id,
+        rollup: totals,
+        columns: column_rollups,
+    })
+}
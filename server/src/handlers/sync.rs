@@ -0,0 +1,498 @@
+//! Delta sync for offline clients.
+//!
+//! Triggers (migration 0020) stamp every inserted or changed board, list and
+//! card with the next value of a server-wide change sequence, its version,
+//! and with the transaction that made the change. Deletions, and moves to
+//! another board, leave tombstones. A sync token is the oldest transaction
+//! still running when the sync took its snapshot, so changes committed later
+//! by transactions that were already running are picked up by the next sync.
+//! Changes near the horizon may be sent twice; applying them again is
+//! harmless.
+//!
+//! A board is visible to a user who owns it or is one of its members, and
+//! to everyone when it has no owner. When its owner or members change, its
+//! lists and cards are all sent again, as created, to everyone who can see
+//! it, and those who no longer can get a tombstone for the board, which
+//! stands for its lists and cards too.
+
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgConnection, Row};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::boards::{update_board_in, BOARD_COLUMNS};
+use crate::handlers::cards::{self, delete_card_in, move_card_in, update_card_in, CARD_COLUMNS};
+use crate::handlers::collab;
+use crate::handlers::columns::{self, delete_column_in, update_column_in, COLUMN_COLUMNS};
+use crate::models::{
+    Board, BoardColumn, Card, MoveCardRequest, SyncChanges, SyncEntity, SyncOperation, SyncOperationResult,
+    SyncOperationStatus, SyncRequest, SyncResponse,
+};
+
+/// Whether board `b` is visible to the user bound as `$1`
+const VISIBLE: &str = "(b.owner_id IS NULL OR b.owner_id = $1 OR $1 = ANY(b.members))";
+
+/// Rows changed since the token bound as `$2`, or all rows without one
+const CHANGED_SINCE: &str = "($2::TEXT IS NULL OR change_xid >= $2::TEXT::XID8)";
+
+/// Whether a row was created since the token bound as `$2`, or is on a
+/// board whose access changed since, as `reshared` tells
+fn created_since(reshared: &str) -> String {
+    format!("COALESCE(created_xid >= $2::TEXT::XID8 OR {reshared}, TRUE) AS created")
+}
+
+/// An entity with whether it was created since the token
+struct Changed<T> {
+    entity: T,
+    created: bool,
+}
+
+impl<'r, T: FromRow<'r, PgRow>> FromRow<'r, PgRow> for Changed<T> {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self { entity: T::from_row(row)?, created: row.try_get("created")? })
+    }
+}
+
+fn parse_token(token: &str) -> Result<&str, AppError> {
+    token
+        .parse::<u64>()
+        .map(|_| token)
+        .map_err(|_| AppError::ValidationError("Invalid sync token".to_string()))
+}
+
+async fn changes<T>(
+    conn: &mut PgConnection,
+    sql: &str,
+    user_id: &str,
+    since: Option<&str>,
+) -> Result<SyncChanges<T>, AppError>
+where
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+{
+    let rows = sqlx::query_as::<_, Changed<T>>(sql)
+        .bind(user_id)
+        .bind(since)
+        .fetch_all(conn)
+        .await?;
+
+    let mut changes = SyncChanges::default();
+    for row in rows {
+        if row.created {
+            changes.created.push(row.entity);
+        } else {
+            changes.updated.push(row.entity);
+        }
+    }
+    Ok(changes)
+}
+
+/// Add a tombstoned ID unless the entity is back, e.g. restored by undo or
+/// moved to another board the user can see
+fn add_deleted<T>(changes: &mut SyncChanges<T>, id: i32, entity_id: impl Fn(&T) -> i32) {
+    let present = changes.created.iter().chain(&changes.updated).any(|entity| entity_id(entity) == id);
+    if !present && !changes.deleted.contains(&id) {
+        changes.deleted.push(id);
+    }
+}
+
+/// Get the boards, lists and cards visible to a user that changed since a
+/// token, or all of them without one
+pub async fn get_changes(pool: &DbPool, user_id: &str, since: Option<&str>) -> Result<SyncResponse, AppError> {
+    if user_id.trim().is_empty() {
+        return Err(AppError::ValidationError("userId is required".to_string()));
+    }
+    let since = since.map(parse_token).transpose()?;
+
+    // One snapshot for the token and every read
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    let token: String = sqlx::query_scalar("SELECT pg_snapshot_xmin(pg_current_snapshot())::TEXT")
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let visible_boards = format!("SELECT b.id FROM board b WHERE {VISIBLE}");
+    // Visible boards whose owner or members changed since the token
+    let reshared_boards = format!("SELECT b.id FROM board b WHERE {VISIBLE} AND b.access_xid >= $2::TEXT::XID8");
+    let board_reshared = format!("id IN ({reshared_boards})");
+    let column_reshared = format!("board_id IN ({reshared_boards})");
+    let card_reshared = format!("list_id IN (SELECT id FROM board_column WHERE board_id IN ({reshared_boards}))");
+    let mut boards = changes::<Board>(
+        &mut tx,
+        &format!(
+            "SELECT {BOARD_COLUMNS}, change_seq AS version, {} FROM board
+             WHERE id IN ({visible_boards}) AND ({CHANGED_SINCE} OR {board_reshared})
+             ORDER BY change_seq",
+            created_since(&board_reshared)
+        ),
+        user_id,
+        since,
+    )
+    .await?;
+    let mut columns = changes::<BoardColumn>(
+        &mut tx,
+        &format!(
+            "SELECT {COLUMN_COLUMNS}, rank, change_seq AS version, {} FROM board_column
+             WHERE board_id IN ({visible_boards}) AND ({CHANGED_SINCE} OR {column_reshared})
+             ORDER BY change_seq",
+            created_since(&column_reshared)
+        ),
+        user_id,
+        since,
+    )
+    .await?;
+    let mut cards = changes::<Card>(
+        &mut tx,
+        &format!(
+            "SELECT {CARD_COLUMNS}, rank, change_seq AS version, {} FROM card
+             WHERE list_id IN (SELECT id FROM board_column WHERE board_id IN ({visible_boards}))
+               AND ({CHANGED_SINCE} OR {card_reshared})
+             ORDER BY change_seq",
+            created_since(&card_reshared)
+        ),
+        user_id,
+        since,
+    )
+    .await?;
+
+    if since.is_some() {
+        let tombstones: Vec<(SyncEntity, i32)> = sqlx::query_as(&format!(
+            "SELECT entity, entity_id FROM sync_tombstone b
+             WHERE {VISIBLE} AND {CHANGED_SINCE}
+             ORDER BY change_seq"
+        ))
+        .bind(user_id)
+        .bind(since)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for (entity, id) in tombstones {
+            match entity {
+                SyncEntity::Board => add_deleted(&mut boards, id, |board| board.id),
+                SyncEntity::Column => add_deleted(&mut columns, id, |column| column.id),
+                SyncEntity::Card => add_deleted(&mut cards, id, |card| card.id),
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(SyncResponse { token, boards, columns, cards })
+}
+
+/// Apply a batch of client operations in order, each in its own
+/// transaction. A failed or conflicting operation does not stop the batch.
+pub async fn apply_operations(pool: &DbPool, req: SyncRequest) -> Result<Vec<SyncOperationResult>, AppError> {
+    if req.user_id.trim().is_empty() {
+        return Err(AppError::ValidationError("userId is required".to_string()));
+    }
+
+    let mut results = Vec::with_capacity(req.operations.len());
+    for (index, operation) in req.operations.into_iter().enumerate() {
+        let client_id = match &operation {
+            SyncOperation::CreateCard { client_id, .. } | SyncOperation::CreateColumn { client_id, .. } => {
+                client_id.clone()
+            }
+            _ => None,
+        };
+
+        let mut result = match apply_operation(pool, &req.user_id, operation).await {
+            Ok(result) => result,
+            Err(err) => SyncOperationResult {
+                error: Some(failure_message(err)),
+                ..SyncOperationResult::new(index, SyncOperationStatus::Failed)
+            },
+        };
+        result.index = index;
+        result.client_id = client_id;
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+/// The message of an error as the API would report it
+pub(crate) fn failure_message(err: AppError) -> String {
+    match err {
+        AppError::DatabaseError(err) => {
+            tracing::error!("Database error: {}", err);
+            "Database error occurred".to_string()
+        }
+        AppError::InternalError(msg) => {
+            tracing::error!("Internal error: {}", msg);
+            msg
+        }
+        AppError::NotFound(msg)
+        | AppError::ValidationError(msg)
+        | AppError::Unauthorized(msg)
+        | AppError::Conflict(msg)
+        | AppError::WipLimitExceeded(msg) => msg,
+    }
+}
+
+/// Version of an entity and whether the user can see its board; None when
+/// it does not exist. Locks the entity's row.
+type Lock = Option<(i64, bool)>;
+
+async fn lock_card(conn: &mut PgConnection, user_id: &str, id: i32) -> Result<Lock, AppError> {
+    let lock = sqlx::query_as(&format!(
+        "SELECT c.change_seq, {VISIBLE} FROM card c
+         JOIN board_column bc ON bc.id = c.list_id
+         JOIN board b ON b.id = bc.board_id
+         WHERE c.id = $2
+         FOR UPDATE OF c"
+    ))
+    .bind(user_id)
+    .bind(id)
+    .fetch_optional(conn)
+    .await?;
+    Ok(lock)
+}
+
+async fn lock_column(conn: &mut PgConnection, user_id: &str, id: i32) -> Result<Lock, AppError> {
+    let lock = sqlx::query_as(&format!(
+        "SELECT bc.change_seq, {VISIBLE} FROM board_column bc
+         JOIN board b ON b.id = bc.board_id
+         WHERE bc.id = $2
+         FOR UPDATE OF bc"
+    ))
+    .bind(user_id)
+    .bind(id)
+    .fetch_optional(conn)
+    .await?;
+    Ok(lock)
+}
+
+async fn lock_board(conn: &mut PgConnection, user_id: &str, id: i32) -> Result<Lock, AppError> {
+    let lock = sqlx::query_as(&format!("SELECT b.change_seq, {VISIBLE} FROM board b WHERE b.id = $2 FOR UPDATE"))
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(conn)
+        .await?;
+    Ok(lock)
+}
+
+/// Fail unless a list exists on a board the user can see
+async fn ensure_list_visible(conn: &mut PgConnection, user_id: &str, list_id: i32) -> Result<(), AppError> {
+    let visible: bool = sqlx::query_scalar(&format!(
+        "SELECT EXISTS(SELECT 1 FROM board_column bc JOIN board b ON b.id = bc.board_id
+                       WHERE bc.id = $2 AND {VISIBLE})"
+    ))
+    .bind(user_id)
+    .bind(list_id)
+    .fetch_one(conn)
+    .await?;
+
+    if !visible {
+        return Err(AppError::NotFound(format!("List with id {} not found", list_id)));
+    }
+    Ok(())
+}
+
+/// Fail unless a board exists and the user can see it
+async fn ensure_board_visible(conn: &mut PgConnection, user_id: &str, board_id: i32) -> Result<(), AppError> {
+    let visible: bool = sqlx::query_scalar(&format!("SELECT EXISTS(SELECT 1 FROM board b WHERE b.id = $2 AND {VISIBLE})"))
+        .bind(user_id)
+        .bind(board_id)
+        .fetch_one(conn)
+        .await?;
+
+    if !visible {
+        return Err(AppError::NotFound(format!("Board with id {} not found", board_id)));
+    }
+    Ok(())
+}
+
+async fn fetch_card(conn: &mut PgConnection, id: i32) -> Result<Card, AppError> {
+    let card = sqlx::query_as::<_, Card>(&format!(
+        "SELECT {CARD_COLUMNS}, rank, change_seq AS version FROM card WHERE id = $1"
+    ))
+    .bind(id)
+    .fetch_one(conn)
+    .await?;
+    Ok(card)
+}
+
+async fn fetch_column(conn: &mut PgConnection, id: i32) -> Result<BoardColumn, AppError> {
+    let column = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS}, rank, change_seq AS version FROM board_column WHERE id = $1"
+    ))
+    .bind(id)
+    .fetch_one(conn)
+    .await?;
+    Ok(column)
+}
+
+async fn fetch_board(conn: &mut PgConnection, id: i32) -> Result<Board, AppError> {
+    let board = sqlx::query_as::<_, Board>(&format!(
+        "SELECT {BOARD_COLUMNS}, change_seq AS version FROM board WHERE id = $1"
+    ))
+    .bind(id)
+    .fetch_one(conn)
+    .await?;
+    Ok(board)
+}
+
+/// What to do with an operation on an existing entity
+enum Check {
+    Proceed,
+    /// The entity is gone
+    Deleted,
+    /// The entity changed since the client's version
+    Changed,
+}
+
+fn check(lock: Lock, base_version: Option<i64>, what: &str, id: i32) -> Result<Check, AppError> {
+    match lock {
+        None => Ok(Check::Deleted),
+        Some((_, false)) => Err(AppError::NotFound(format!("{} with id {} not found", what, id))),
+        Some((version, true)) if base_version.is_some_and(|base| base != version) => Ok(Check::Changed),
+        Some(_) => Ok(Check::Proceed),
+    }
+}
+
+fn conflict(error: String) -> SyncOperationResult {
+    SyncOperationResult { error: Some(error), ..SyncOperationResult::new(0, SyncOperationStatus::Conflict) }
+}
+
+fn applied() -> SyncOperationResult {
+    SyncOperationResult::new(0, SyncOperationStatus::Applied)
+}
+
+async fn apply_operation(
+    pool: &DbPool,
+    user_id: &str,
+    operation: SyncOperation,
+) -> Result<SyncOperationResult, AppError> {
+    // Description edits reach open collaboration sessions once committed
+    let edited_description = match &operation {
+        SyncOperation::UpdateCard { card_id, changes, .. } if changes.description.is_some() => Some(*card_id),
+        _ => None,
+    };
+
+    let mut tx = pool.begin().await?;
+
+    let result = match operation {
+        SyncOperation::CreateCard { card, .. } => {
+            ensure_list_visible(&mut tx, user_id, card.list_id).await?;
+            drop(tx);
+            let (card, warning) = cards::create_card(pool, card).await?;
+            let mut conn = pool.acquire().await?;
+            return Ok(SyncOperationResult {
+                warning,
+                card: Some(fetch_card(&mut conn, card.id).await?),
+                ..applied()
+            });
+        }
+        SyncOperation::UpdateCard { card_id, base_version, mut changes } => {
+            match check(lock_card(&mut tx, user_id, card_id).await?, base_version, "Card", card_id)? {
+                Check::Deleted => conflict(format!("Card {} has been deleted", card_id)),
+                Check::Changed => SyncOperationResult {
+                    card: Some(fetch_card(&mut tx, card_id).await?),
+                    ..conflict(format!("Card {} has changed since version {}", card_id, base_version.unwrap_or_default()))
+                },
+                Check::Proceed => {
+                    if let Some(list_id) = changes.list_id {
+                        ensure_list_visible(&mut tx, user_id, list_id).await?;
+                    }
+                    changes.edited_by.get_or_insert_with(|| user_id.to_string());
+                    let current = fetch_card(&mut tx, card_id).await?;
+                    let (_, warning) = update_card_in(&mut tx, current, changes).await?;
+                    SyncOperationResult { warning, card: Some(fetch_card(&mut tx, card_id).await?), ..applied() }
+                }
+            }
+        }
+        SyncOperation::MoveCard { card_id, base_version, list_id, lane_id, position } => {
+            match check(lock_card(&mut tx, user_id, card_id).await?, base_version, "Card", card_id)? {
+                Check::Deleted => conflict(format!("Card {} has been deleted", card_id)),
+                Check::Changed => SyncOperationResult {
+                    card: Some(fetch_card(&mut tx, card_id).await?),
+                    ..conflict(format!("Card {} has changed since version {}", card_id, base_version.unwrap_or_default()))
+                },
+                Check::Proceed => {
+                    if let Some(list_id) = list_id {
+                        ensure_list_visible(&mut tx, user_id, list_id).await?;
+                    }
+                    let current = fetch_card(&mut tx, card_id).await?;
+                    let req = MoveCardRequest { list_id, lane_id, position, user_id: None };
+                    let (_, warning) = move_card_in(&mut tx, &current, req).await?;
+                    SyncOperationResult { warning, card: Some(fetch_card(&mut tx, card_id).await?), ..applied() }
+                }
+            }
+        }
+        SyncOperation::DeleteCard { card_id, base_version } => {
+            match check(lock_card(&mut tx, user_id, card_id).await?, base_version, "Card", card_id)? {
+                // Deleting twice is not a conflict
+                Check::Deleted => applied(),
+                Check::Changed => SyncOperationResult {
+                    card: Some(fetch_card(&mut tx, card_id).await?),
+                    ..conflict(format!("Card {} has changed since version {}", card_id, base_version.unwrap_or_default()))
+                },
+                Check::Proceed => {
+                    delete_card_in(&mut tx, card_id).await?;
+                    applied()
+                }
+            }
+        }
+        SyncOperation::CreateColumn { column, .. } => {
+            ensure_board_visible(&mut tx, user_id, column.board_id).await?;
+            drop(tx);
+            let column = columns::create_column(pool, column).await?;
+            let mut conn = pool.acquire().await?;
+            return Ok(SyncOperationResult { column: Some(fetch_column(&mut conn, column.id).await?), ..applied() });
+        }
+        SyncOperation::UpdateColumn { column_id, base_version, changes } => {
+            match check(lock_column(&mut tx, user_id, column_id).await?, base_version, "List", column_id)? {
+                Check::Deleted => conflict(format!("List {} has been deleted", column_id)),
+                Check::Changed => SyncOperationResult {
+                    column: Some(fetch_column(&mut tx, column_id).await?),
+                    ..conflict(format!("List {} has changed since version {}", column_id, base_version.unwrap_or_default()))
+                },
+                Check::Proceed => {
+                    if let Some(board_id) = changes.board_id {
+                        ensure_board_visible(&mut tx, user_id, board_id).await?;
+                    }
+                    let current = fetch_column(&mut tx, column_id).await?;
+                    update_column_in(&mut tx, current, changes).await?;
+                    SyncOperationResult { column: Some(fetch_column(&mut tx, column_id).await?), ..applied() }
+                }
+            }
+        }
+        SyncOperation::DeleteColumn { column_id, base_version } => {
+            match check(lock_column(&mut tx, user_id, column_id).await?, base_version, "List", column_id)? {
+                Check::Deleted => applied(),
+                Check::Changed => SyncOperationResult {
+                    column: Some(fetch_column(&mut tx, column_id).await?),
+                    ..conflict(format!("List {} has changed since version {}", column_id, base_version.unwrap_or_default()))
+                },
+                Check::Proceed => {
+                    delete_column_in(&mut tx, column_id).await?;
+                    applied()
+                }
+            }
+        }
+        SyncOperation::UpdateBoard { board_id, base_version, changes } => {
+            match check(lock_board(&mut tx, user_id, board_id).await?, base_version, "Board", board_id)? {
+                Check::Deleted => conflict(format!("Board {} has been deleted", board_id)),
+                Check::Changed => SyncOperationResult {
+                    board: Some(fetch_board(&mut tx, board_id).await?),
+                    ..conflict(format!("Board {} has changed since version {}", board_id, base_version.unwrap_or_default()))
+                },
+                Check::Proceed => {
+                    update_board_in(&mut tx, board_id, changes).await?;
+                    SyncOperationResult { board: Some(fetch_board(&mut tx, board_id).await?), ..applied() }
+                }
+            }
+        }
+    };
+
+    tx.commit().await?;
+
+    if let Some(card_id) = edited_description {
+        collab::refresh_session(pool, card_id);
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,264 @@
+use chrono::Utc;
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::boards::get_board_by_id;
+use crate::handlers::{card_keys, lanes, mentions, ranks};
+use crate::handlers::card_lifecycle::stamps_on_enter;
+use crate::handlers::cards::CARD_COLUMNS;
+use crate::handlers::columns::COLUMN_COLUMNS;
+use crate::models::{
+    BoardColumn, BoardTemplate, BoardTemplateDefinition, Card, ColumnCategory,
+    SaveBoardAsTemplateRequest, TemplateCard, TemplateColumn,
+};
+
+const TEMPLATE_COLUMNS: &str = "id, name, description, built_in, definition, created_at, updated_at";
+
+fn column(title: &str, category: ColumnCategory, cards: &[&str]) -> TemplateColumn {
+    TemplateColumn {
+        title: title.to_string(),
+        category,
+        cards: cards
+            .iter()
+            .map(|title| TemplateCard {
+                title: title.to_string(),
+                description: None,
+                labels: Vec::new(),
+                priority: None,
+                estimate: None,
+                estimate_unit: None,
+            })
+            .collect(),
+    }
+}
+
+/// Templates shipped with the server, keyed by a stable slug
+fn builtin_templates() -> Vec<(&'static str, &'static str, &'static str, BoardTemplateDefinition)> {
+    vec![
+        (
+            "project",
+            "Project board",
+            "Backlog, Ready, In progress, Review and Done with a few starter cards",
+            BoardTemplateDefinition {
+                columns: vec![
+                    column(
+                        "Backlog",
+                        ColumnCategory::Backlog,
+                        &["Collect requirements", "Define milestones"],
+                    ),
+                    column("Ready", ColumnCategory::Todo, &["Set up project repository"]),
+                    column("In progress", ColumnCategory::InProgress, &[]),
+                    column("Review", ColumnCategory::InProgress, &[]),
+                    column("Done", ColumnCategory::Done, &[]),
+                ],
+            },
+        ),
+        (
+            "simple",
+            "Simple to-do",
+            "To do, Doing and Done",
+            BoardTemplateDefinition {
+                columns: vec![
+                    column("To do", ColumnCategory::Todo, &[]),
+                    column("Doing", ColumnCategory::InProgress, &[]),
+                    column("Done", ColumnCategory::Done, &[]),
+                ],
+            },
+        ),
+    ]
+}
+
+/// Insert or refresh the built-in templates. Called once at startup.
+pub async fn sync_builtin_templates(pool: &DbPool) -> Result<(), AppError> {
+    for (slug, name, description, definition) in builtin_templates() {
+        sqlx::query(
+            "INSERT INTO board_template (slug, name, description, built_in, definition)
+             VALUES ($1, $2, $3, TRUE, $4)
+             ON CONFLICT (slug) DO UPDATE
+             SET name = EXCLUDED.name,
+                 description = EXCLUDED.description,
+                 definition = EXCLUDED.definition,
+                 updated_at = NOW()"
+        )
+        .bind(slug)
+        .bind(name)
+        .bind(description)
+        .bind(Json(definition))
+        .execute(pool)
+        .await?;
+    }
+
+    tracing::info!("Built-in board templates synchronised");
+    Ok(())
+}
+
+/// Get all templates, built-in templates first
+pub async fn get_all_templates(pool: &DbPool) -> Result<Vec<BoardTemplate>, AppError> {
+    let templates = sqlx::query_as::<_, BoardTemplate>(&format!(
+        "SELECT {TEMPLATE_COLUMNS}
+         FROM board_template
+         ORDER BY built_in DESC, name ASC"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(templates)
+}
+
+/// Get template by ID
+pub async fn get_template_by_id(pool: &DbPool, id: i32) -> Result<BoardTemplate, AppError> {
+    let template = sqlx::query_as::<_, BoardTemplate>(&format!(
+        "SELECT {TEMPLATE_COLUMNS}
+         FROM board_template
+         WHERE id = $1"
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    template.ok_or_else(|| AppError::NotFound(format!("Template with id {} not found", id)))
+}
+
+/// Save an existing board's lists (and optionally its cards) as a template
+pub async fn save_board_as_template(
+    pool: &DbPool,
+    board_id: i32,
+    req: SaveBoardAsTemplateRequest,
+) -> Result<BoardTemplate, AppError> {
+    if req.name.trim().is_empty() {
+        return Err(AppError::ValidationError("Name is required".to_string()));
+    }
+
+    get_board_by_id(pool, board_id).await?;
+
+    let columns = sqlx::query_as::<_, BoardColumn>(&format!(
+        "SELECT {COLUMN_COLUMNS}
+         FROM board_column
+         WHERE board_id = $1
+         ORDER BY rank ASC"
+    ))
+    .bind(board_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut definition = BoardTemplateDefinition::default();
+    for board_column in columns {
+        let cards = if req.include_cards {
+            sqlx::query_as::<_, Card>(&format!(
+                "SELECT {CARD_COLUMNS} FROM card WHERE list_id = $1 ORDER BY lane_id ASC, rank ASC"
+            ))
+            .bind(board_column.id)
+            .fetch_all(pool)
+            .await?
+        } else {
+            Vec::new()
+        };
+
+        definition.columns.push(TemplateColumn {
+            title: board_column.title,
+            category: board_column.category,
+            cards: cards
+                .into_iter()
+                .map(|card| TemplateCard {
+                    title: card.title,
+                    description: card.description,
+                    labels: card.labels,
+                    priority: card.priority,
+                    estimate: card.estimate,
+                    estimate_unit: card.estimate_unit,
+                })
+                .collect(),
+        });
+    }
+
+    let template = sqlx::query_as::<_, BoardTemplate>(&format!(
+        "INSERT INTO board_template (name, description, built_in, definition)
+         VALUES ($1, $2, FALSE, $3)
+         RETURNING {TEMPLATE_COLUMNS}"
+    ))
+    .bind(&req.name)
+    .bind(&req.description)
+    .bind(Json(definition))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(template)
+}
+
+/// Delete a saved template. Built-in templates cannot be deleted.
+pub async fn delete_template(pool: &DbPool, id: i32) -> Result<(), AppError> {
+    let template = get_template_by_id(pool, id).await?;
+    if template.built_in {
+        return Err(AppError::ValidationError(
+            "Built-in templates cannot be deleted".to_string(),
+        ));
+    }
+
+    sqlx::query("DELETE FROM board_template WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Create a template's lists and seed cards on a (new) board
+pub async fn instantiate_template(
+    conn: &mut PgConnection,
+    board_id: i32,
+    definition: &BoardTemplateDefinition,
+) -> Result<(), AppError> {
+    let lane_id = lanes::get_default_lane_id(&mut *conn, board_id).await?;
+    let now = Utc::now();
+    let column_ranks = ranks::spread_ranks(definition.columns.len());
+    for (template_column, column_rank) in definition.columns.iter().zip(&column_ranks) {
+        let (started_at, completed_at) = stamps_on_enter(template_column.category, (None, None), now);
+        let list_id: i32 = sqlx::query_scalar(
+            "INSERT INTO board_column (title, board_id, rank, category)
+             VALUES ($1, $2, $3, $4)
+             RETURNING id"
+        )
+        .bind(&template_column.title)
+        .bind(board_id)
+        .bind(column_rank)
+        .bind(template_column.category)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let card_ranks = ranks::spread_ranks(template_column.cards.len());
+        for (seed, card_rank) in template_column.cards.iter().zip(&card_ranks) {
+            let (card_number, card_key) = card_keys::allocate_card_key(&mut *conn, board_id).await?;
+
+            let card_id: i32 = sqlx::query_scalar(
+                "INSERT INTO card (card_number, card_key, title, description, list_id, lane_id,
+                                   rank, labels, priority, estimate, estimate_unit,
+                                   started_at, completed_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                 RETURNING id"
+            )
+            .bind(card_number)
+            .bind(&card_key)
+            .bind(&seed.title)
+            .bind(&seed.description)
+            .bind(list_id)
+            .bind(lane_id)
+            .bind(card_rank)
+            .bind(&seed.labels)
+            .bind(seed.priority)
+            .bind(seed.estimate)
+            .bind(seed.estimate.map(|_| seed.estimate_unit.unwrap_or_default()))
+            .bind(started_at)
+            .bind(completed_at)
+            .fetch_one(&mut *conn)
+            .await?;
+
+            if let Some(description) = seed.description.as_deref() {
+                mentions::sync_mentions(&mut *conn, card_id, list_id, None, description).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
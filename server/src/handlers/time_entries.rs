@@ -0,0 +1,362 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::db::DbPool;
+use crate::errors::{is_unique_violation, AppError};
+use crate::models::{
+    CreateTimeEntryRequest, StartTimerRequest, StopTimerRequest, TimeEntry, TimeReportGroupBy,
+    TimeReportQuery, TimeReportRow,
+};
+
+pub(crate) const TIME_ENTRY_COLUMNS: &str =
+    "id, card_id, user_id, started_at, ended_at, duration_seconds, note, created_at";
+
+/// Longest time entry that can be logged: a year
+const MAX_ENTRY_SECONDS: i64 = 366 * 24 * 60 * 60;
+
+/// Return a 404 if the card does not exist
+async fn ensure_card_exists(pool: &DbPool, card_id: i32) -> Result<(), AppError> {
+    let card_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM card WHERE id = $1)"
+    )
+    .bind(card_id)
+    .fetch_one(pool)
+    .await?;
+
+    if !card_exists {
+        return Err(AppError::NotFound(format!("Card with id {} not found", card_id)));
+    }
+
+    Ok(())
+}
+
+fn validate_user_id(user_id: &str) -> Result<(), AppError> {
+    if user_id.trim().is_empty() {
+        return Err(AppError::ValidationError("userId is required".to_string()));
+    }
+    Ok(())
+}
+
+/// Get all time entries of a card, newest first
+pub async fn get_time_entries_for_card(pool: &DbPool, card_id: i32) -> Result<Vec<TimeEntry>, AppError> {
+    ensure_card_exists(pool, card_id).await?;
+
+    let entries = sqlx::query_as::<_, TimeEntry>(&format!(
+        "SELECT {TIME_ENTRY_COLUMNS}
+         FROM time_entry
+         WHERE card_id = $1
+         ORDER BY started_at DESC, id DESC"
+    ))
+    .bind(card_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+/// Log a finished time entry on a card
+pub async fn create_time_entry(
+    pool: &DbPool,
+    card_id: i32,
+    req: CreateTimeEntryRequest,
+) -> Result<TimeEntry, AppError> {
+    validate_user_id(&req.user_id)?;
+
+    let (ended_at, duration_seconds) = entry_end(req.started_at, req.ended_at, req.duration_seconds)?;
+
+    ensure_card_exists(pool, card_id).await?;
+
+    let entry = sqlx::query_as::<_, TimeEntry>(&format!(
+        "INSERT INTO time_entry (card_id, user_id, started_at, ended_at, duration_seconds, note)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING {TIME_ENTRY_COLUMNS}"
+    ))
+    .bind(card_id)
+    .bind(&req.user_id)
+    .bind(req.started_at)
+    .bind(ended_at)
+    .bind(duration_seconds)
+    .bind(&req.note)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+/// The end and duration of a logged entry, from whichever of the two was given
+fn entry_end(
+    started_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+    duration_seconds: Option<i64>,
+) -> Result<(DateTime<Utc>, i64), AppError> {
+    let duration_seconds = match (ended_at, duration_seconds) {
+        (Some(ended_at), None) => (ended_at - started_at).num_seconds(),
+        (None, Some(seconds)) => seconds,
+        _ => {
+            return Err(AppError::ValidationError(
+                "Provide either endedAt or durationSeconds".to_string(),
+            ))
+        }
+    };
+
+    if duration_seconds <= 0 {
+        return Err(AppError::ValidationError(
+            "Time entry must have a positive duration".to_string(),
+        ));
+    }
+    if duration_seconds > MAX_ENTRY_SECONDS {
+        return Err(AppError::ValidationError(format!(
+            "Time entry must not be longer than {} seconds",
+            MAX_ENTRY_SECONDS
+        )));
+    }
+
+    let ended_at = match ended_at {
+        Some(ended_at) => ended_at,
+        None => TimeDelta::try_seconds(duration_seconds)
+            .and_then(|duration| started_at.checked_add_signed(duration))
+            .ok_or_else(|| AppError::ValidationError("Time entry ends out of range".to_string()))?,
+    };
+
+    Ok((ended_at, duration_seconds))
+}
+
+/// Start a timer on a card. Any timer the user has running elsewhere is
+/// stopped first, so a user never has more than one running timer.
+pub async fn start_timer(
+    pool: &DbPool,
+    card_id: i32,
+    req: StartTimerRequest,
+) -> Result<TimeEntry, AppError> {
+    validate_user_id(&req.user_id)?;
+    ensure_card_exists(pool, card_id).await?;
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "UPDATE time_entry
+         SET ended_at = NOW(),
+             duration_seconds = EXTRACT(EPOCH FROM NOW() - started_at)::BIGINT
+         WHERE user_id = $1 AND ended_at IS NULL"
+    )
+    .bind(&req.user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    // The partial unique index on running timers rejects a concurrent start
+    let entry = sqlx::query_as::<_, TimeEntry>(&format!(
+        "INSERT INTO time_entry (card_id, user_id, started_at, note)
+         VALUES ($1, $2, $3, $4)
+         RETURNING {TIME_ENTRY_COLUMNS}"
+    ))
+    .bind(card_id)
+    .bind(&req.user_id)
+    .bind(Utc::now())
+    .bind(&req.note)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| {
+        if is_unique_violation(&err) {
+            AppError::Conflict("A timer is already running for this user".to_string())
+        } else {
+            err.into()
+        }
+    })?;
+
+    tx.commit().await?;
+
+    Ok(entry)
+}
+
+/// Stop the user's running timer on a card
+pub async fn stop_timer(
+    pool: &DbPool,
+    card_id: i32,
+    req: StopTimerRequest,
+) -> Result<TimeEntry, AppError> {
+    validate_user_id(&req.user_id)?;
+
+    let entry = sqlx::query_as::<_, TimeEntry>(&format!(
+        "UPDATE time_entry
+         SET ended_at = NOW(),
+             duration_seconds = EXTRACT(EPOCH FROM NOW() - started_at)::BIGINT
+         WHERE card_id = $1 AND user_id = $2 AND ended_at IS NULL
+         RETURNING {TIME_ENTRY_COLUMNS}"
+    ))
+    .bind(card_id)
+    .bind(&req.user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    entry.ok_or_else(|| AppError::NotFound("No running timer on this card for this user".to_string()))
+}
+
+/// Delete a time entry
+pub async fn delete_time_entry(pool: &DbPool, id: i32) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM time_entry WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Time entry not found".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Aggregate finished time entries, filtered by user, board, label and date range
+pub async fn get_time_report(pool: &DbPool, query: &TimeReportQuery) -> Result<Vec<TimeReportRow>, AppError> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(AppError::ValidationError("from must not be after to".to_string()));
+        }
+    }
+
+    // Grouping by label counts an entry once for every label of its card
+    let (key, label_join) = match query.group_by {
+        TimeReportGroupBy::User => ("te.user_id", ""),
+        TimeReportGroupBy::Board => ("b.title || ' #' || b.id", ""),
+        TimeReportGroupBy::Label => (
+            "COALESCE(l.label, '(none)')",
+            "LEFT JOIN LATERAL unnest(c.labels) AS l(label) ON TRUE",
+        ),
+        TimeReportGroupBy::Day => ("to_char(te.started_at AT TIME ZONE 'UTC', 'YYYY-MM-DD')", ""),
+    };
+
+    let rows = sqlx::query_as::<_, TimeReportRow>(&format!(
+        "SELECT {key} AS key,
+                COALESCE(SUM(te.duration_seconds), 0)::BIGINT AS total_seconds,
+                COUNT(*) AS entry_count
+         FROM time_entry te
+         JOIN card c ON c.id = te.card_id
+         JOIN board_column bc ON bc.id = c.list_id
+         JOIN board b ON b.id = bc.board_id
+         {label_join}
+         WHERE te.ended_at IS NOT NULL
+           AND ($1::TEXT IS NULL OR te.user_id = $1)
+           AND ($2::INT IS NULL OR b.id = $2)
+           AND ($3::TEXT IS NULL OR $3 = ANY(c.labels))
+           AND ($4::DATE IS NULL OR te.started_at >= $4::DATE)
+           AND ($5::DATE IS NULL OR te.started_at < $5::DATE + 1)
+         GROUP BY 1
+         ORDER BY 1"
+    ))
+    .bind(&query.user_id)
+    .bind(query.board_id)
+    .bind(&query.label)
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break. A
+/// field that a spreadsheet would run as a formula gets a leading `'`.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Render a time report as CSV
+pub fn time_report_to_csv(group_by: TimeReportGroupBy, rows: &[TimeReportRow]) -> String {
+    let key_header = match group_by {
+        TimeReportGroupBy::User => "user",
+        TimeReportGroupBy::Board => "board",
+        TimeReportGroupBy::Label => "label",
+        TimeReportGroupBy::Day => "day",
+    };
+
+    let mut csv = format!("{key_header},total_seconds,total_hours,entry_count\n");
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{:.2},{}\n",
+            csv_field(&row.key),
+            row.total_seconds,
+            row.total_seconds as f64 / 3600.0,
+            row.entry_count
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn entry_end_from_duration_or_end() {
+        let started_at = at("2024-03-01T09:00:00Z");
+        assert_eq!(
+            entry_end(started_at, None, Some(5400)).unwrap(),
+            (at("2024-03-01T10:30:00Z"), 5400)
+        );
+        assert_eq!(
+            entry_end(started_at, Some(at("2024-03-01T09:15:00Z")), None).unwrap(),
+            (at("2024-03-01T09:15:00Z"), 900)
+        );
+    }
+
+    #[test]
+    fn entry_end_needs_exactly_one_of_end_and_duration() {
+        let started_at = at("2024-03-01T09:00:00Z");
+        assert!(entry_end(started_at, None, None).is_err());
+        assert!(entry_end(started_at, Some(at("2024-03-01T10:00:00Z")), Some(3600)).is_err());
+    }
+
+    #[test]
+    fn entry_end_rejects_durations_out_of_range() {
+        let started_at = at("2024-03-01T09:00:00Z");
+        assert!(entry_end(started_at, None, Some(0)).is_err());
+        assert!(entry_end(started_at, Some(at("2024-03-01T08:00:00Z")), None).is_err());
+        assert!(entry_end(started_at, None, Some(MAX_ENTRY_SECONDS + 1)).is_err());
+        assert!(entry_end(started_at, None, Some(i64::MAX)).is_err());
+        assert!(entry_end(started_at, Some(at("2099-01-01T00:00:00Z")), None).is_err());
+        assert!(entry_end(DateTime::<Utc>::MAX_UTC, None, Some(60)).is_err());
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("alice"), "alice");
+        assert_eq!(csv_field("Board #3"), "Board #3");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+    }
+
+    #[test]
+    fn csv_field_defuses_formulas() {
+        assert_eq!(csv_field("=SUM(A1:A9)"), "'=SUM(A1:A9)");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-2"), "'-2");
+        assert_eq!(csv_field("@alice"), "'@alice");
+        assert_eq!(csv_field("=HYPERLINK(\"x\",\"y\")"), "\"'=HYPERLINK(\"\"x\"\",\"\"y\"\")\"");
+        assert_eq!(csv_field("a=b"), "a=b");
+    }
+
+    #[test]
+    fn time_report_csv_has_header_and_hours() {
+        let rows = vec![
+            TimeReportRow { key: "alice".to_string(), total_seconds: 5400, entry_count: 2 },
+            TimeReportRow { key: "Launch, v2 #4".to_string(), total_seconds: 60, entry_count: 1 },
+        ];
+        assert_eq!(
+            time_report_to_csv(TimeReportGroupBy::User, &rows),
+            "user,total_seconds,total_hours,entry_count\nalice,5400,1.50,2\n\"Launch, v2 #4\",60,0.02,1\n"
+        );
+    }
+}
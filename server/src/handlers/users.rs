@@ -0,0 +1,52 @@
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{UpdateUserProfileRequest, UserProfile};
+
+/// Longest display name accepted
+const MAX_DISPLAY_NAME_CHARS: usize = 100;
+
+/// Get a user's profile; users without one are shown by their ID
+pub async fn get_profile(pool: &DbPool, user_id: &str) -> Result<UserProfile, AppError> {
+    let profile = sqlx::query_as::<_, UserProfile>(
+        "SELECT user_id, display_name, updated_at FROM user_profile WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(profile.unwrap_or_else(|| UserProfile {
+        user_id: user_id.to_string(),
+        display_name: user_id.to_string(),
+        updated_at: None,
+    }))
+}
+
+/// Set a user's display name
+pub async fn update_profile(
+    pool: &DbPool,
+    user_id: &str,
+    req: UpdateUserProfileRequest,
+) -> Result<UserProfile, AppError> {
+    let display_name = req.display_name.trim();
+    if display_name.is_empty() {
+        return Err(AppError::ValidationError("displayName is required".to_string()));
+    }
+    if display_name.chars().count() > MAX_DISPLAY_NAME_CHARS {
+        return Err(AppError::ValidationError(format!(
+            "displayName must be at most {} characters",
+            MAX_DISPLAY_NAME_CHARS
+        )));
+    }
+
+    let profile = sqlx::query_as::<_, UserProfile>(
+        "INSERT INTO user_profile (user_id, display_name) VALUES ($1, $2)
+         ON CONFLICT (user_id) DO UPDATE SET display_name = EXCLUDED.display_name, updated_at = NOW()
+         RETURNING user_id, display_name, updated_at"
+    )
+    .bind(user_id)
+    .bind(display_name)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(profile)
+}
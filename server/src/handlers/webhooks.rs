@@ -0,0 +1,631 @@
+//! Outgoing webhooks.
+//!
+//! Handlers call [`emit`] (or [`emit_card`]) inside the transaction of the
+//! change they report, which queues one `webhook_delivery` row per matching
+//! active webhook of the board. The `webhook_deliveries` job POSTs pending
+//! deliveries, signing each body with the webhook's secret, and retries
+//! failures with exponential backoff. A webhook whose attempts keep failing
+//! is disabled until it is re-enabled through the API.
+//!
+//! Deliveries only go to public addresses: the client resolves host names
+//! itself and drops loopback, private, link-local and other special-purpose
+//! addresses, so a webhook cannot be pointed at this host or its network
+//! (and its responses read back from the delivery history). Set
+//! `WEBHOOK_ALLOW_PRIVATE_NETWORKS=true` to allow them, e.g. in development.
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::types::Json;
+use sqlx::PgConnection;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers::boards::get_board_by_id;
+use crate::handlers::card_keys;
+use crate::models::{
+    Card, CreateWebhookRequest, UpdateWebhookRequest, Webhook, WebhookDelivery, WebhookDeliveryQuery,
+    WebhookDeliveryStatus, WebhookEvent,
+};
+
+const WEBHOOK_COLUMNS: &str = "id, board_id, url, events, active, consecutive_failures, \
+                               disabled_reason, created_at, updated_at";
+
+const DELIVERY_COLUMNS: &str = "id, webhook_id, event, payload, status, attempts, next_attempt_at, \
+                                response_status, response_body, error, duration_ms, delivered_at, \
+                                redelivery_of, created_at";
+
+/// Deliveries sent per run of the delivery job
+const DELIVERY_BATCH: i64 = 100;
+
+/// A delivery is given up after this many failed attempts
+const MAX_ATTEMPTS: i32 = 6;
+
+/// Delay before the first retry; doubles with each further attempt
+const RETRY_BASE_SECS: i64 = 30;
+
+/// A webhook is disabled after this many failed attempts in a row, across
+/// all of its deliveries
+const DISABLE_AFTER_FAILURES: i32 = 15;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A delivery being sent is claimed for this long; one claimed by an
+/// instance that crashed is sent again afterwards
+const CLAIM_SECS: f64 = 120.0;
+
+/// Longest response body kept in the delivery history
+const RESPONSE_BODY_CHARS: usize = 2000;
+
+const MIN_SECRET_LEN: usize = 16;
+
+const DEFAULT_HISTORY_LIMIT: i64 = 50;
+
+const MAX_HISTORY_LIMIT: i64 = 500;
+
+/// Whether deliveries may go to private and other non-public addresses
+fn private_networks_allowed() -> bool {
+    static ALLOWED: OnceLock<bool> = OnceLock::new();
+    *ALLOWED.get_or_init(|| {
+        std::env::var("WEBHOOK_ALLOW_PRIVATE_NETWORKS").is_ok_and(|value| value.eq_ignore_ascii_case("true"))
+    })
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Shared address space (carrier-grade NAT)
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking
+        || (a == 198 && (18..20).contains(&b))
+        // Reserved
+        || a >= 240)
+}
+
+/// Whether an address is reachable on the public internet. IPv4 addresses
+/// embedded in IPv6 ones are judged as IPv4.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ipv4(ip);
+            }
+            let segments = ip.segments();
+            // NAT64
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [.., a, b, c, d] = ip.octets();
+                return is_public_ipv4(Ipv4Addr::new(a, b, c, d));
+            }
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+                // IPv4-compatible (deprecated) addresses
+                || segments[..6] == [0; 6]
+                // Documentation
+                || segments[..2] == [0x2001, 0xdb8]
+                // 6to4, which embeds any IPv4 address
+                || segments[0] == 0x2002)
+        }
+    }
+}
+
+/// Resolves host names to their public addresses only; a name with none
+/// fails to resolve
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| private_networks_allowed() || is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            // A proxy would resolve names itself, bypassing the resolver
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver))
+            .user_agent(concat!("task-management-webhooks/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("Failed to build webhook HTTP client")
+    })
+}
+
+/// Why a URL's host may not be delivered to, when it is an address that is
+/// not public. Host names are checked when they are resolved.
+fn blocked_host(url: &reqwest::Url) -> Option<String> {
+    // IP hosts are serialised in canonical form, IPv6 ones in brackets
+    let ip: IpAddr = url.host_str()?.trim_start_matches('[').trim_end_matches(']').parse().ok()?;
+    (!private_networks_allowed() && !is_public_ip(ip)).then(|| format!("{} is not a public address", ip))
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of the body
+fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Queue a payload for every active webhook of the board that subscribes to
+/// `event`. The payload is `{ event, boardId, occurredAt, data }`.
+pub async fn emit<T: Serialize>(
+    conn: &mut PgConnection,
+    board_id: i32,
+    event: WebhookEvent,
+    data: &T,
+) -> Result<(), AppError> {
+    let payload = json!({
+        "event": event.name(),
+        "boardId": board_id,
+        "occurredAt": Utc::now(),
+        "data": data,
+    });
+
+    sqlx::query(
+        "INSERT INTO webhook_delivery (webhook_id, event, payload)
+         SELECT id, $2, $3 FROM webhook
+         WHERE board_id = $1 AND active AND (events = '[]'::JSONB OR events ? $2)"
+    )
+    .bind(board_id)
+    .bind(event.name())
+    .bind(Json(payload))
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Queue a card event for the webhooks of the card's board
+pub async fn emit_card(conn: &mut PgConnection, event: WebhookEvent, card: &Card) -> Result<(), AppError> {
+    let board_id = card_keys::get_board_id_for_list(&mut *conn, card.list_id).await?;
+    emit(conn, board_id, event, card).await
+}
+
+fn validate_url(url: &str) -> Result<(), AppError> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|_| AppError::ValidationError(format!("Invalid webhook URL: {}", url)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::ValidationError("Webhook URL must use http or https".to_string()));
+    }
+    if let Some(reason) = blocked_host(&parsed) {
+        return Err(AppError::ValidationError(format!("Invalid webhook URL: {}", reason)));
+    }
+    Ok(())
+}
+
+fn validate_secret(secret: &str) -> Result<(), AppError> {
+    if secret.len() < MIN_SECRET_LEN {
+        return Err(AppError::ValidationError(format!(
+            "Webhook secret must be at least {} characters",
+            MIN_SECRET_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Get the webhooks of a board
+pub async fn get_webhooks_for_board(pool: &DbPool, board_id: i32) -> Result<Vec<Webhook>, AppError> {
+    get_board_by_id(pool, board_id).await?;
+
+    let webhooks = sqlx::query_as::<_, Webhook>(&format!(
+        "SELECT {WEBHOOK_COLUMNS} FROM webhook WHERE board_id = $1 ORDER BY id ASC"
+    ))
+    .bind(board_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(webhooks)
+}
+
+/// Get a webhook by ID
+pub async fn get_webhook_by_id(pool: &DbPool, id: i32) -> Result<Webhook, AppError> {
+    let webhook = sqlx::query_as::<_, Webhook>(&format!("SELECT {WEBHOOK_COLUMNS} FROM webhook WHERE id = $1"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    webhook.ok_or_else(|| AppError::NotFound(format!("Webhook with id {} not found", id)))
+}
+
+/// Subscribe a URL to a board's events
+pub async fn create_webhook(pool: &DbPool, board_id: i32, req: CreateWebhookRequest) -> Result<Webhook, AppError> {
+    validate_url(&req.url)?;
+    validate_secret(&req.secret)?;
+    get_board_by_id(pool, board_id).await?;
+
+    let webhook = sqlx::query_as::<_, Webhook>(&format!(
+        "INSERT INTO webhook (board_id, url, secret, events) VALUES ($1, $2, $3, $4) RETURNING {WEBHOOK_COLUMNS}"
+    ))
+    .bind(board_id)
+    .bind(&req.url)
+    .bind(&req.secret)
+    .bind(Json(&req.events))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(webhook)
+}
+
+/// Update a webhook. Re-enabling it resets its failure count; pending
+/// deliveries are then sent again.
+pub async fn update_webhook(pool: &DbPool, id: i32, req: UpdateWebhookRequest) -> Result<Webhook, AppError> {
+    if let Some(url) = &req.url {
+        validate_url(url)?;
+    }
+    if let Some(secret) = &req.secret {
+        validate_secret(secret)?;
+    }
+
+    let webhook = sqlx::query_as::<_, Webhook>(&format!(
+        "UPDATE webhook
+         SET url = COALESCE($2, url),
+             secret = COALESCE($3, secret),
+             events = COALESCE($4, events),
+             active = COALESCE($5, active),
+             consecutive_failures = CASE WHEN $5 THEN 0 ELSE consecutive_failures END,
+             disabled_reason = CASE WHEN $5 THEN NULL ELSE disabled_reason END,
+             updated_at = NOW()
+         WHERE id = $1
+         RETURNING {WEBHOOK_COLUMNS}"
+    ))
+    .bind(id)
+    .bind(&req.url)
+    .bind(&req.secret)
+    .bind(req.events.as_ref().map(Json))
+    .bind(req.active)
+    .fetch_optional(pool)
+    .await?;
+
+    webhook.ok_or_else(|| AppError::NotFound(format!("Webhook with id {} not found", id)))
+}
+
+/// Delete a webhook and its delivery history
+pub async fn delete_webhook(pool: &DbPool, id: i32) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM webhook WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Webhook with id {} not found", id)));
+    }
+
+    Ok(())
+}
+
+/// Get a webhook's deliveries, newest first
+pub async fn get_deliveries(
+    pool: &DbPool,
+    webhook_id: i32,
+    query: &WebhookDeliveryQuery,
+) -> Result<Vec<WebhookDelivery>, AppError> {
+    get_webhook_by_id(pool, webhook_id).await?;
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+
+    let deliveries = sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "SELECT {DELIVERY_COLUMNS}
+         FROM webhook_delivery
+         WHERE webhook_id = $1 AND ($2::webhook_delivery_status IS NULL OR status = $2)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3"
+    ))
+    .bind(webhook_id)
+    .bind(query.status)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deliveries)
+}
+
+/// Queue a delivery's payload again as a new delivery, sent at the next run
+/// of the delivery job
+pub async fn redeliver(pool: &DbPool, webhook_id: i32, delivery_id: i32) -> Result<WebhookDelivery, AppError> {
+    let delivery = sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "INSERT INTO webhook_delivery (webhook_id, event, payload, redelivery_of)
+         SELECT webhook_id, event, payload, id FROM webhook_delivery WHERE id = $1 AND webhook_id = $2
+         RETURNING {DELIVERY_COLUMNS}"
+    ))
+    .bind(delivery_id)
+    .bind(webhook_id)
+    .fetch_optional(pool)
+    .await?;
+
+    delivery.ok_or_else(|| {
+        AppError::NotFound(format!("Delivery {} of webhook {} not found", delivery_id, webhook_id))
+    })
+}
+
+#[derive(sqlx::FromRow)]
+struct QueuedDelivery {
+    id: i32,
+    webhook_id: i32,
+    event: String,
+    payload: Json<serde_json::Value>,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+/// Outcome of one HTTP attempt
+struct Attempt {
+    status: Option<i32>,
+    body: Option<String>,
+    error: Option<String>,
+    duration_ms: i32,
+}
+
+impl Attempt {
+    fn succeeded(&self) -> bool {
+        self.status.is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+/// An error with its causes, which say why a request could not be sent
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
+}
+
+async fn attempt(delivery: &QueuedDelivery) -> Attempt {
+    let body = serde_json::to_vec(&delivery.payload.0).unwrap_or_default();
+    let started = Instant::now();
+
+    let blocked = reqwest::Url::parse(&delivery.url)
+        .map_err(|err| err.to_string())
+        .and_then(|url| blocked_host(&url).map_or(Ok(()), Err));
+    if let Err(reason) = blocked {
+        return Attempt { status: None, body: None, error: Some(reason), duration_ms: 0 };
+    }
+
+    let response = client()
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Webhook-Event", &delivery.event)
+        .header("X-Webhook-Delivery", delivery.id.to_string())
+        .header("X-Webhook-Signature", signature(&delivery.secret, &body))
+        .body(body)
+        .send()
+        .await;
+
+    let (status, body, error) = match response {
+        Ok(response) => {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            let text: String = text.chars().take(RESPONSE_BODY_CHARS).collect();
+            let error = (!status.is_success()).then(|| format!("Endpoint answered {}", status));
+            (Some(status.as_u16() as i32), Some(text), error)
+        }
+        Err(err) => (None, None, Some(error_chain(&err))),
+    };
+
+    Attempt {
+        status,
+        body,
+        error,
+        duration_ms: started.elapsed().as_millis().min(i32::MAX as u128) as i32,
+    }
+}
+
+/// Claim the next due delivery of an active webhook by moving its next
+/// attempt `CLAIM_SECS` ahead, so no other instance sends it meanwhile
+async fn claim_delivery(pool: &DbPool) -> Result<Option<QueuedDelivery>, AppError> {
+    let delivery = sqlx::query_as(
+        "UPDATE webhook_delivery d
+         SET next_attempt_at = NOW() + make_interval(secs => $1)
+         FROM webhook w
+         WHERE w.id = d.webhook_id
+           AND d.id = (
+               SELECT d.id FROM webhook_delivery d
+               JOIN webhook w ON w.id = d.webhook_id
+               WHERE d.status = 'pending' AND d.next_attempt_at <= NOW() AND w.active
+               ORDER BY d.next_attempt_at ASC, d.id ASC
+               LIMIT 1
+               FOR UPDATE OF d SKIP LOCKED
+           )
+         RETURNING d.id, d.webhook_id, d.event, d.payload, d.attempts, w.url, w.secret"
+    )
+    .bind(CLAIM_SECS)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(delivery)
+}
+
+/// Send due deliveries of active webhooks, claiming them one at a time.
+/// Returns the number delivered.
+pub async fn deliver_pending(pool: &DbPool) -> Result<u64, AppError> {
+    let mut delivered = 0;
+    for _ in 0..DELIVERY_BATCH {
+        let Some(delivery) = claim_delivery(pool).await? else {
+            break;
+        };
+
+        let attempt = attempt(&delivery).await;
+        let attempts = delivery.attempts + 1;
+        let mut tx = pool.begin().await?;
+
+        if attempt.succeeded() {
+            sqlx::query(
+                "UPDATE webhook_delivery
+                 SET status = 'delivered', attempts = $2, response_status = $3, response_body = $4,
+                     error = NULL, duration_ms = $5, delivered_at = NOW()
+                 WHERE id = $1"
+            )
+            .bind(delivery.id)
+            .bind(attempts)
+            .bind(attempt.status)
+            .bind(&attempt.body)
+            .bind(attempt.duration_ms)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query("UPDATE webhook SET consecutive_failures = 0 WHERE id = $1")
+                .bind(delivery.webhook_id)
+                .execute(&mut *tx)
+                .await?;
+
+            delivered += 1;
+        } else {
+            let status = if attempts >= MAX_ATTEMPTS {
+                WebhookDeliveryStatus::Failed
+            } else {
+                WebhookDeliveryStatus::Pending
+            };
+            let retry_at = Utc::now() + chrono::Duration::seconds(RETRY_BASE_SECS << (attempts - 1).min(16));
+            tracing::warn!(
+                "Webhook {} delivery {} failed (attempt {}): {}",
+                delivery.webhook_id,
+                delivery.id,
+                attempts,
+                attempt.error.as_deref().unwrap_or("unknown error")
+            );
+
+            sqlx::query(
+                "UPDATE webhook_delivery
+                 SET status = $2, attempts = $3, next_attempt_at = $4, response_status = $5,
+                     response_body = $6, error = $7, duration_ms = $8
+                 WHERE id = $1"
+            )
+            .bind(delivery.id)
+            .bind(status)
+            .bind(attempts)
+            .bind(retry_at)
+            .bind(attempt.status)
+            .bind(&attempt.body)
+            .bind(&attempt.error)
+            .bind(attempt.duration_ms)
+            .execute(&mut *tx)
+            .await?;
+
+            let disabled: Option<bool> = sqlx::query_scalar(
+                "UPDATE webhook
+                 SET consecutive_failures = consecutive_failures + 1,
+                     active = consecutive_failures + 1 < $2,
+                     disabled_reason = CASE
+                         WHEN consecutive_failures + 1 >= $2 THEN 'Disabled after ' || $2 || ' failed attempts in a row'
+                         ELSE disabled_reason
+                     END,
+                     updated_at = NOW()
+                 WHERE id = $1 AND active
+                 RETURNING NOT active"
+            )
+            .bind(delivery.webhook_id)
+            .bind(DISABLE_AFTER_FAILURES)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if disabled == Some(true) {
+                tracing::warn!("Webhook {} disabled after repeated failures", delivery.webhook_id);
+            }
+        }
+
+        tx.commit().await?;
+    }
+
+    Ok(delivered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocked(url: &str) -> bool {
+        blocked_host(&reqwest::Url::parse(url).unwrap()).is_some()
+    }
+
+    #[test]
+    fn signature_is_hex_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn special_purpose_addresses_are_not_public() {
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "0.0.0.0",
+            "100.64.0.1", "192.0.0.8", "198.18.0.1", "224.0.0.1", "255.255.255.255", "240.0.0.1",
+            "::1", "::", "fe80::1", "fd00::1", "ff02::1", "::ffff:127.0.0.1", "::ffff:10.0.0.1",
+            "64:ff9b::a9fe:a9fe", "2001:db8::1", "2002:7f00:1::1", "::127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{} should not be public", ip);
+        }
+    }
+
+    #[test]
+    fn public_addresses_are_public() {
+        for ip in ["93.184.216.34", "8.8.8.8", "100.128.0.1", "2606:4700::1111", "::ffff:8.8.8.8"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{} should be public", ip);
+        }
+    }
+
+    #[test]
+    fn urls_with_private_ip_hosts_are_blocked() {
+        assert!(blocked("http://127.0.0.1:8080/hook"));
+        assert!(blocked("http://169.254.169.254/latest/meta-data/"));
+        assert!(blocked("http://[::1]/hook"));
+        assert!(blocked("http://[::ffff:7f00:1]/hook"));
+        assert!(blocked("http://0x7f.1/hook"));
+        assert!(blocked("http://2130706433/hook"));
+        assert!(!blocked("https://8.8.8.8/hook"));
+        // Names are checked when they are resolved
+        assert!(!blocked("https://example.com/hook"));
+        assert!(!blocked("http://localhost/hook"));
+    }
+
+    #[test]
+    fn webhook_urls_must_be_http_to_a_public_host() {
+        assert!(validate_url("https://example.com/hook").is_ok());
+        assert!(validate_url("ftp://example.com/hook").is_err());
+        assert!(validate_url("not a url").is_err());
+        assert!(validate_url("http://10.0.0.5/hook").is_err());
+    }
+
+    #[tokio::test]
+    async fn resolver_drops_non_public_addresses() {
+        use reqwest::dns::Resolve;
+
+        let name = "localhost".parse().unwrap();
+        assert!(PublicResolver.resolve(name).await.is_err());
+    }
+}
@@ -0,0 +1,50 @@
+use sqlx::PgConnection;
+
+use crate::errors::AppError;
+use crate::models::WipMode;
+
+/// Check whether `incoming` more cards fit into a column's WIP limit.
+///
+/// Returns a warning when an advisory limit is exceeded and fails with
+/// `WipLimitExceeded` when a strict limit would be. The column row is locked
+/// so concurrent moves into the same column cannot both slip under the limit.
+pub async fn check_wip_limit(
+    conn: &mut PgConnection,
+    list_id: i32,
+    incoming: i64,
+) -> Result<Option<String>, AppError> {
+    let column: Option<(String, Option<i32>, WipMode)> = sqlx::query_as(
+        "SELECT title, wip_limit, wip_mode FROM board_column WHERE id = $1 FOR UPDATE"
+    )
+    .bind(list_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some((title, wip_limit, wip_mode)) = column else {
+        return Err(AppError::NotFound(format!("List with id {} not found", list_id)));
+    };
+
+    let Some(limit) = wip_limit else {
+        return Ok(None);
+    };
+
+    let current: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM card WHERE list_id = $1")
+        .bind(list_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let load = current + incoming;
+    if load <= i64::from(limit) {
+        return Ok(None);
+    }
+
+    let message = format!(
+        "WIP limit of {} exceeded for list \"{}\" ({} cards)",
+        limit, title, load
+    );
+
+    match wip_mode {
+        WipMode::Advisory => Ok(Some(message)),
+        WipMode::Strict => Err(AppError::WipLimitExceeded(message)),
+    }
+}
@@ -41,6 +41,21 @@ async fn main() {
         .await
         .expect("Failed to connect to database");
 
+    handlers::templates::sync_builtin_templates(&db_pool)
+        .await
+        .expect("Failed to synchronise built-in board templates");
+
+    handlers::jobs::sync_jobs(&db_pool)
+        .await
+        .expect("Failed to register scheduled jobs");
+
+    // Run scheduled jobs until shutdown is requested
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let scheduler = tokio::spawn(handlers::jobs::run_scheduler(db_pool.clone(), shutdown_rx));
+
+    // Relay board presence changes announced by any instance
+    tokio::spawn(handlers::presence::run_listener(db_pool.clone()));
+
     // Configure CORS (allow all origins to match Node.js setup)
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -62,36 +77,258 @@ async fn main() {
             routes::boards::create_board,
             routes::boards::update_board,
             routes::boards::delete_board,
+            routes::boards::get_board_rollup,
+            routes::boards::copy_board,
+            routes::boards::export_board,
+            routes::boards::import_board,
+            routes::boards::save_board_as_template,
+            routes::boards::get_board_lanes,
+            routes::boards::create_lane,
+            routes::boards::get_board_details,
+            routes::boards::get_board_automations,
+            routes::boards::create_automation,
+            routes::boards::dry_run_automations,
+            routes::boards::get_board_webhooks,
+            routes::boards::create_webhook,
+            routes::boards::get_history,
+            routes::boards::undo,
+            routes::boards::redo,
+            routes::boards::get_presence,
+            routes::boards::watch_presence,
+            routes::webhooks::update_webhook,
+            routes::webhooks::delete_webhook,
+            routes::webhooks::get_deliveries,
+            routes::webhooks::redeliver,
+            routes::integrations::git_push,
+            routes::sync::get_changes,
+            routes::sync::push_operations,
+            routes::cards::get_commits,
+            routes::cards::get_revisions,
+            routes::cards::diff_revisions,
+            routes::cards::restore_revision,
+            routes::cards::collaborate_on_description,
+            routes::automations::update_automation,
+            routes::automations::delete_automation,
+            routes::automations::get_automation_runs,
+            routes::lanes::update_lane,
+            routes::lanes::delete_lane,
             routes::cards::get_cards,
             routes::cards::get_card,
             routes::cards::create_card,
             routes::cards::update_card,
             routes::cards::delete_card,
+            routes::cards::move_card,
+            routes::cards::copy_card,
+            routes::cards::get_time_entries,
+            routes::cards::create_time_entry,
+            routes::cards::start_timer,
+            routes::cards::stop_timer,
+            routes::cards::get_recurrence,
+            routes::cards::create_recurrence,
+            routes::cards::update_recurrence,
+            routes::cards::pause_recurrence,
+            routes::cards::resume_recurrence,
+            routes::cards::end_recurrence,
+            routes::cards::get_comments,
+            routes::cards::create_comment,
+            routes::cards::get_watchers,
+            routes::cards::watch_card,
+            routes::cards::unwatch_card,
+            routes::comments::update_comment,
+            routes::comments::delete_comment,
+            routes::notifications::get_notifications,
+            routes::notifications::mark_read,
+            routes::notifications::mark_unread,
+            routes::notifications::mark_all_read,
+            routes::notifications::get_preferences,
+            routes::notifications::update_preferences,
+            routes::users::get_profile,
+            routes::users::update_profile,
+            routes::email::get_email_settings,
+            routes::email::update_email_settings,
+            routes::email::confirm_unsubscribe,
+            routes::email::unsubscribe,
+            routes::email::get_outbox,
+            routes::email::retry_email,
+            routes::time_entries::delete_time_entry,
+            routes::templates::get_templates,
+            routes::templates::get_template,
+            routes::templates::delete_template,
+            routes::reports::get_time_report,
+            routes::jobs::get_jobs,
+            routes::jobs::update_job,
+            routes::jobs::run_job,
             routes::columns::get_lists,
             routes::columns::get_list,
             routes::columns::create_list,
             routes::columns::bulk_update_column_order,
             routes::columns::update_list,
             routes::columns::delete_list,
+            routes::columns::get_list_rollup,
+            routes::columns::copy_list,
+            routes::columns::move_list,
         ),
         components(schemas(
             models::Board,
             models::Card,
+            models::CardPriority,
+            models::EstimateUnit,
             models::BoardColumn,
+            models::WipMode,
+            models::ColumnCategory,
             models::CreateBoardRequest,
             models::UpdateBoardRequest,
             models::CreateCardRequest,
             models::UpdateCardRequest,
             models::CreateColumnRequest,
             models::UpdateColumnRequest,
+            models::CopyCardRequest,
+            models::CopyColumnRequest,
+            models::CopyBoardRequest,
+            models::MoveColumnRequest,
+            models::CardKeyChange,
+            models::LaneRemap,
+            models::ColumnMoveReport,
+            models::BoardTemplate,
+            models::BoardTemplateDefinition,
+            models::TemplateColumn,
+            models::TemplateCard,
+            models::SaveBoardAsTemplateRequest,
+            models::Swimlane,
+            models::CreateSwimlaneRequest,
+            models::UpdateSwimlaneRequest,
+            models::MoveCardRequest,
+            models::LaneCards,
+            models::ColumnDetails,
+            models::BoardDetails,
+            models::RuleTrigger,
+            models::RuleCondition,
+            models::RuleAction,
+            models::AutomationRule,
+            models::AutomationRun,
+            models::CreateAutomationRuleRequest,
+            models::UpdateAutomationRuleRequest,
+            models::AutomationDryRunRequest,
+            models::AutomationDryRunResult,
+            models::WebhookEvent,
+            models::Webhook,
+            models::CreateWebhookRequest,
+            models::UpdateWebhookRequest,
+            models::WebhookDeliveryStatus,
+            models::WebhookDelivery,
+            models::Mention,
+            models::CardRevision,
+            models::CardRevisionDiff,
+            models::RestoreRevisionRequest,
+            models::CardPlacement,
+            models::CardDependents,
+            models::UndoableOperation,
+            models::OperationState,
+            models::BoardOperation,
+            models::UndoRequest,
+            models::BoardViewer,
+            models::PresenceClientMessage,
+            models::PresenceServerMessage,
+            models::BoardExport,
+            models::ImportBoardRequest,
+            models::ImportedIds,
+            models::BoardImportResult,
+            models::SyncResponse,
+            models::SyncOperation,
+            models::SyncRequest,
+            models::SyncOperationStatus,
+            models::SyncOperationResult,
+            models::UserProfile,
+            models::UpdateUserProfileRequest,
+            models::GitPushPayload,
+            models::GitCommit,
+            models::GitAuthor,
+            models::GitRepository,
+            models::GitLabProject,
+            models::CardCommit,
+            models::GitCardLink,
+            models::GitPushResult,
             handlers::columns_bulk::BulkColumnOrderUpdate,
             handlers::columns_bulk::ColumnOrderUpdate,
+            models::TimeEntry,
+            models::CreateTimeEntryRequest,
+            models::StartTimerRequest,
+            models::StopTimerRequest,
+            models::TimeReportRow,
+            models::PriorityCounts,
+            models::EstimateRollup,
+            models::ColumnRollup,
+            models::BoardRollup,
+            models::ScheduledJob,
+            models::UpdateScheduledJobRequest,
+            models::RecurrenceWeekday,
+            models::RecurrenceRule,
+            models::RecurrenceStatus,
+            models::CardRecurrence,
+            models::CreateRecurrenceRequest,
+            models::UpdateRecurrenceRequest,
+            models::CardComment,
+            models::CreateCommentRequest,
+            models::UpdateCommentRequest,
+            models::WatchCardRequest,
+            models::NotificationKind,
+            models::Notification,
+            models::NotificationPreference,
+            models::UpdateNotificationPreferencesRequest,
+            models::MarkAllReadRequest,
+            models::EmailMode,
+            models::EmailSettings,
+            models::UpdateEmailSettingsRequest,
+            models::EmailStatus,
+            models::OutboxEmail,
             models::ApiResponse<models::Board>,
             models::ApiResponse<Vec<models::Board>>,
             models::ApiResponse<models::Card>,
             models::ApiResponse<Vec<models::Card>>,
             models::ApiResponse<models::BoardColumn>,
             models::ApiResponse<Vec<models::BoardColumn>>,
+            models::ApiResponse<models::TimeEntry>,
+            models::ApiResponse<Vec<models::TimeEntry>>,
+            models::ApiResponse<Vec<models::TimeReportRow>>,
+            models::ApiResponse<models::BoardTemplate>,
+            models::ApiResponse<Vec<models::BoardTemplate>>,
+            models::ApiResponse<models::ColumnRollup>,
+            models::ApiResponse<models::ColumnMoveReport>,
+            models::ApiResponse<models::BoardRollup>,
+            models::ApiResponse<models::Swimlane>,
+            models::ApiResponse<Vec<models::Swimlane>>,
+            models::ApiResponse<models::BoardDetails>,
+            models::ApiResponse<models::AutomationRule>,
+            models::ApiResponse<Vec<models::AutomationRule>>,
+            models::ApiResponse<Vec<models::AutomationRun>>,
+            models::ApiResponse<models::AutomationDryRunResult>,
+            models::ApiResponse<models::Webhook>,
+            models::ApiResponse<Vec<models::Webhook>>,
+            models::ApiResponse<models::WebhookDelivery>,
+            models::ApiResponse<Vec<models::WebhookDelivery>>,
+            models::ApiResponse<models::ScheduledJob>,
+            models::ApiResponse<Vec<models::ScheduledJob>>,
+            models::ApiResponse<models::CardRecurrence>,
+            models::ApiResponse<models::CardComment>,
+            models::ApiResponse<Vec<models::CardComment>>,
+            models::ApiResponse<Vec<String>>,
+            models::ApiResponse<models::Notification>,
+            models::ApiResponse<Vec<models::Notification>>,
+            models::ApiResponse<Vec<models::NotificationPreference>>,
+            models::ApiResponse<models::EmailSettings>,
+            models::ApiResponse<models::OutboxEmail>,
+            models::ApiResponse<Vec<models::OutboxEmail>>,
+            models::ApiResponse<models::UserProfile>,
+            models::ApiResponse<Vec<models::CardRevision>>,
+            models::ApiResponse<models::CardRevisionDiff>,
+            models::ApiResponse<models::GitPushResult>,
+            models::ApiResponse<Vec<models::CardCommit>>,
+            models::ApiResponse<models::BoardOperation>,
+            models::ApiResponse<Vec<models::BoardOperation>>,
+            models::ApiResponse<models::SyncResponse>,
+            models::ApiResponse<Vec<models::SyncOperationResult>>,
+            models::ApiResponse<Vec<models::BoardViewer>>,
+            models::ApiResponse<models::BoardImportResult>,
         ))
     )]
     struct ApiDoc;
@@ -102,6 +339,19 @@ async fn main() {
         .nest("/api/boards", routes::boards::router())
         .nest("/api/cards", routes::cards::router())
         .nest("/api/lists", routes::columns::router())
+        .nest("/api/lanes", routes::lanes::router())
+        .nest("/api/automations", routes::automations::router())
+        .nest("/api/webhooks", routes::webhooks::router())
+        .nest("/api/time-entries", routes::time_entries::router())
+        .nest("/api/reports", routes::reports::router())
+        .nest("/api/templates", routes::templates::router())
+        .nest("/api/jobs", routes::jobs::router())
+        .nest("/api/comments", routes::comments::router())
+        .nest("/api/notifications", routes::notifications::router())
+        .nest("/api/users", routes::users::router())
+        .nest("/api/email", routes::email::router())
+        .nest("/api/integrations", routes::integrations::router())
+        .nest("/api/sync", routes::sync::router())
         .merge(SwaggerUi::new("/swagger").url("/api/openapi.json", ApiDoc::openapi()))
         .with_state(db_pool)
         .layer(cors);
@@ -123,9 +373,15 @@ async fn main() {
 
     // Start server with graceful shutdown
     axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            let _ = shutdown_tx.send(true);
+        })
         .await
         .expect("Server error");
+
+    // Let a job that is still running finish and release its lease
+    scheduler.await.expect("Job scheduler panicked");
 }
 
 /// Health check endpoint
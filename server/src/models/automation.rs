@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use super::card::{Card, CardPriority};
+use super::column::ColumnCategory;
+
+/// Card event a rule reacts to. Omitted filters match any list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
+pub enum RuleTrigger {
+    /// A card was created or copied into a list
+    CardCreated {
+        #[serde(default, rename = "listId", skip_serializing_if = "Option::is_none")]
+        list_id: Option<i32>,
+    },
+    /// A card moved from one list to another
+    CardMoved {
+        #[serde(default, rename = "fromListId", skip_serializing_if = "Option::is_none")]
+        from_list_id: Option<i32>,
+        #[serde(default, rename = "toListId", skip_serializing_if = "Option::is_none")]
+        to_list_id: Option<i32>,
+    },
+    /// A card moved into a list of this category, or its list was
+    /// recategorised to it
+    CardEnteredCategory { category: ColumnCategory },
+    /// A card was edited through the API (changes made by rules do not count)
+    CardUpdated,
+}
+
+/// Condition on the card a rule fires for; all conditions of a rule must hold
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleCondition {
+    HasLabel { label: String },
+    LacksLabel { label: String },
+    /// `null` matches cards without a priority
+    PriorityIs { priority: Option<CardPriority> },
+    /// `null` matches unassigned cards
+    AssigneeIs {
+        #[serde(rename = "assigneeId")]
+        assignee_id: Option<String>,
+    },
+    /// Case-insensitive substring match on the title
+    TitleContains { text: String },
+}
+
+/// Change a rule makes to the card it fires for
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    AddLabel { label: String },
+    RemoveLabel { label: String },
+    /// `null` clears the priority
+    SetPriority { priority: Option<CardPriority> },
+    /// `null` removes the assignee
+    SetAssignee {
+        #[serde(rename = "assigneeId")]
+        assignee_id: Option<String>,
+    },
+    /// `null` clears the due date
+    SetDueInDays { days: Option<i64> },
+    /// Move the card to the end of another list of the same board
+    MoveToList {
+        #[serde(rename = "listId")]
+        list_id: i32,
+    },
+}
+
+/// Automation rule model (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AutomationRule {
+    pub id: i32,
+    #[serde(rename = "boardId")]
+    pub board_id: i32,
+    pub name: String,
+    pub enabled: bool,
+    #[schema(value_type = RuleTrigger)]
+    pub trigger: Json<RuleTrigger>,
+    #[schema(value_type = Vec<RuleCondition>)]
+    pub conditions: Json<Vec<RuleCondition>>,
+    #[schema(value_type = Vec<RuleAction>)]
+    pub actions: Json<Vec<RuleAction>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for creating an automation rule
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAutomationRuleRequest {
+    pub name: String,
+    /// Defaults to enabled
+    pub enabled: Option<bool>,
+    pub trigger: RuleTrigger,
+    #[serde(default)]
+    pub conditions: Vec<RuleCondition>,
+    pub actions: Vec<RuleAction>,
+}
+
+/// Request body for updating an automation rule
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateAutomationRuleRequest {
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+    pub trigger: Option<RuleTrigger>,
+    pub conditions: Option<Vec<RuleCondition>>,
+    pub actions: Option<Vec<RuleAction>>,
+}
+
+/// One execution of a rule on a card (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AutomationRun {
+    pub id: i32,
+    #[serde(rename = "ruleId")]
+    pub rule_id: i32,
+    #[serde(rename = "cardId")]
+    pub card_id: Option<i32>,
+    /// The event that fired the rule
+    #[schema(value_type = RuleTrigger)]
+    pub event: Json<RuleTrigger>,
+    #[schema(value_type = Vec<RuleAction>)]
+    pub actions: Json<Vec<RuleAction>>,
+    /// How many rule executions led to this one; 0 for a direct API change
+    pub depth: i32,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for simulating an event against a board's rules
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AutomationDryRunRequest {
+    #[serde(rename = "cardId")]
+    pub card_id: i32,
+    /// Event to simulate; list filters are filled in with concrete lists
+    pub event: RuleTrigger,
+}
+
+/// What a dry run would have done
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AutomationDryRunResult {
+    /// Rule executions, in order, including ones triggered by other rules
+    pub runs: Vec<AutomationRun>,
+    /// The card as it would look afterwards
+    pub card: Card,
+}
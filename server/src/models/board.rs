@@ -10,10 +10,24 @@ pub struct Board {
     pub title: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// The description rendered from Markdown to sanitised HTML; only
+    /// included when requested with `html=true`
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "descriptionHtml")]
+    pub description_html: Option<String>,
+    /// Prefix of the board's card keys, e.g. `OPS`
+    #[serde(rename = "keyPrefix")]
+    pub key_prefix: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub owner_id: Option<String>,
     #[serde(default)]
     pub members: Vec<String>,
+    /// Version from the server-wide change sequence, new with every change;
+    /// only included in sync responses
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
@@ -26,6 +40,14 @@ pub struct CreateBoardRequest {
     pub title: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Card key prefix; derived from the title when omitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "keyPrefix")]
+    pub key_prefix: Option<String>,
+    /// Template to create the board's lists and seed cards from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "templateId")]
+    pub template_id: Option<i32>,
 }
 
 /// Request body for updating a board
@@ -1,18 +1,65 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use sqlx::FromRow;
 use utoipa::ToSchema;
 
+use super::mention::Mention;
+
+/// Card priority (stored as the `card_priority` Postgres enum)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "card_priority", rename_all = "lowercase")]
+pub enum CardPriority {
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+/// Unit of a card estimate (stored as the `estimate_unit` Postgres enum)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "estimate_unit", rename_all = "lowercase")]
+pub enum EstimateUnit {
+    #[default]
+    Points,
+    Hours,
+}
+
 /// Card model (matches database schema)
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Card {
     pub id: i32,
+    /// Per-board sequence number
+    #[serde(rename = "number")]
+    pub card_number: i32,
+    /// Human-readable key, e.g. `OPS-42`
+    #[serde(rename = "key")]
+    pub card_key: String,
     pub title: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// The description rendered from Markdown to sanitised HTML; only
+    /// included when requested with `html=true`
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "descriptionHtml")]
+    pub description_html: Option<String>,
+    /// Board members @mentioned in the description
+    #[serde(default)]
+    #[schema(value_type = Vec<Mention>)]
+    pub mentions: Json<Vec<Mention>>,
     #[serde(rename = "listId")]
     pub list_id: i32,
+    #[serde(rename = "laneId")]
+    pub lane_id: i32,
+    /// Index within the card's (list, lane) cell, derived from its rank
     pub position: i32,
+    /// Rank ordering the card within its cell, compared bytewise; only included in sync responses
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "assigneeId")]
     pub assignee_id: Option<String>,
@@ -21,6 +68,29 @@ pub struct Card {
     pub due_date: Option<DateTime<Utc>>,
     #[serde(default)]
     pub labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<CardPriority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "estimateUnit")]
+    pub estimate_unit: Option<EstimateUnit>,
+    #[serde(default)]
+    #[serde(rename = "trackedSeconds")]
+    pub tracked_seconds: i64,
+    /// Set when the card first enters an in-progress or done column
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "startedAt")]
+    pub started_at: Option<DateTime<Utc>>,
+    /// Set when the card enters a done column
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "completedAt")]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Version from the server-wide change sequence, new with every change;
+    /// only included in sync responses
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
@@ -35,6 +105,10 @@ pub struct CreateCardRequest {
     pub description: Option<String>,
     #[serde(rename = "listId")]
     pub list_id: i32,
+    /// Swimlane of the card; defaults to the board's default lane
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "laneId")]
+    pub lane_id: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "assigneeId")]
     pub assignee_id: Option<String>,
@@ -43,10 +117,17 @@ pub struct CreateCardRequest {
     pub due_date: Option<DateTime<Utc>>,
     #[serde(default)]
     pub labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<CardPriority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "estimateUnit")]
+    pub estimate_unit: Option<EstimateUnit>,
 }
 
 /// Request body for updating a card
-#[derive(Debug, Deserialize, ToSchema)]
+#[derive(Debug, Default, Deserialize, ToSchema)]
 pub struct UpdateCardRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
@@ -56,6 +137,10 @@ pub struct UpdateCardRequest {
     #[serde(rename = "listId")]
     pub list_id: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "laneId")]
+    pub lane_id: Option<i32>,
+    /// Index to move the card to within its (new) cell
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub position: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "assigneeId")]
@@ -65,4 +150,25 @@ pub struct UpdateCardRequest {
     pub due_date: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<CardPriority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "estimateUnit")]
+    pub estimate_unit: Option<EstimateUnit>,
+    /// User making the edit, recorded in the card's revision history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "editedBy")]
+    pub edited_by: Option<String>,
+    /// Revision the edit restores; only set when restoring one
+    #[serde(skip)]
+    pub restored_from: Option<i32>,
+    /// Remove the description, for restoring a revision that had none
+    #[serde(skip)]
+    pub clears_description: bool,
+    /// Whether the description is a snapshot of its collaborative document,
+    /// which then needs no merging into the document
+    #[serde(skip)]
+    pub from_document: bool,
 }
@@ -3,6 +3,31 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::ToSchema;
 
+/// How a column's WIP limit is enforced (stored as the `wip_mode` Postgres enum)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "wip_mode", rename_all = "lowercase")]
+pub enum WipMode {
+    /// Exceeding the limit succeeds with a warning in the response
+    #[default]
+    Advisory,
+    /// Creating or moving a card into a full column fails
+    Strict,
+}
+
+/// What a column means for the card lifecycle (stored as the
+/// `column_category` Postgres enum)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+#[sqlx(type_name = "column_category", rename_all = "snake_case")]
+pub enum ColumnCategory {
+    Backlog,
+    #[default]
+    Todo,
+    InProgress,
+    Done,
+}
+
 /// BoardColumn model (matches database schema)
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct BoardColumn {
@@ -10,7 +35,27 @@ pub struct BoardColumn {
     pub title: String,
     #[serde(rename = "boardId")]
     pub board_id: i32,
+    /// Index among the board's columns, derived from the column's rank
     pub position: i32,
+    /// Rank ordering the column on its board, compared bytewise; only included in sync responses
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank: Option<String>,
+    pub category: ColumnCategory,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "wipLimit")]
+    pub wip_limit: Option<i32>,
+    #[serde(rename = "wipMode")]
+    pub wip_mode: WipMode,
+    /// Current number of cards in the column
+    #[serde(default)]
+    #[serde(rename = "cardCount")]
+    pub card_count: i64,
+    /// Version from the server-wide change sequence, new with every change;
+    /// only included in sync responses
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
@@ -23,6 +68,11 @@ pub struct CreateColumnRequest {
     pub title: String,
     #[serde(rename = "boardId")]
     pub board_id: i32,
+    pub category: Option<ColumnCategory>,
+    #[serde(rename = "wipLimit")]
+    pub wip_limit: Option<i32>,
+    #[serde(rename = "wipMode")]
+    pub wip_mode: Option<WipMode>,
 }
 
 /// Request body for updating a column/list
@@ -31,5 +81,70 @@ pub struct UpdateColumnRequest {
     pub title: Option<String>,
     #[serde(rename = "boardId")]
     pub board_id: Option<i32>,
+    /// Index to move the column to among its board's columns
+    pub position: Option<i32>,
+    pub category: Option<ColumnCategory>,
+    /// WIP limit; `0` removes the limit
+    #[serde(rename = "wipLimit")]
+    pub wip_limit: Option<i32>,
+    #[serde(rename = "wipMode")]
+    pub wip_mode: Option<WipMode>,
+    /// User making the change; renames are recorded on their undo stack
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+}
+
+/// Request body for moving a column/list with its cards to another board
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MoveColumnRequest {
+    /// Target board; may be the list's own board to only reorder it
+    #[serde(rename = "boardId")]
+    pub board_id: i32,
+    /// Target index among the board's lists; defaults to the end
     pub position: Option<i32>,
 }
+
+/// A card that got a new key because its list moved to another board
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CardKeyChange {
+    #[serde(rename = "cardId")]
+    pub card_id: i32,
+    /// Previous key, which keeps resolving to the card
+    #[serde(rename = "oldKey")]
+    pub old_key: String,
+    #[serde(rename = "newKey")]
+    pub new_key: String,
+}
+
+/// Where the cards of one source lane ended up on the target board
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LaneRemap {
+    #[serde(rename = "sourceLaneId")]
+    pub source_lane_id: i32,
+    #[serde(rename = "sourceLaneTitle")]
+    pub source_lane_title: String,
+    #[serde(rename = "targetLaneId")]
+    pub target_lane_id: i32,
+    #[serde(rename = "targetLaneTitle")]
+    pub target_lane_title: String,
+    /// `false` when no lane of the same title exists and the cards were
+    /// dropped into the target board's default lane
+    #[serde(rename = "matchedByTitle")]
+    pub matched_by_title: bool,
+    #[serde(rename = "cardCount")]
+    pub card_count: i64,
+}
+
+/// Result of moving a column/list, listing the board-scoped data that changed
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ColumnMoveReport {
+    pub column: BoardColumn,
+    #[serde(rename = "sourceBoardId")]
+    pub source_board_id: i32,
+    #[serde(rename = "targetBoardId")]
+    pub target_board_id: i32,
+    #[serde(rename = "rekeyedCards")]
+    pub rekeyed_cards: Vec<CardKeyChange>,
+    #[serde(rename = "remappedLanes")]
+    pub remapped_lanes: Vec<LaneRemap>,
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use super::mention::Mention;
+
+/// Card comment model (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CardComment {
+    pub id: i32,
+    #[serde(rename = "cardId")]
+    pub card_id: i32,
+    #[serde(rename = "authorId")]
+    pub author_id: String,
+    pub body: String,
+    /// Board members @mentioned in the body
+    #[serde(default)]
+    #[schema(value_type = Vec<Mention>)]
+    pub mentions: Json<Vec<Mention>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for commenting on a card
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCommentRequest {
+    #[serde(rename = "authorId")]
+    pub author_id: String,
+    pub body: String,
+}
+
+/// Request body for editing a comment
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCommentRequest {
+    pub body: String,
+}
+
+/// Request body for watching a card
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WatchCardRequest {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+}
@@ -0,0 +1,56 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+fn default_true() -> bool {
+    true
+}
+
+/// Request body for copying a card
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CopyCardRequest {
+    /// Target list; defaults to the card's own list
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "listId")]
+    pub list_id: Option<i32>,
+    /// Target position in the list; defaults to the end
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<i32>,
+    /// Title of the copy; defaults to the original title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Whether the copy keeps the card's labels
+    #[serde(default = "default_true")]
+    #[serde(rename = "keepLabels")]
+    pub keep_labels: bool,
+    /// Whether the copy gets copies of the card's comments
+    #[serde(default)]
+    #[serde(rename = "keepComments")]
+    pub keep_comments: bool,
+}
+
+/// Request body for copying a column/list with its cards
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CopyColumnRequest {
+    /// Target board; defaults to the list's own board
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "boardId")]
+    pub board_id: Option<i32>,
+    /// Target position among the board's lists; defaults to the end
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<i32>,
+    /// Title of the copy; defaults to the original title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// Request body for deep-copying a board
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CopyBoardRequest {
+    /// Title of the copy; defaults to "<original title> (copy)"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Card key prefix of the copy; derived from the title when omitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "keyPrefix")]
+    pub key_prefix: Option<String>,
+}
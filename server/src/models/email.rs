@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// How a user gets notifications by email (stored as the `email_mode` Postgres enum)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "email_mode", rename_all = "lowercase")]
+pub enum EmailMode {
+    /// One message per notification, within a minute or so
+    Immediate,
+    /// One message a day listing the notifications still unread
+    Digest,
+    /// No email
+    Off,
+}
+
+/// A user's email address and delivery mode (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct EmailSettings {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub address: String,
+    pub mode: EmailMode,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for setting a user's email address or delivery mode
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateEmailSettingsRequest {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    /// Required the first time
+    pub address: Option<String>,
+    /// Defaults to immediate the first time
+    pub mode: Option<EmailMode>,
+}
+
+/// Delivery state of an outgoing message (stored as the `email_status` Postgres enum)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "email_status", rename_all = "lowercase")]
+pub enum EmailStatus {
+    /// Waiting for its first or next attempt
+    Pending,
+    Sent,
+    /// Gave up after too many failed attempts
+    Failed,
+}
+
+/// Message in the email outbox (matches database schema, without the bodies)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OutboxEmail {
+    pub id: i32,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "toAddress")]
+    pub to_address: String,
+    pub subject: String,
+    pub status: EmailStatus,
+    /// Failed attempts so far
+    pub attempts: i32,
+    #[serde(rename = "nextAttemptAt")]
+    pub next_attempt_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "sentAt")]
+    pub sent_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters for listing the outbox
+#[derive(Debug, Deserialize)]
+pub struct OutboxQuery {
+    pub status: Option<EmailStatus>,
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Query parameters of an unsubscribe link
+#[derive(Debug, Deserialize)]
+pub struct UnsubscribeQuery {
+    pub token: String,
+}
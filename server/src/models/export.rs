@@ -0,0 +1,84 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::automation::AutomationRule;
+use super::board::Board;
+use super::card::Card;
+use super::column::BoardColumn;
+use super::comment::CardComment;
+use super::swimlane::Swimlane;
+use super::time_entry::TimeEntry;
+
+/// Version of the board export format written by this server
+pub const BOARD_EXPORT_VERSION: i32 = 1;
+
+/// A board with everything on it, for backups and moving it between
+/// environments. IDs are those of the exporting environment; lists, cards,
+/// comments and rules refer to each other by them.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BoardExport {
+    /// Format version, checked on import
+    pub version: i32,
+    #[serde(rename = "exportedAt")]
+    pub exported_at: DateTime<Utc>,
+    pub board: Board,
+    pub lanes: Vec<Swimlane>,
+    pub columns: Vec<BoardColumn>,
+    pub cards: Vec<Card>,
+    #[serde(default)]
+    pub comments: Vec<CardComment>,
+    #[serde(default)]
+    #[serde(rename = "timeEntries")]
+    pub time_entries: Vec<TimeEntry>,
+    /// Automation rules; their list filters and targets are exported list IDs
+    #[serde(default)]
+    pub rules: Vec<AutomationRule>,
+}
+
+/// Request body for importing an exported board
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ImportBoardRequest {
+    /// User importing the board; owns it when the exported owner is unknown
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    /// Document from `GET /api/boards/{id}/export`
+    #[schema(value_type = BoardExport)]
+    pub document: serde_json::Value,
+    /// Title of the new board; defaults to the exported title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Card key prefix; defaults to the exported prefix, or one derived
+    /// from the title when that is in use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "keyPrefix")]
+    pub key_prefix: Option<String>,
+    /// Exported user IDs to replace with users of this environment
+    #[serde(default)]
+    #[serde(rename = "userMap")]
+    pub user_map: HashMap<String, String>,
+}
+
+/// New IDs of imported entities, by exported ID
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ImportedIds {
+    pub lanes: BTreeMap<i32, i32>,
+    pub columns: BTreeMap<i32, i32>,
+    pub cards: BTreeMap<i32, i32>,
+    pub comments: BTreeMap<i32, i32>,
+    pub rules: BTreeMap<i32, i32>,
+}
+
+/// Outcome of importing a board
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BoardImportResult {
+    pub board: Board,
+    pub ids: ImportedIds,
+    /// Exported user IDs with no matching user here, and not in `userMap`
+    #[serde(rename = "unknownUsers")]
+    pub unknown_users: Vec<String>,
+    /// What could not be imported as exported
+    pub warnings: Vec<String>,
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Push event from GitHub, GitLab or Gitea; fields not listed are ignored
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GitPushPayload {
+    /// Pushed ref, e.g. `refs/heads/main`
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    #[serde(default)]
+    pub commits: Vec<GitCommit>,
+    /// GitHub and Gitea
+    pub repository: Option<GitRepository>,
+    /// GitLab
+    pub project: Option<GitLabProject>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GitCommit {
+    /// Commit SHA
+    pub id: String,
+    pub message: String,
+    pub url: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub author: Option<GitAuthor>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GitAuthor {
+    pub name: Option<String>,
+    /// GitHub and Gitea only
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GitRepository {
+    pub full_name: Option<String>,
+    pub name: Option<String>,
+    pub default_branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GitLabProject {
+    pub path_with_namespace: Option<String>,
+    pub default_branch: Option<String>,
+}
+
+/// Commit that references a card (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CardCommit {
+    #[serde(rename = "cardId")]
+    pub card_id: i32,
+    pub sha: String,
+    /// Repository path, e.g. `acme/api`
+    pub repository: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "authorName")]
+    pub author_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "authorUsername")]
+    pub author_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "committedAt")]
+    pub committed_at: Option<DateTime<Utc>>,
+    /// The message closes the card, e.g. "fixes OPS-42"
+    pub closes: bool,
+    /// When the commit closed the card, once pushed to the default branch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "closedAt")]
+    pub closed_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a push did to one referenced card
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GitCardLink {
+    #[serde(rename = "cardId")]
+    pub card_id: i32,
+    #[serde(rename = "cardKey")]
+    pub card_key: String,
+    pub sha: String,
+    pub closes: bool,
+    /// False when the commit was already linked by an earlier push
+    #[serde(rename = "newlyLinked")]
+    pub newly_linked: bool,
+    /// The card was moved to its board's done list
+    pub moved: bool,
+    /// Why a closing commit did not move the card this time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "moveSkipped")]
+    pub move_skipped: Option<String>,
+}
+
+/// Result of processing a push
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GitPushResult {
+    pub links: Vec<GitCardLink>,
+    /// Card keys in commit messages that match no card
+    #[serde(rename = "unknownRefs")]
+    pub unknown_refs: Vec<String>,
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use super::card::Card;
+
+/// Where a card sits: its list, lane and index within that cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CardPlacement {
+    #[serde(rename = "listId")]
+    pub list_id: i32,
+    #[serde(rename = "laneId")]
+    pub lane_id: i32,
+    pub position: i32,
+}
+
+/// A change to a board that can be undone and redone
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UndoableOperation {
+    /// A card moved within the board, or was reordered within its cell
+    CardMoved {
+        #[serde(rename = "cardId")]
+        card_id: i32,
+        from: CardPlacement,
+        to: CardPlacement,
+    },
+    CardRenamed {
+        #[serde(rename = "cardId")]
+        card_id: i32,
+        from: String,
+        to: String,
+    },
+    /// Undoing restores the card with the rows that hung off it
+    CardDeleted {
+        card: Box<Card>,
+        dependents: CardDependents,
+    },
+    ColumnRenamed {
+        #[serde(rename = "columnId")]
+        column_id: i32,
+        from: String,
+        to: String,
+    },
+    /// The board's columns were reordered; IDs in order before and after
+    ColumnsReordered { from: Vec<i32>, to: Vec<i32> },
+}
+
+/// Rows deleted along with a card, as JSON objects of their columns, so
+/// undoing the deletion can put them back. Mentions are those in comments;
+/// the description's are found again from its text.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CardDependents {
+    pub comments: Vec<serde_json::Value>,
+    pub mentions: Vec<serde_json::Value>,
+    #[serde(rename = "timeEntries")]
+    pub time_entries: Vec<serde_json::Value>,
+    pub watchers: Vec<serde_json::Value>,
+    pub commits: Vec<serde_json::Value>,
+    pub revisions: Vec<serde_json::Value>,
+    #[serde(rename = "descriptionDoc")]
+    pub description_doc: Vec<serde_json::Value>,
+    #[serde(rename = "keyAliases")]
+    pub key_aliases: Vec<serde_json::Value>,
+}
+
+/// Whether an operation is currently applied (stored as the
+/// `board_operation_state` Postgres enum)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "board_operation_state", rename_all = "lowercase")]
+pub enum OperationState {
+    Done,
+    Undone,
+}
+
+/// Entry of a user's undo/redo stack on a board (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BoardOperation {
+    pub id: i32,
+    #[serde(rename = "boardId")]
+    pub board_id: i32,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[schema(value_type = UndoableOperation)]
+    pub operation: Json<UndoableOperation>,
+    pub state: OperationState,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for undoing or redoing a user's last operation on a board
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UndoRequest {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Scheduled background job and its run state (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ScheduledJob {
+    pub name: String,
+    /// Cron expression with a leading seconds field, evaluated in UTC
+    pub schedule: String,
+    pub enabled: bool,
+    #[serde(rename = "nextRunAt")]
+    pub next_run_at: DateTime<Utc>,
+    /// Failed attempts since the last success
+    pub attempts: i32,
+    /// Instance currently running the job
+    #[serde(rename = "lockedBy")]
+    pub locked_by: Option<String>,
+    #[serde(rename = "lockedUntil")]
+    pub locked_until: Option<DateTime<Utc>>,
+    #[serde(rename = "lastStartedAt")]
+    pub last_started_at: Option<DateTime<Utc>>,
+    #[serde(rename = "lastFinishedAt")]
+    pub last_finished_at: Option<DateTime<Utc>>,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+}
+
+/// Request body for updating a scheduled job
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateScheduledJobRequest {
+    pub enabled: Option<bool>,
+}
@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// An @mention of a board member, resolved to the user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Mention {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    /// The user's display name, or their ID when they have none
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+    /// Character offset of the `@` in the text
+    pub start: i32,
+    /// Character offset just past the mention
+    pub end: i32,
+}
+
+/// How a user is shown to others (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserProfile {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    /// Defaults to the user ID
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Request body for changing a user's display name
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateUserProfileRequest {
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+}
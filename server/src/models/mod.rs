@@ -1,10 +1,81 @@
+pub mod automation;
 pub mod board;
 pub mod card;
 pub mod column;
+pub mod comment;
+pub mod copy;
+pub mod email;
+pub mod export;
+pub mod git;
+pub mod history;
+pub mod job;
+pub mod mention;
+pub mod notification;
+pub mod presence;
+pub mod recurrence;
+pub mod revision;
+pub mod rollup;
+pub mod swimlane;
+pub mod sync;
+pub mod template;
+pub mod time_entry;
+pub mod webhook;
 
+pub use automation::{
+    AutomationDryRunRequest, AutomationDryRunResult, AutomationRule, AutomationRun,
+    CreateAutomationRuleRequest, RuleAction, RuleCondition, RuleTrigger, UpdateAutomationRuleRequest,
+};
 pub use board::{Board, CreateBoardRequest, UpdateBoardRequest};
-pub use card::{Card, CreateCardRequest, UpdateCardRequest};
-pub use column::{BoardColumn, CreateColumnRequest, UpdateColumnRequest};
+pub use card::{Card, CardPriority, CreateCardRequest, EstimateUnit, UpdateCardRequest};
+pub use column::{
+    BoardColumn, CardKeyChange, ColumnCategory, ColumnMoveReport, CreateColumnRequest, LaneRemap,
+    MoveColumnRequest, UpdateColumnRequest, WipMode,
+};
+pub use comment::{CardComment, CreateCommentRequest, UpdateCommentRequest, WatchCardRequest};
+pub use copy::{CopyBoardRequest, CopyCardRequest, CopyColumnRequest};
+pub use email::{
+    EmailMode, EmailSettings, EmailStatus, OutboxEmail, OutboxQuery, UnsubscribeQuery,
+    UpdateEmailSettingsRequest,
+};
+pub use export::{BoardExport, BoardImportResult, ImportBoardRequest, ImportedIds, BOARD_EXPORT_VERSION};
+pub use git::{
+    CardCommit, GitAuthor, GitCardLink, GitCommit, GitLabProject, GitPushPayload, GitPushResult,
+    GitRepository,
+};
+pub use history::{BoardOperation, CardDependents, CardPlacement, OperationState, UndoRequest, UndoableOperation};
+pub use job::{ScheduledJob, UpdateScheduledJobRequest};
+pub use mention::{Mention, UpdateUserProfileRequest, UserProfile};
+pub use notification::{
+    MarkAllReadRequest, Notification, NotificationKind, NotificationPreference, NotificationQuery,
+    UpdateNotificationPreferencesRequest,
+};
+pub use presence::{BoardViewer, PresenceClientMessage, PresenceQuery, PresenceServerMessage};
+pub use recurrence::{
+    CardRecurrence, CreateRecurrenceRequest, RecurrenceRule, RecurrenceStatus, RecurrenceWeekday,
+    UpdateRecurrenceRequest,
+};
+pub use revision::{CardRevision, CardRevisionDiff, RestoreRevisionRequest, RevisionDiffQuery};
+pub use rollup::{BoardRollup, ColumnRollup, EstimateRollup, PriorityCounts};
+pub use swimlane::{
+    BoardDetails, ColumnDetails, CreateSwimlaneRequest, LaneCards, MoveCardRequest, Swimlane,
+    UpdateSwimlaneRequest,
+};
+pub use sync::{
+    SyncChanges, SyncEntity, SyncOperation, SyncOperationResult, SyncOperationStatus, SyncQuery, SyncRequest,
+    SyncResponse,
+};
+pub use template::{
+    BoardTemplate, BoardTemplateDefinition, SaveBoardAsTemplateRequest, TemplateCard,
+    TemplateColumn,
+};
+pub use time_entry::{
+    CreateTimeEntryRequest, ReportFormat, StartTimerRequest, StopTimerRequest, TimeEntry,
+    TimeReportGroupBy, TimeReportQuery, TimeReportRow,
+};
+pub use webhook::{
+    CreateWebhookRequest, UpdateWebhookRequest, Webhook, WebhookDelivery, WebhookDeliveryQuery,
+    WebhookDeliveryStatus, WebhookEvent,
+};
 
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -19,6 +90,8 @@ pub struct ApiResponse<T> {
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
 }
 
 impl<T> ApiResponse<T> {
@@ -28,6 +101,7 @@ impl<T> ApiResponse<T> {
             data: Some(data),
             error: None,
             message: None,
+            warning: None,
         }
     }
 
@@ -37,6 +111,7 @@ impl<T> ApiResponse<T> {
             data: Some(data),
             error: None,
             message: Some(message),
+            warning: None,
         }
     }
 
@@ -46,6 +121,13 @@ impl<T> ApiResponse<T> {
             data: None,
             error: None,
             message: Some(message),
+            warning: None,
         }
     }
+
+    /// Attach a non-fatal warning, e.g. an exceeded advisory WIP limit
+    pub fn with_warning(mut self, warning: Option<String>) -> Self {
+        self.warning = warning;
+        self
+    }
 }
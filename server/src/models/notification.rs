@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// What a notification is about (stored as the `notification_kind` Postgres enum)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "notification_kind", rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The user was made the assignee of a card
+    Assigned,
+    /// The user was mentioned
+    Mentioned,
+    /// Someone commented on a card the user watches
+    Commented,
+    /// A card assigned to or watched by the user is due within a day
+    DueSoon,
+    /// A card assigned to or watched by the user is past its due date
+    Overdue,
+}
+
+impl NotificationKind {
+    pub const ALL: &'static [NotificationKind] = &[
+        NotificationKind::Assigned,
+        NotificationKind::Mentioned,
+        NotificationKind::Commented,
+        NotificationKind::DueSoon,
+        NotificationKind::Overdue,
+    ];
+}
+
+/// Notification model (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Notification {
+    pub id: i32,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub kind: NotificationKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "cardId")]
+    pub card_id: Option<i32>,
+    /// User whose action caused the notification
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "actorId")]
+    pub actor_id: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "readAt")]
+    pub read_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters for the inbox
+#[derive(Debug, Deserialize)]
+pub struct NotificationQuery {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    /// Only unread notifications
+    #[serde(default, rename = "unreadOnly")]
+    pub unread_only: bool,
+    pub limit: Option<i64>,
+}
+
+/// Whether a user receives one kind of notification
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct NotificationPreference {
+    pub kind: NotificationKind,
+    pub enabled: bool,
+}
+
+/// Request body for changing a user's notification preferences; kinds not
+/// listed keep their setting
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateNotificationPreferencesRequest {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub preferences: Vec<NotificationPreference>,
+}
+
+/// Request body for marking all of a user's notifications read
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MarkAllReadRequest {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+}
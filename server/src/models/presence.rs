@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A user currently viewing a board
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct BoardViewer {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    /// The user's display name, or their ID when they have none
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+    /// Card the user has open, from their most recently focused connection
+    #[serde(rename = "cardId")]
+    pub card_id: Option<i32>,
+    #[serde(rename = "cardKey")]
+    pub card_key: Option<String>,
+    /// When the user started viewing the board
+    pub since: DateTime<Utc>,
+    #[serde(rename = "lastSeenAt")]
+    pub last_seen_at: DateTime<Utc>,
+}
+
+/// Query parameters for joining a board's presence channel
+#[derive(Debug, Deserialize)]
+pub struct PresenceQuery {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+}
+
+/// Message from a client on a board's presence channel
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PresenceClientMessage {
+    /// Keeps the connection's presence from expiring
+    Heartbeat,
+    /// The card the user has open, or null when none
+    Focus {
+        #[serde(rename = "cardId")]
+        card_id: Option<i32>,
+    },
+    Leave,
+}
+
+/// Message to clients on a board's presence channel
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PresenceServerMessage {
+    /// Everyone viewing the board; sent on joining and whenever it changes
+    Presence {
+        #[serde(rename = "boardId")]
+        board_id: i32,
+        viewers: Vec<BoardViewer>,
+    },
+    /// A message from the client was rejected; the connection stays open
+    Error { message: String },
+}
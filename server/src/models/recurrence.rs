@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc, Weekday};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Day of the week
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RecurrenceWeekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl From<Weekday> for RecurrenceWeekday {
+    fn from(weekday: Weekday) -> Self {
+        match weekday {
+            Weekday::Mon => RecurrenceWeekday::Mon,
+            Weekday::Tue => RecurrenceWeekday::Tue,
+            Weekday::Wed => RecurrenceWeekday::Wed,
+            Weekday::Thu => RecurrenceWeekday::Thu,
+            Weekday::Fri => RecurrenceWeekday::Fri,
+            Weekday::Sat => RecurrenceWeekday::Sat,
+            Weekday::Sun => RecurrenceWeekday::Sun,
+        }
+    }
+}
+
+/// When a series is due again (a subset of iCalendar RRULE). Due dates fall at
+/// the time of day of the series' `startsAt`, in UTC.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "freq", rename_all = "snake_case")]
+pub enum RecurrenceRule {
+    /// Every `interval` days, counted from `startsAt`
+    Daily { interval: u32 },
+    /// On each of the given days of the week
+    Weekly { weekdays: Vec<RecurrenceWeekday> },
+    /// On day `day` of every month; shorter months use their last day
+    Monthly { day: u32 },
+    /// `days` days after the previous card of the series was completed
+    AfterCompletion { days: u32 },
+}
+
+/// State of a recurring series (stored as the `recurrence_status` Postgres enum)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "recurrence_status", rename_all = "lowercase")]
+pub enum RecurrenceStatus {
+    Active,
+    Paused,
+    /// Ended explicitly or after `endsAt`; no more cards are created
+    Ended,
+}
+
+/// Recurring series of cards copied from a template card (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CardRecurrence {
+    pub id: i32,
+    /// Card whose title, description, labels, assignee, priority and estimate
+    /// each new card copies
+    #[serde(rename = "templateCardId")]
+    pub template_card_id: i32,
+    /// List new cards are created in
+    #[serde(rename = "listId")]
+    pub list_id: i32,
+    #[schema(value_type = RecurrenceRule)]
+    pub rule: Json<RecurrenceRule>,
+    pub status: RecurrenceStatus,
+    /// No card is due before this; also sets the time of day of due dates
+    #[serde(rename = "startsAt")]
+    pub starts_at: DateTime<Utc>,
+    /// The series ends before the first due date after this
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "endsAt")]
+    pub ends_at: Option<DateTime<Utc>>,
+    /// Cards are created this many days before their due date
+    #[serde(rename = "leadDays")]
+    pub lead_days: i32,
+    /// Due date of the next card; empty while an after-completion series
+    /// waits for its last card to be completed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "nextDueAt")]
+    pub next_due_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "lastCardId")]
+    pub last_card_id: Option<i32>,
+    /// Number of cards created so far
+    pub occurrences: i32,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for making a card the template of a recurring series
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateRecurrenceRequest {
+    #[serde(rename = "listId")]
+    pub list_id: i32,
+    pub rule: RecurrenceRule,
+    /// Defaults to now
+    #[serde(rename = "startsAt")]
+    pub starts_at: Option<DateTime<Utc>>,
+    #[serde(rename = "endsAt")]
+    pub ends_at: Option<DateTime<Utc>>,
+    /// Defaults to 1
+    #[serde(rename = "leadDays")]
+    pub lead_days: Option<i32>,
+}
+
+/// Request body for editing a recurring series
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateRecurrenceRequest {
+    #[serde(rename = "listId")]
+    pub list_id: Option<i32>,
+    pub rule: Option<RecurrenceRule>,
+    #[serde(rename = "startsAt")]
+    pub starts_at: Option<DateTime<Utc>>,
+    /// `null` removes the end date; omitted keeps it
+    #[serde(default, deserialize_with = "present")]
+    #[serde(rename = "endsAt")]
+    #[schema(value_type = Option<DateTime<Utc>>)]
+    pub ends_at: Option<Option<DateTime<Utc>>>,
+    #[serde(rename = "leadDays")]
+    pub lead_days: Option<i32>,
+}
+
+/// Read a field that is present as `Some`, so that an explicit `null`
+/// becomes `Some(None)` and only an omitted field is `None`
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// A card's title and description as of one edit (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CardRevision {
+    pub id: i32,
+    #[serde(rename = "cardId")]
+    pub card_id: i32,
+    /// Per-card revision number, starting at 1
+    pub revision: i32,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "authorId")]
+    pub author_id: Option<String>,
+    /// Revision whose text this one restored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "restoredFrom")]
+    pub restored_from: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters for comparing two revisions
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffQuery {
+    pub from: i32,
+    /// Defaults to the latest revision
+    pub to: Option<i32>,
+}
+
+/// Unified diffs between two revisions of a card
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CardRevisionDiff {
+    pub from: i32,
+    pub to: i32,
+    /// Empty when the title did not change
+    #[serde(rename = "titleDiff")]
+    pub title_diff: String,
+    /// Empty when the description did not change
+    #[serde(rename = "descriptionDiff")]
+    pub description_diff: String,
+}
+
+/// Request body for restoring a revision
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RestoreRevisionRequest {
+    /// User restoring it, recorded as the author of the new revision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "editedBy")]
+    pub edited_by: Option<String>,
+}
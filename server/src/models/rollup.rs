@@ -0,0 +1,53 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Number of cards per priority
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct PriorityCounts {
+    pub low: i64,
+    pub medium: i64,
+    pub high: i64,
+    pub urgent: i64,
+    pub none: i64,
+}
+
+/// Estimate totals for a set of cards
+///
+/// Points and hours are never summed together, so each unit has its own totals.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct EstimateRollup {
+    #[serde(rename = "cardCount")]
+    pub card_count: i64,
+    #[serde(rename = "estimatedCardCount")]
+    pub estimated_card_count: i64,
+    #[serde(rename = "totalPoints")]
+    pub total_points: f64,
+    #[serde(rename = "totalHours")]
+    pub total_hours: f64,
+    #[serde(rename = "remainingPoints")]
+    pub remaining_points: f64,
+    #[serde(rename = "remainingHours")]
+    pub remaining_hours: f64,
+    #[serde(rename = "countByPriority")]
+    pub count_by_priority: PriorityCounts,
+}
+
+/// Estimate rollup for a single column/list
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ColumnRollup {
+    #[serde(rename = "listId")]
+    pub list_id: i32,
+    pub title: String,
+    #[serde(flatten)]
+    pub rollup: EstimateRollup,
+}
+
+/// Estimate rollup for a whole board, with a breakdown per column
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BoardRollup {
+    #[serde(rename = "boardId")]
+    pub board_id: i32,
+    #[serde(flatten)]
+    pub rollup: EstimateRollup,
+    pub columns: Vec<ColumnRollup>,
+}
@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use super::board::Board;
+use super::card::Card;
+use super::column::BoardColumn;
+
+/// Swimlane model (matches database schema)
+///
+/// Swimlanes are ordered rows spanning all columns of a board. Every board has
+/// exactly one default lane, which holds cards not assigned to another lane.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Swimlane {
+    pub id: i32,
+    #[serde(rename = "boardId")]
+    pub board_id: i32,
+    pub title: String,
+    pub position: i32,
+    #[serde(rename = "isDefault")]
+    pub is_default: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for creating a swimlane
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSwimlaneRequest {
+    pub title: String,
+}
+
+/// Request body for updating a swimlane
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSwimlaneRequest {
+    pub title: Option<String>,
+    /// New index among the board's lanes; other lanes shift to make room
+    pub position: Option<i32>,
+}
+
+/// Request body for moving a card to a position within a (list, lane) cell
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MoveCardRequest {
+    /// Target list; defaults to the card's current list
+    #[serde(rename = "listId")]
+    pub list_id: Option<i32>,
+    /// Target lane; defaults to the card's current lane on the same board
+    /// and to the default lane on another board
+    #[serde(rename = "laneId")]
+    pub lane_id: Option<i32>,
+    /// Index within the target cell, clamped to its end
+    pub position: i32,
+    /// User moving the card; recorded on their undo stack for the board
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+}
+
+/// Cards of one lane within a column
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LaneCards {
+    #[serde(rename = "laneId")]
+    pub lane_id: i32,
+    pub cards: Vec<Card>,
+}
+
+/// A column with its cards grouped by lane
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ColumnDetails {
+    #[serde(flatten)]
+    pub column: BoardColumn,
+    pub lanes: Vec<LaneCards>,
+}
+
+/// A board with its lanes, columns and cards
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BoardDetails {
+    #[serde(flatten)]
+    pub board: Board,
+    pub lanes: Vec<Swimlane>,
+    pub columns: Vec<ColumnDetails>,
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::board::{Board, UpdateBoardRequest};
+use super::card::{Card, CreateCardRequest, UpdateCardRequest};
+use super::column::{BoardColumn, CreateColumnRequest, UpdateColumnRequest};
+
+/// Query parameters for pulling changes
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    /// Token from the previous sync; omitted for a full sync
+    pub since: Option<String>,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+}
+
+/// Kind of a tombstoned entity (stored as the `sync_entity` Postgres enum)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "sync_entity", rename_all = "lowercase")]
+pub enum SyncEntity {
+    Board,
+    Column,
+    Card,
+}
+
+/// Changes to one kind of entity since a sync token
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SyncChanges<T> {
+    pub created: Vec<T>,
+    pub updated: Vec<T>,
+    /// IDs deleted, or moved to a board the user cannot see
+    pub deleted: Vec<i32>,
+}
+
+impl<T> Default for SyncChanges<T> {
+    fn default() -> Self {
+        Self { created: Vec::new(), updated: Vec::new(), deleted: Vec::new() }
+    }
+}
+
+/// Boards, lists and cards visible to a user that changed since a token
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SyncResponse {
+    /// Pass as `since` on the next sync
+    pub token: String,
+    pub boards: SyncChanges<Board>,
+    pub columns: SyncChanges<BoardColumn>,
+    pub cards: SyncChanges<Card>,
+}
+
+/// A change made by an offline client. Operations with a `baseVersion`
+/// conflict when the entity's version has moved on since.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncOperation {
+    CreateCard {
+        /// Client-side ID, echoed in the result
+        #[serde(rename = "clientId")]
+        client_id: Option<String>,
+        card: CreateCardRequest,
+    },
+    UpdateCard {
+        #[serde(rename = "cardId")]
+        card_id: i32,
+        #[serde(rename = "baseVersion")]
+        base_version: Option<i64>,
+        changes: UpdateCardRequest,
+    },
+    MoveCard {
+        #[serde(rename = "cardId")]
+        card_id: i32,
+        #[serde(rename = "baseVersion")]
+        base_version: Option<i64>,
+        #[serde(rename = "listId")]
+        list_id: Option<i32>,
+        #[serde(rename = "laneId")]
+        lane_id: Option<i32>,
+        position: i32,
+    },
+    DeleteCard {
+        #[serde(rename = "cardId")]
+        card_id: i32,
+        #[serde(rename = "baseVersion")]
+        base_version: Option<i64>,
+    },
+    CreateColumn {
+        /// Client-side ID, echoed in the result
+        #[serde(rename = "clientId")]
+        client_id: Option<String>,
+        column: CreateColumnRequest,
+    },
+    UpdateColumn {
+        #[serde(rename = "columnId")]
+        column_id: i32,
+        #[serde(rename = "baseVersion")]
+        base_version: Option<i64>,
+        changes: UpdateColumnRequest,
+    },
+    DeleteColumn {
+        #[serde(rename = "columnId")]
+        column_id: i32,
+        #[serde(rename = "baseVersion")]
+        base_version: Option<i64>,
+    },
+    UpdateBoard {
+        #[serde(rename = "boardId")]
+        board_id: i32,
+        #[serde(rename = "baseVersion")]
+        base_version: Option<i64>,
+        changes: UpdateBoardRequest,
+    },
+}
+
+/// Request body for pushing a batch of offline changes
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SyncRequest {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub operations: Vec<SyncOperation>,
+}
+
+/// Outcome of one pushed operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncOperationStatus {
+    Applied,
+    /// The entity changed or was deleted since `baseVersion`; the result
+    /// carries its current state, if it still exists
+    Conflict,
+    Failed,
+}
+
+/// Result of one pushed operation, in request order
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SyncOperationResult {
+    /// Index of the operation in the request
+    pub index: usize,
+    pub status: SyncOperationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "clientId")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Non-fatal warning, e.g. an exceeded advisory WIP limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub board: Option<Board>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<BoardColumn>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card: Option<Card>,
+}
+
+impl SyncOperationResult {
+    pub fn new(index: usize, status: SyncOperationStatus) -> Self {
+        Self {
+            index,
+            status,
+            client_id: None,
+            error: None,
+            warning: None,
+            board: None,
+            column: None,
+            card: None,
+        }
+    }
+}
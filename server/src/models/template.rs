@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use super::card::{CardPriority, EstimateUnit};
+use super::column::ColumnCategory;
+
+/// Seed card created when a template is instantiated
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TemplateCard {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<CardPriority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "estimateUnit")]
+    pub estimate_unit: Option<EstimateUnit>,
+}
+
+/// Column created when a template is instantiated
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TemplateColumn {
+    pub title: String,
+    #[serde(default)]
+    pub category: ColumnCategory,
+    #[serde(default)]
+    pub cards: Vec<TemplateCard>,
+}
+
+/// Board layout stored in a template
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct BoardTemplateDefinition {
+    pub columns: Vec<TemplateColumn>,
+}
+
+/// Board template model (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BoardTemplate {
+    pub id: i32,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Built-in templates ship with the server and cannot be deleted
+    #[serde(rename = "builtIn")]
+    pub built_in: bool,
+    #[schema(value_type = BoardTemplateDefinition)]
+    pub definition: Json<BoardTemplateDefinition>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for saving an existing board as a template
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SaveBoardAsTemplateRequest {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Whether the board's cards become seed cards of the template
+    #[serde(default)]
+    #[serde(rename = "includeCards")]
+    pub include_cards: bool,
+}
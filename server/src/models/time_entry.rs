@@ -0,0 +1,108 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Time entry model (matches database schema)
+///
+/// A running timer is a time entry without `ended_at`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TimeEntry {
+    pub id: i32,
+    #[serde(rename = "cardId")]
+    pub card_id: i32,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "endedAt")]
+    pub ended_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "durationSeconds")]
+    pub duration_seconds: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for logging a finished time entry.
+/// Either `endedAt` or `durationSeconds` must be provided.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTimeEntryRequest {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "endedAt")]
+    pub ended_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "durationSeconds")]
+    pub duration_seconds: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Request body for starting a timer on a card
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StartTimerRequest {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Request body for stopping a user's running timer
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StopTimerRequest {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+}
+
+/// Dimension a time report is grouped by
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeReportGroupBy {
+    #[default]
+    User,
+    Board,
+    Label,
+    Day,
+}
+
+/// Output format of a time report
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Query parameters for the time report
+#[derive(Debug, Deserialize)]
+pub struct TimeReportQuery {
+    #[serde(default, rename = "groupBy")]
+    pub group_by: TimeReportGroupBy,
+    #[serde(default)]
+    pub format: ReportFormat,
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+    #[serde(rename = "boardId")]
+    pub board_id: Option<i32>,
+    pub label: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// One aggregated row of a time report
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct TimeReportRow {
+    pub key: String,
+    #[serde(rename = "totalSeconds")]
+    pub total_seconds: i64,
+    #[serde(rename = "entryCount")]
+    pub entry_count: i64,
+}
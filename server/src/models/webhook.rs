@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Change reported to webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum WebhookEvent {
+    /// A card was created, copied or created by a recurring series
+    #[serde(rename = "card.created")]
+    CardCreated,
+    /// A card was edited
+    #[serde(rename = "card.updated")]
+    CardUpdated,
+    /// A card was moved to another list or position
+    #[serde(rename = "card.moved")]
+    CardMoved,
+    #[serde(rename = "card.deleted")]
+    CardDeleted,
+    #[serde(rename = "column.created")]
+    ColumnCreated,
+    #[serde(rename = "column.updated")]
+    ColumnUpdated,
+    #[serde(rename = "column.deleted")]
+    ColumnDeleted,
+    #[serde(rename = "board.updated")]
+    BoardUpdated,
+}
+
+impl WebhookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            WebhookEvent::CardCreated => "card.created",
+            WebhookEvent::CardUpdated => "card.updated",
+            WebhookEvent::CardMoved => "card.moved",
+            WebhookEvent::CardDeleted => "card.deleted",
+            WebhookEvent::ColumnCreated => "column.created",
+            WebhookEvent::ColumnUpdated => "column.updated",
+            WebhookEvent::ColumnDeleted => "column.deleted",
+            WebhookEvent::BoardUpdated => "board.updated",
+        }
+    }
+}
+
+/// Webhook subscription of a board (matches database schema, without the
+/// secret, which is never returned)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Webhook {
+    pub id: i32,
+    #[serde(rename = "boardId")]
+    pub board_id: i32,
+    pub url: String,
+    /// Events delivered; empty means all
+    #[schema(value_type = Vec<WebhookEvent>)]
+    pub events: Json<Vec<WebhookEvent>>,
+    /// Inactive webhooks receive nothing; set automatically after repeated failures
+    pub active: bool,
+    #[serde(rename = "consecutiveFailures")]
+    pub consecutive_failures: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "disabledReason")]
+    pub disabled_reason: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request body for subscribing to a board's events
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    /// http(s) URL payloads are POSTed to
+    pub url: String,
+    /// Key of the HMAC-SHA256 signature in the `X-Webhook-Signature` header
+    pub secret: String,
+    /// Defaults to all events
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+}
+
+/// Request body for updating a webhook; setting `active` re-enables a
+/// disabled webhook and resets its failure count
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<WebhookEvent>>,
+    pub active: Option<bool>,
+}
+
+/// Delivery state of a webhook payload (stored as the
+/// `webhook_delivery_status` Postgres enum)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    /// Waiting for its first or next attempt
+    Pending,
+    /// The endpoint answered with a 2xx status
+    Delivered,
+    /// Gave up after too many failed attempts
+    Failed,
+}
+
+/// One payload sent, or to be sent, to a webhook (matches database schema)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    #[serde(rename = "webhookId")]
+    pub webhook_id: i32,
+    pub event: String,
+    #[schema(value_type = Object)]
+    pub payload: Json<serde_json::Value>,
+    pub status: WebhookDeliveryStatus,
+    /// Attempts made so far
+    pub attempts: i32,
+    #[serde(rename = "nextAttemptAt")]
+    pub next_attempt_at: DateTime<Utc>,
+    /// HTTP status of the last attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "responseStatus")]
+    pub response_status: Option<i32>,
+    /// Start of the response body of the last attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "responseBody")]
+    pub response_body: Option<String>,
+    /// Why the last attempt failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "durationMs")]
+    pub duration_ms: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "deliveredAt")]
+    pub delivered_at: Option<DateTime<Utc>>,
+    /// The delivery this one repeats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "redeliveryOf")]
+    pub redelivery_of: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query parameters for a webhook's delivery history
+#[derive(Debug, Deserialize)]
+pub struct WebhookDeliveryQuery {
+    pub status: Option<WebhookDeliveryStatus>,
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,87 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{ApiResponse, AutomationRule, AutomationRun, UpdateAutomationRuleRequest};
+
+pub fn router() -> Router<DbPool> {
+    Router::new()
+        .route("/:id", put(update_automation).delete(delete_automation))
+        .route("/:id/runs", get(get_automation_runs))
+}
+
+/// Update an automation rule, e.g. to enable or disable it
+#[utoipa::path(
+    put,
+    path = "/api/automations/{id}",
+    tag = "Automations",
+    params(
+        ("id" = i32, Path, description = "Rule ID")
+    ),
+    request_body = UpdateAutomationRuleRequest,
+    responses(
+        (status = 200, description = "Rule updated", body = ApiResponse<AutomationRule>),
+        (status = 404, description = "Rule not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn update_automation(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<UpdateAutomationRuleRequest>,
+) -> Result<Json<ApiResponse<AutomationRule>>, AppError> {
+    let rule = handlers::automation::update_rule(&pool, id, req).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        rule,
+        "Automation rule updated successfully".to_string(),
+    )))
+}
+
+/// Delete an automation rule
+#[utoipa::path(
+    delete,
+    path = "/api/automations/{id}",
+    tag = "Automations",
+    params(
+        ("id" = i32, Path, description = "Rule ID")
+    ),
+    responses(
+        (status = 200, description = "Rule deleted"),
+        (status = 404, description = "Rule not found")
+    )
+)]
+pub async fn delete_automation(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    handlers::automation::delete_rule(&pool, id).await?;
+    Ok(Json(ApiResponse::message_only(
+        "Automation rule deleted successfully".to_string(),
+    )))
+}
+
+/// Get the most recent executions of a rule, newest first
+#[utoipa::path(
+    get,
+    path = "/api/automations/{id}/runs",
+    tag = "Automations",
+    params(
+        ("id" = i32, Path, description = "Rule ID")
+    ),
+    responses(
+        (status = 200, description = "Execution log", body = ApiResponse<Vec<AutomationRun>>),
+        (status = 404, description = "Rule not found")
+    )
+)]
+pub async fn get_automation_runs(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Vec<AutomationRun>>>, AppError> {
+    let runs = handlers::automation::get_rule_runs(&pool, id).await?;
+    Ok(Json(ApiResponse::success(runs)))
+}
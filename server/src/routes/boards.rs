@@ -1,19 +1,48 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    routing::get,
+    extract::{ws::WebSocketUpgrade, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
     Json, Router,
 };
+use serde::Deserialize;
 
 use crate::db::DbPool;
 use crate::errors::AppError;
 use crate::handlers;
-use crate::models::{ApiResponse, Board, CreateBoardRequest, UpdateBoardRequest};
+use crate::models::{
+    ApiResponse, AutomationDryRunRequest, AutomationDryRunResult, AutomationRule, Board,
+    BoardDetails, BoardExport, BoardImportResult, BoardOperation, BoardRollup, BoardTemplate,
+    BoardViewer, CopyBoardRequest, CreateAutomationRuleRequest, CreateBoardRequest,
+    CreateSwimlaneRequest, CreateWebhookRequest, ImportBoardRequest, PresenceQuery, SaveBoardAsTemplateRequest, Swimlane, UndoRequest, UpdateBoardRequest, Webhook,
+};
+use crate::routes::cards::HtmlQuery;
 
 pub fn router() -> Router<DbPool> {
     Router::new()
         .route("/", get(get_boards).post(create_board))
         .route("/:id", get(get_board).put(update_board).delete(delete_board))
+        .route("/:id/rollup", get(get_board_rollup))
+        .route("/import", post(import_board))
+        .route("/:id/copy", post(copy_board))
+        .route("/:id/export", get(export_board))
+        .route("/:id/template", post(save_board_as_template))
+        .route("/:id/lanes", get(get_board_lanes).post(create_lane))
+        .route("/:id/details", get(get_board_details))
+        .route("/:id/automations", get(get_board_automations).post(create_automation))
+        .route("/:id/automations/dry-run", post(dry_run_automations))
+        .route("/:id/webhooks", get(get_board_webhooks).post(create_webhook))
+        .route("/:id/history", get(get_history))
+        .route("/:id/undo", post(undo))
+        .route("/:id/redo", post(redo))
+        .route("/:id/presence", get(get_presence))
+        .route("/:id/presence/ws", get(watch_presence))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct HistoryQuery {
+    #[serde(rename = "userId")]
+    user_id: String,
 }
 
 /// Get all boards
@@ -21,14 +50,21 @@ pub fn router() -> Router<DbPool> {
     get,
     path = "/api/boards",
     tag = "Boards",
+    params(
+        ("html" = Option<bool>, Query, description = "Include descriptionHtml, the description rendered from Markdown")
+    ),
     responses(
         (status = 200, description = "List of boards", body = ApiResponse<Vec<Board>>)
     )
 )]
 pub async fn get_boards(
     State(pool): State<DbPool>,
+    Query(params): Query<HtmlQuery>,
 ) -> Result<Json<ApiResponse<Vec<Board>>>, AppError> {
-    let boards = handlers::boards::get_all_boards(&pool).await?;
+    let mut boards = handlers::boards::get_all_boards(&pool).await?;
+    if params.html {
+        handlers::markdown::attach_board_html(&pool, &mut boards).await?;
+    }
     Ok(Json(ApiResponse::success(boards)))
 }
 
@@ -38,7 +74,8 @@ pub async fn get_boards(
     path = "/api/boards/{id}",
     tag = "Boards",
     params(
-        ("id" = i32, Path, description = "Board ID")
+        ("id" = i32, Path, description = "Board ID"),
+        ("html" = Option<bool>, Query, description = "Include descriptionHtml, the description rendered from Markdown")
     ),
     responses(
         (status = 200, description = "Board found", body = ApiResponse<Board>),
@@ -48,8 +85,12 @@ pub async fn get_boards(
 pub async fn get_board(
     State(pool): State<DbPool>,
     Path(id): Path<i32>,
+    Query(params): Query<HtmlQuery>,
 ) -> Result<Json<ApiResponse<Board>>, AppError> {
-    let board = handlers::boards::get_board_by_id(&pool, id).await?;
+    let mut board = handlers::boards::get_board_by_id(&pool, id).await?;
+    if params.html {
+        handlers::markdown::attach_board_html(&pool, std::slice::from_mut(&mut board)).await?;
+    }
     Ok(Json(ApiResponse::success(board)))
 }
 
@@ -61,7 +102,8 @@ pub async fn get_board(
     request_body = CreateBoardRequest,
     responses(
         (status = 201, description = "Board created", body = ApiResponse<Board>),
-        (status = 400, description = "Invalid input")
+        (status = 400, description = "Invalid input"),
+        (status = 404, description = "Template not found")
     )
 )]
 pub async fn create_board(
@@ -127,3 +169,474 @@ pub async fn delete_board(
         "Board deleted successfully".to_string(),
     )))
 }
+
+/// Get estimate and priority rollups for a board
+#[utoipa::path(
+    get,
+    path = "/api/boards/{id}/rollup",
+    tag = "Boards",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    responses(
+        (status = 200, description = "Board rollup", body = ApiResponse<BoardRollup>),
+        (status = 404, description = "Board not found")
+    )
+)]
+pub async fn get_board_rollup(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<BoardRollup>>, AppError> {
+    let rollup = handlers::rollups::get_board_rollup(&pool, id).await?;
+    Ok(Json(ApiResponse::success(rollup)))
+}
+
+/// Deep-copy a board with its lists, cards and automation rules
+#[utoipa::path(
+    post,
+    path = "/api/boards/{id}/copy",
+    tag = "Boards",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    request_body = CopyBoardRequest,
+    responses(
+        (status = 201, description = "Board copied", body = ApiResponse<Board>),
+        (status = 404, description = "Board not found"),
+        (status = 409, description = "Key prefix already in use, or a list's cards exceed its strict WIP limit")
+    )
+)]
+pub async fn copy_board(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<CopyBoardRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Board>>), AppError> {
+    let board = handlers::copy::copy_board(&pool, id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(
+            board,
+            "Board copied successfully".to_string(),
+        )),
+    ))
+}
+
+/// Export a board with its lanes, lists, cards, comments and time entries
+/// as a versioned JSON document, for backups and moving it between
+/// environments
+#[utoipa::path(
+    get,
+    path = "/api/boards/{id}/export",
+    tag = "Boards",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    responses(
+        (status = 200, description = "Export document", body = BoardExport),
+        (status = 404, description = "Board not found")
+    )
+)]
+pub async fn export_board(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Response, AppError> {
+    let export = handlers::export::export_board(&pool, id).await?;
+    let disposition = format!("attachment; filename=\"board-{}.json\"", export.board.key_prefix);
+    Ok(([(header::CONTENT_DISPOSITION, disposition)], Json(export)).into_response())
+}
+
+/// Import an exported board as a new board with fresh IDs. Exported users
+/// with no match here are reported, along with anything else that could not
+/// be imported as exported.
+#[utoipa::path(
+    post,
+    path = "/api/boards/import",
+    tag = "Boards",
+    request_body = ImportBoardRequest,
+    responses(
+        (status = 201, description = "Board imported", body = ApiResponse<BoardImportResult>),
+        (status = 400, description = "Unsupported version or invalid document"),
+        (status = 409, description = "Key prefix already in use")
+    )
+)]
+pub async fn import_board(
+    State(pool): State<DbPool>,
+    Json(req): Json<ImportBoardRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<BoardImportResult>>), AppError> {
+    let result = handlers::export::import_board(&pool, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(
+            result,
+            "Board imported successfully".to_string(),
+        )),
+    ))
+}
+
+/// Save a board's lists, and optionally its cards, as a template
+#[utoipa::path(
+    post,
+    path = "/api/boards/{id}/template",
+    tag = "Templates",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    request_body = SaveBoardAsTemplateRequest,
+    responses(
+        (status = 201, description = "Template created", body = ApiResponse<BoardTemplate>),
+        (status = 404, description = "Board not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn save_board_as_template(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<SaveBoardAsTemplateRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<BoardTemplate>>), AppError> {
+    let template = handlers::templates::save_board_as_template(&pool, id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(
+            template,
+            "Template created successfully".to_string(),
+        )),
+    ))
+}
+
+/// Get the swimlanes of a board in order
+#[utoipa::path(
+    get,
+    path = "/api/boards/{id}/lanes",
+    tag = "Lanes",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    responses(
+        (status = 200, description = "List of lanes", body = ApiResponse<Vec<Swimlane>>),
+        (status = 404, description = "Board not found")
+    )
+)]
+pub async fn get_board_lanes(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Vec<Swimlane>>>, AppError> {
+    let lanes = handlers::lanes::get_board_lanes(&pool, id).await?;
+    Ok(Json(ApiResponse::success(lanes)))
+}
+
+/// Add a swimlane at the bottom of a board
+#[utoipa::path(
+    post,
+    path = "/api/boards/{id}/lanes",
+    tag = "Lanes",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    request_body = CreateSwimlaneRequest,
+    responses(
+        (status = 201, description = "Lane created", body = ApiResponse<Swimlane>),
+        (status = 404, description = "Board not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn create_lane(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<CreateSwimlaneRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Swimlane>>), AppError> {
+    let lane = handlers::lanes::create_lane(&pool, id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(
+            lane,
+            "Lane created successfully".to_string(),
+        )),
+    ))
+}
+
+/// Get a board with its lanes, and each list's cards grouped by lane
+#[utoipa::path(
+    get,
+    path = "/api/boards/{id}/details",
+    tag = "Boards",
+    params(
+        ("id" = i32, Path, description = "Board ID"),
+        ("html" = Option<bool>, Query, description = "Include descriptionHtml on the board and its cards")
+    ),
+    responses(
+        (status = 200, description = "Board with lanes, lists and cards", body = ApiResponse<BoardDetails>),
+        (status = 404, description = "Board not found")
+    )
+)]
+pub async fn get_board_details(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Query(params): Query<HtmlQuery>,
+) -> Result<Json<ApiResponse<BoardDetails>>, AppError> {
+    let mut details = handlers::lanes::get_board_details(&pool, id).await?;
+    if params.html {
+        handlers::markdown::attach_board_html(&pool, std::slice::from_mut(&mut details.board)).await?;
+        for column in &mut details.columns {
+            for lane in &mut column.lanes {
+                handlers::markdown::attach_card_html(&pool, &mut lane.cards).await?;
+            }
+        }
+    }
+    Ok(Json(ApiResponse::success(details)))
+}
+
+/// Get the automation rules of a board
+#[utoipa::path(
+    get,
+    path = "/api/boards/{id}/automations",
+    tag = "Automations",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    responses(
+        (status = 200, description = "List of rules", body = ApiResponse<Vec<AutomationRule>>),
+        (status = 404, description = "Board not found")
+    )
+)]
+pub async fn get_board_automations(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Vec<AutomationRule>>>, AppError> {
+    let rules = handlers::automation::get_rules_for_board(&pool, id).await?;
+    Ok(Json(ApiResponse::success(rules)))
+}
+
+/// Add an automation rule to a board
+#[utoipa::path(
+    post,
+    path = "/api/boards/{id}/automations",
+    tag = "Automations",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    request_body = CreateAutomationRuleRequest,
+    responses(
+        (status = 201, description = "Rule created", body = ApiResponse<AutomationRule>),
+        (status = 404, description = "Board not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn create_automation(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<CreateAutomationRuleRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<AutomationRule>>), AppError> {
+    let rule = handlers::automation::create_rule(&pool, id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(
+            rule,
+            "Automation rule created successfully".to_string(),
+        )),
+    ))
+}
+
+/// Show what a board's rules would do for an event on a card, without changing anything
+#[utoipa::path(
+    post,
+    path = "/api/boards/{id}/automations/dry-run",
+    tag = "Automations",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    request_body = AutomationDryRunRequest,
+    responses(
+        (status = 200, description = "Simulated rule executions", body = ApiResponse<AutomationDryRunResult>),
+        (status = 404, description = "Card not found"),
+        (status = 400, description = "Card is not on the board")
+    )
+)]
+pub async fn dry_run_automations(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<AutomationDryRunRequest>,
+) -> Result<Json<ApiResponse<AutomationDryRunResult>>, AppError> {
+    let result = handlers::automation::dry_run(&pool, id, req).await?;
+    Ok(Json(ApiResponse::success(result)))
+}
+
+/// Get the webhooks of a board
+#[utoipa::path(
+    get,
+    path = "/api/boards/{id}/webhooks",
+    tag = "Webhooks",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    responses(
+        (status = 200, description = "List of webhooks", body = ApiResponse<Vec<Webhook>>),
+        (status = 404, description = "Board not found")
+    )
+)]
+pub async fn get_board_webhooks(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Vec<Webhook>>>, AppError> {
+    let webhooks = handlers::webhooks::get_webhooks_for_board(&pool, id).await?;
+    Ok(Json(ApiResponse::success(webhooks)))
+}
+
+/// Subscribe a URL to a board's card, column and board events
+#[utoipa::path(
+    post,
+    path = "/api/boards/{id}/webhooks",
+    tag = "Webhooks",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook created", body = ApiResponse<Webhook>),
+        (status = 404, description = "Board not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn create_webhook(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Webhook>>), AppError> {
+    let webhook = handlers::webhooks::create_webhook(&pool, id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(
+            webhook,
+            "Webhook created successfully".to_string(),
+        )),
+    ))
+}
+
+/// Get a user's undo/redo stack on a board, newest first
+#[utoipa::path(
+    get,
+    path = "/api/boards/{id}/history",
+    tag = "Boards",
+    params(
+        ("id" = i32, Path, description = "Board ID"),
+        ("userId" = String, Query, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "The user's operations on the board", body = ApiResponse<Vec<BoardOperation>>)
+    )
+)]
+pub async fn get_history(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<ApiResponse<Vec<BoardOperation>>>, AppError> {
+    let operations = handlers::history::get_history(&pool, id, &query.user_id).await?;
+    Ok(Json(ApiResponse::success(operations)))
+}
+
+/// Undo the user's last card move, rename or deletion or list rename or
+/// reorder on a board
+#[utoipa::path(
+    post,
+    path = "/api/boards/{id}/undo",
+    tag = "Boards",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    request_body = UndoRequest,
+    responses(
+        (status = 200, description = "Operation undone", body = ApiResponse<BoardOperation>),
+        (status = 404, description = "Nothing to undo"),
+        (status = 409, description = "The card or list has changed since")
+    )
+)]
+pub async fn undo(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<UndoRequest>,
+) -> Result<Json<ApiResponse<BoardOperation>>, AppError> {
+    let operation = handlers::history::undo(&pool, id, &req.user_id).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        operation,
+        "Operation undone".to_string(),
+    )))
+}
+
+/// Redo the user's last undone operation on a board
+#[utoipa::path(
+    post,
+    path = "/api/boards/{id}/redo",
+    tag = "Boards",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    request_body = UndoRequest,
+    responses(
+        (status = 200, description = "Operation redone", body = ApiResponse<BoardOperation>),
+        (status = 404, description = "Nothing to redo"),
+        (status = 409, description = "The card or list has changed since")
+    )
+)]
+pub async fn redo(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<UndoRequest>,
+) -> Result<Json<ApiResponse<BoardOperation>>, AppError> {
+    let operation = handlers::history::redo(&pool, id, &req.user_id).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        operation,
+        "Operation redone".to_string(),
+    )))
+}
+
+/// Get who is viewing a board and which card each has open
+#[utoipa::path(
+    get,
+    path = "/api/boards/{id}/presence",
+    tag = "Boards",
+    params(
+        ("id" = i32, Path, description = "Board ID")
+    ),
+    responses(
+        (status = 200, description = "Users viewing the board, longest viewing first", body = ApiResponse<Vec<BoardViewer>>),
+        (status = 404, description = "Board not found")
+    )
+)]
+pub async fn get_presence(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Vec<BoardViewer>>>, AppError> {
+    handlers::boards::get_board_by_id(&pool, id).await?;
+    let viewers = handlers::presence::get_presence(&pool, id).await?;
+    Ok(Json(ApiResponse::success(viewers)))
+}
+
+/// View a board. Upgrades to a WebSocket on which the client sends
+/// `PresenceClientMessage`s (heartbeats at least every 30 seconds, and the
+/// card it has open) and receives `PresenceServerMessage`s with everyone
+/// viewing the board whenever that changes.
+#[utoipa::path(
+    get,
+    path = "/api/boards/{id}/presence/ws",
+    tag = "Boards",
+    params(
+        ("id" = i32, Path, description = "Board ID"),
+        ("userId" = String, Query, description = "User viewing the board")
+    ),
+    responses(
+        (status = 101, description = "Switched to the presence WebSocket"),
+        (status = 404, description = "Board not found"),
+        (status = 400, description = "Missing userId")
+    )
+)]
+pub async fn watch_presence(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Query(query): Query<PresenceQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    if query.user_id.trim().is_empty() {
+        return Err(AppError::ValidationError("userId is required".to_string()));
+    }
+    handlers::boards::get_board_by_id(&pool, id).await?;
+    Ok(ws.on_upgrade(move |socket| handlers::presence::watch_board(pool, id, query.user_id, socket)))
+}
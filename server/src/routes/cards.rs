@@ -1,7 +1,8 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{ws::WebSocketUpgrade, Path, Query, State},
     http::StatusCode,
-    routing::get,
+    response::{IntoResponse, Redirect, Response},
+    routing::{delete, get, post},
     Json, Router,
 };
 use serde::Deserialize;
@@ -9,17 +10,69 @@ use serde::Deserialize;
 use crate::db::DbPool;
 use crate::errors::AppError;
 use crate::handlers;
-use crate::models::{ApiResponse, Card, CreateCardRequest, UpdateCardRequest};
+use crate::models::{
+    ApiResponse, Card, CardComment, CardCommit, CardRecurrence, CardRevision, CardRevisionDiff,
+    CopyCardRequest, CreateCardRequest, CreateCommentRequest, CreateRecurrenceRequest,
+    CreateTimeEntryRequest, MoveCardRequest, RestoreRevisionRequest, RevisionDiffQuery,
+    StartTimerRequest, StopTimerRequest, TimeEntry, UpdateCardRequest, UpdateRecurrenceRequest,
+    WatchCardRequest,
+};
 
 pub fn router() -> Router<DbPool> {
     Router::new()
         .route("/", get(get_cards).post(create_card))
         .route("/:id", get(get_card).put(update_card).delete(delete_card))
+        .route("/:id/move", post(move_card))
+        .route("/:id/copy", post(copy_card))
+        .route("/:id/time-entries", get(get_time_entries).post(create_time_entry))
+        .route("/:id/timer/start", post(start_timer))
+        .route("/:id/timer/stop", post(stop_timer))
+        .route(
+            "/:id/recurrence",
+            get(get_recurrence).post(create_recurrence).put(update_recurrence),
+        )
+        .route("/:id/recurrence/pause", post(pause_recurrence))
+        .route("/:id/recurrence/resume", post(resume_recurrence))
+        .route("/:id/recurrence/end", post(end_recurrence))
+        .route("/:id/comments", get(get_comments).post(create_comment))
+        .route("/:id/watchers", get(get_watchers).post(watch_card))
+        .route("/:id/watchers/:user_id", delete(unwatch_card))
+        .route("/:id/commits", get(get_commits))
+        .route("/:id/revisions", get(get_revisions))
+        .route("/:id/revisions/diff", get(diff_revisions))
+        .route("/:id/revisions/:revision/restore", post(restore_revision))
+        .route("/:id/description/collab", get(collaborate_on_description))
 }
 
 #[derive(Deserialize)]
 pub(crate) struct CardsQuery {
     list_id: Option<i32>,
+    #[serde(default)]
+    html: bool,
+}
+
+/// User deleting a card, so the deletion can be undone
+#[derive(Deserialize)]
+pub(crate) struct DeleteCardQuery {
+    #[serde(rename = "userId")]
+    user_id: Option<String>,
+}
+
+/// User joining a description's collaboration channel
+#[derive(Deserialize)]
+pub(crate) struct CollabQuery {
+    #[serde(rename = "userId")]
+    user_id: String,
+}
+
+/// Largest sync message accepted on a collaboration channel
+const MAX_SYNC_MESSAGE_BYTES: usize = 4 << 20;
+
+/// Whether to include `descriptionHtml`
+#[derive(Deserialize)]
+pub(crate) struct HtmlQuery {
+    #[serde(default)]
+    pub(crate) html: bool,
 }
 
 /// Get all cards, optionally filtered by list_id
@@ -28,7 +81,8 @@ pub(crate) struct CardsQuery {
     path = "/api/cards",
     tag = "Cards",
     params(
-        ("list_id" = Option<i32>, Query, description = "Filter cards by list ID")
+        ("list_id" = Option<i32>, Query, description = "Filter cards by list ID"),
+        ("html" = Option<bool>, Query, description = "Include descriptionHtml, the description rendered from Markdown")
     ),
     responses(
         (status = 200, description = "List of cards", body = ApiResponse<Vec<Card>>)
@@ -38,29 +92,45 @@ pub async fn get_cards(
     State(pool): State<DbPool>,
     Query(params): Query<CardsQuery>,
 ) -> Result<Json<ApiResponse<Vec<Card>>>, AppError> {
-    let cards = handlers::cards::get_all_cards(&pool, params.list_id).await?;
+    let mut cards = handlers::cards::get_all_cards(&pool, params.list_id).await?;
+    if params.html {
+        handlers::markdown::attach_card_html(&pool, &mut cards).await?;
+    }
     Ok(Json(ApiResponse::success(cards)))
 }
 
-/// Get a single card by ID
+/// Get a single card by ID or key. Keys a card used on a previous board
+/// redirect to its current key.
 #[utoipa::path(
     get,
     path = "/api/cards/{id}",
     tag = "Cards",
     params(
-        ("id" = i32, Path, description = "Card ID")
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42"),
+        ("html" = Option<bool>, Query, description = "Include descriptionHtml, the description rendered from Markdown")
     ),
     responses(
         (status = 200, description = "Card found", body = ApiResponse<Card>),
+        (status = 308, description = "Card moved boards; redirects to its current key"),
         (status = 404, description = "Card not found")
     )
 )]
 pub async fn get_card(
     State(pool): State<DbPool>,
-    Path(id): Path<i32>,
-) -> Result<Json<ApiResponse<Card>>, AppError> {
-    let card = handlers::cards::get_card_by_id(&pool, id).await?;
-    Ok(Json(ApiResponse::success(card)))
+    Path(card_ref): Path<String>,
+    Query(params): Query<HtmlQuery>,
+) -> Result<Response, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    if let Some(current_key) = resolved.redirect_to {
+        let query = if params.html { "?html=true" } else { "" };
+        return Ok(Redirect::permanent(&format!("/api/cards/{}{}", current_key, query)).into_response());
+    }
+
+    let mut card = handlers::cards::get_card_by_id(&pool, resolved.id).await?;
+    if params.html {
+        handlers::markdown::attach_card_html(&pool, std::slice::from_mut(&mut card)).await?;
+    }
+    Ok(Json(ApiResponse::success(card)).into_response())
 }
 
 /// Create a new card
@@ -71,20 +141,21 @@ pub async fn get_card(
     request_body = CreateCardRequest,
     responses(
         (status = 201, description = "Card created", body = ApiResponse<Card>),
-        (status = 400, description = "Invalid input")
+        (status = 400, description = "Invalid input"),
+        (status = 409, description = "Strict WIP limit of the list exceeded")
     )
 )]
 pub async fn create_card(
     State(pool): State<DbPool>,
     Json(req): Json<CreateCardRequest>,
 ) -> Result<(StatusCode, Json<ApiResponse<Card>>), AppError> {
-    let card = handlers::cards::create_card(&pool, req).await?;
+    let (card, wip_warning) = handlers::cards::create_card(&pool, req).await?;
     Ok((
         StatusCode::CREATED,
-        Json(ApiResponse::success_with_message(
-            card,
-            "Card created successfully".to_string(),
-        )),
+        Json(
+            ApiResponse::success_with_message(card, "Card created successfully".to_string())
+                .with_warning(wip_warning),
+        ),
     ))
 }
 
@@ -94,25 +165,27 @@ pub async fn create_card(
     path = "/api/cards/{id}",
     tag = "Cards",
     params(
-        ("id" = i32, Path, description = "Card ID")
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
     ),
     request_body = UpdateCardRequest,
     responses(
         (status = 200, description = "Card updated", body = ApiResponse<Card>),
         (status = 404, description = "Card not found"),
-        (status = 400, description = "Invalid input")
+        (status = 400, description = "Invalid input"),
+        (status = 409, description = "Strict WIP limit of the target list exceeded")
     )
 )]
 pub async fn update_card(
     State(pool): State<DbPool>,
-    Path(id): Path<i32>,
+    Path(card_ref): Path<String>,
     Json(req): Json<UpdateCardRequest>,
 ) -> Result<Json<ApiResponse<Card>>, AppError> {
-    let card = handlers::cards::update_card(&pool, id, req).await?;
-    Ok(Json(ApiResponse::success_with_message(
-        card,
-        "Card updated successfully".to_string(),
-    )))
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let (card, wip_warning) = handlers::cards::update_card(&pool, resolved.id, req).await?;
+    Ok(Json(
+        ApiResponse::success_with_message(card, "Card updated successfully".to_string())
+            .with_warning(wip_warning),
+    ))
 }
 
 /// Delete a card
@@ -121,7 +194,8 @@ pub async fn update_card(
     path = "/api/cards/{id}",
     tag = "Cards",
     params(
-        ("id" = i32, Path, description = "Card ID")
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42"),
+        ("userId" = Option<String>, Query, description = "User deleting the card; adds the deletion to their undo stack")
     ),
     responses(
         (status = 200, description = "Card deleted"),
@@ -130,10 +204,594 @@ pub async fn update_card(
 )]
 pub async fn delete_card(
     State(pool): State<DbPool>,
-    Path(id): Path<i32>,
+    Path(card_ref): Path<String>,
+    Query(query): Query<DeleteCardQuery>,
 ) -> Result<Json<ApiResponse<()>>, AppError> {
-    handlers::cards::delete_card(&pool, id).await?;
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    handlers::cards::delete_card(&pool, resolved.id, query.user_id.as_deref()).await?;
     Ok(Json(ApiResponse::message_only(
         "Card deleted successfully".to_string(),
     )))
 }
+
+/// Move a card to a position within a list and lane
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/move",
+    tag = "Cards",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    request_body = MoveCardRequest,
+    responses(
+        (status = 200, description = "Card moved", body = ApiResponse<Card>),
+        (status = 404, description = "Card, list or lane not found"),
+        (status = 400, description = "Lane does not belong to the list's board"),
+        (status = 409, description = "Strict WIP limit of the target list exceeded")
+    )
+)]
+pub async fn move_card(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Json(req): Json<MoveCardRequest>,
+) -> Result<Json<ApiResponse<Card>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let (card, wip_warning) = handlers::cards::move_card(&pool, resolved.id, req).await?;
+    Ok(Json(
+        ApiResponse::success_with_message(card, "Card moved successfully".to_string())
+            .with_warning(wip_warning),
+    ))
+}
+
+/// Copy a card to any list and position
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/copy",
+    tag = "Cards",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    request_body = CopyCardRequest,
+    responses(
+        (status = 201, description = "Card copied", body = ApiResponse<Card>),
+        (status = 404, description = "Card or target list not found"),
+        (status = 400, description = "Invalid input"),
+        (status = 409, description = "Strict WIP limit of the target list exceeded")
+    )
+)]
+pub async fn copy_card(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Json(req): Json<CopyCardRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<Card>>), AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let (card, wip_warning) = handlers::copy::copy_card(&pool, resolved.id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(
+            ApiResponse::success_with_message(card, "Card copied successfully".to_string())
+                .with_warning(wip_warning),
+        ),
+    ))
+}
+
+/// Get all time entries of a card
+#[utoipa::path(
+    get,
+    path = "/api/cards/{id}/time-entries",
+    tag = "Time Tracking",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    responses(
+        (status = 200, description = "Time entries of the card", body = ApiResponse<Vec<TimeEntry>>),
+        (status = 404, description = "Card not found")
+    )
+)]
+pub async fn get_time_entries(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+) -> Result<Json<ApiResponse<Vec<TimeEntry>>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let entries = handlers::time_entries::get_time_entries_for_card(&pool, resolved.id).await?;
+    Ok(Json(ApiResponse::success(entries)))
+}
+
+/// Log a finished time entry on a card
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/time-entries",
+    tag = "Time Tracking",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    request_body = CreateTimeEntryRequest,
+    responses(
+        (status = 201, description = "Time entry created", body = ApiResponse<TimeEntry>),
+        (status = 404, description = "Card not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn create_time_entry(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Json(req): Json<CreateTimeEntryRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<TimeEntry>>), AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let entry = handlers::time_entries::create_time_entry(&pool, resolved.id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(
+            entry,
+            "Time entry created successfully".to_string(),
+        )),
+    ))
+}
+
+/// Start a timer on a card, stopping the user's other running timer
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/timer/start",
+    tag = "Time Tracking",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    request_body = StartTimerRequest,
+    responses(
+        (status = 201, description = "Timer started", body = ApiResponse<TimeEntry>),
+        (status = 404, description = "Card not found"),
+        (status = 409, description = "A timer was started concurrently for this user")
+    )
+)]
+pub async fn start_timer(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Json(req): Json<StartTimerRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<TimeEntry>>), AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let entry = handlers::time_entries::start_timer(&pool, resolved.id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(entry, "Timer started".to_string())),
+    ))
+}
+
+/// Stop the user's running timer on a card
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/timer/stop",
+    tag = "Time Tracking",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    request_body = StopTimerRequest,
+    responses(
+        (status = 200, description = "Timer stopped", body = ApiResponse<TimeEntry>),
+        (status = 404, description = "No running timer found")
+    )
+)]
+pub async fn stop_timer(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Json(req): Json<StopTimerRequest>,
+) -> Result<Json<ApiResponse<TimeEntry>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let entry = handlers::time_entries::stop_timer(&pool, resolved.id, req).await?;
+    Ok(Json(ApiResponse::success_with_message(entry, "Timer stopped".to_string())))
+}
+
+/// Get the recurring series a card is the template of
+#[utoipa::path(
+    get,
+    path = "/api/cards/{id}/recurrence",
+    tag = "Recurrence",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    responses(
+        (status = 200, description = "Recurring series", body = ApiResponse<CardRecurrence>),
+        (status = 404, description = "Card not found or not recurring")
+    )
+)]
+pub async fn get_recurrence(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+) -> Result<Json<ApiResponse<CardRecurrence>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let recurrence = handlers::recurrence::get_recurrence(&pool, resolved.id).await?;
+    Ok(Json(ApiResponse::success(recurrence)))
+}
+
+/// Make a card the template of a recurring series
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/recurrence",
+    tag = "Recurrence",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    request_body = CreateRecurrenceRequest,
+    responses(
+        (status = 201, description = "Recurring series created", body = ApiResponse<CardRecurrence>),
+        (status = 404, description = "Card or list not found"),
+        (status = 409, description = "Card already has a recurrence"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn create_recurrence(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Json(req): Json<CreateRecurrenceRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<CardRecurrence>>), AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let recurrence = handlers::recurrence::create_recurrence(&pool, resolved.id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(
+            recurrence,
+            "Recurrence created successfully".to_string(),
+        )),
+    ))
+}
+
+/// Edit the rule, list, lead time or end of a recurring series
+#[utoipa::path(
+    put,
+    path = "/api/cards/{id}/recurrence",
+    tag = "Recurrence",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    request_body = UpdateRecurrenceRequest,
+    responses(
+        (status = 200, description = "Recurring series updated", body = ApiResponse<CardRecurrence>),
+        (status = 404, description = "Card not found or not recurring"),
+        (status = 400, description = "Invalid input or series ended")
+    )
+)]
+pub async fn update_recurrence(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Json(req): Json<UpdateRecurrenceRequest>,
+) -> Result<Json<ApiResponse<CardRecurrence>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let recurrence = handlers::recurrence::update_recurrence(&pool, resolved.id, req).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        recurrence,
+        "Recurrence updated successfully".to_string(),
+    )))
+}
+
+/// Pause a recurring series
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/recurrence/pause",
+    tag = "Recurrence",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    responses(
+        (status = 200, description = "Recurring series paused", body = ApiResponse<CardRecurrence>),
+        (status = 404, description = "Card not found or not recurring"),
+        (status = 400, description = "Series ended")
+    )
+)]
+pub async fn pause_recurrence(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+) -> Result<Json<ApiResponse<CardRecurrence>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let recurrence = handlers::recurrence::set_recurrence_paused(&pool, resolved.id, true).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        recurrence,
+        "Recurrence paused".to_string(),
+    )))
+}
+
+/// Resume a paused recurring series, skipping due dates missed while paused
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/recurrence/resume",
+    tag = "Recurrence",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    responses(
+        (status = 200, description = "Recurring series resumed", body = ApiResponse<CardRecurrence>),
+        (status = 404, description = "Card not found or not recurring"),
+        (status = 400, description = "Series ended")
+    )
+)]
+pub async fn resume_recurrence(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+) -> Result<Json<ApiResponse<CardRecurrence>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let recurrence = handlers::recurrence::set_recurrence_paused(&pool, resolved.id, false).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        recurrence,
+        "Recurrence resumed".to_string(),
+    )))
+}
+
+/// End a recurring series; cards already created are kept
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/recurrence/end",
+    tag = "Recurrence",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    responses(
+        (status = 200, description = "Recurring series ended", body = ApiResponse<CardRecurrence>),
+        (status = 404, description = "Card not found or not recurring")
+    )
+)]
+pub async fn end_recurrence(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+) -> Result<Json<ApiResponse<CardRecurrence>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let recurrence = handlers::recurrence::end_recurrence(&pool, resolved.id).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        recurrence,
+        "Recurrence ended".to_string(),
+    )))
+}
+
+/// Get the comments of a card, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/cards/{id}/comments",
+    tag = "Comments",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    responses(
+        (status = 200, description = "List of comments", body = ApiResponse<Vec<CardComment>>),
+        (status = 404, description = "Card not found")
+    )
+)]
+pub async fn get_comments(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+) -> Result<Json<ApiResponse<Vec<CardComment>>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let comments = handlers::comments::get_comments_for_card(&pool, resolved.id).await?;
+    Ok(Json(ApiResponse::success(comments)))
+}
+
+/// Comment on a card, notifying its watchers
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/comments",
+    tag = "Comments",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    request_body = CreateCommentRequest,
+    responses(
+        (status = 201, description = "Comment created", body = ApiResponse<CardComment>),
+        (status = 404, description = "Card not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn create_comment(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Json(req): Json<CreateCommentRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<CardComment>>), AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let comment = handlers::comments::create_comment(&pool, resolved.id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(
+            comment,
+            "Comment created successfully".to_string(),
+        )),
+    ))
+}
+
+/// Get the users watching a card
+#[utoipa::path(
+    get,
+    path = "/api/cards/{id}/watchers",
+    tag = "Notifications",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    responses(
+        (status = 200, description = "User IDs of the watchers", body = ApiResponse<Vec<String>>),
+        (status = 404, description = "Card not found")
+    )
+)]
+pub async fn get_watchers(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+) -> Result<Json<ApiResponse<Vec<String>>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let watchers = handlers::notifications::get_card_watchers(&pool, resolved.id).await?;
+    Ok(Json(ApiResponse::success(watchers)))
+}
+
+/// Watch a card to be notified of its comments and due date
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/watchers",
+    tag = "Notifications",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    request_body = WatchCardRequest,
+    responses(
+        (status = 200, description = "User IDs of the watchers", body = ApiResponse<Vec<String>>),
+        (status = 404, description = "Card not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn watch_card(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Json(req): Json<WatchCardRequest>,
+) -> Result<Json<ApiResponse<Vec<String>>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let watchers = handlers::notifications::add_watcher(&pool, resolved.id, &req.user_id).await?;
+    Ok(Json(ApiResponse::success(watchers)))
+}
+
+/// Stop watching a card
+#[utoipa::path(
+    delete,
+    path = "/api/cards/{id}/watchers/{user_id}",
+    tag = "Notifications",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42"),
+        ("user_id" = String, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Stopped watching"),
+        (status = 404, description = "Card not found or not watched by the user")
+    )
+)]
+pub async fn unwatch_card(
+    State(pool): State<DbPool>,
+    Path((card_ref, user_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    handlers::notifications::unwatch_card(&pool, resolved.id, &user_id).await?;
+    Ok(Json(ApiResponse::message_only(
+        "Stopped watching card".to_string(),
+    )))
+}
+
+/// Get the git commits that reference a card, newest first
+#[utoipa::path(
+    get,
+    path = "/api/cards/{id}/commits",
+    tag = "Integrations",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    responses(
+        (status = 200, description = "Linked commits", body = ApiResponse<Vec<CardCommit>>),
+        (status = 404, description = "Card not found")
+    )
+)]
+pub async fn get_commits(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+) -> Result<Json<ApiResponse<Vec<CardCommit>>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let commits = handlers::git::get_commits_for_card(&pool, resolved.id).await?;
+    Ok(Json(ApiResponse::success(commits)))
+}
+
+/// Get the title and description revisions of a card, newest first
+#[utoipa::path(
+    get,
+    path = "/api/cards/{id}/revisions",
+    tag = "Cards",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42")
+    ),
+    responses(
+        (status = 200, description = "Revision history", body = ApiResponse<Vec<CardRevision>>),
+        (status = 404, description = "Card not found")
+    )
+)]
+pub async fn get_revisions(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+) -> Result<Json<ApiResponse<Vec<CardRevision>>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let revisions = handlers::revisions::get_revisions(&pool, resolved.id).await?;
+    Ok(Json(ApiResponse::success(revisions)))
+}
+
+/// Compare two revisions of a card as unified diffs
+#[utoipa::path(
+    get,
+    path = "/api/cards/{id}/revisions/diff",
+    tag = "Cards",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42"),
+        ("from" = i32, Query, description = "Older revision number"),
+        ("to" = Option<i32>, Query, description = "Newer revision number (default the latest)")
+    ),
+    responses(
+        (status = 200, description = "Diffs of the title and description", body = ApiResponse<CardRevisionDiff>),
+        (status = 404, description = "Card or revision not found")
+    )
+)]
+pub async fn diff_revisions(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Query(query): Query<RevisionDiffQuery>,
+) -> Result<Json<ApiResponse<CardRevisionDiff>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let diff = handlers::revisions::diff_revisions(&pool, resolved.id, query.from, query.to).await?;
+    Ok(Json(ApiResponse::success(diff)))
+}
+
+/// Restore the title and description of a revision, recorded as a new revision
+#[utoipa::path(
+    post,
+    path = "/api/cards/{id}/revisions/{revision}/restore",
+    tag = "Cards",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42"),
+        ("revision" = i32, Path, description = "Revision number to restore")
+    ),
+    request_body(content = Option<RestoreRevisionRequest>),
+    responses(
+        (status = 200, description = "Revision restored", body = ApiResponse<Card>),
+        (status = 404, description = "Card or revision not found")
+    )
+)]
+pub async fn restore_revision(
+    State(pool): State<DbPool>,
+    Path((card_ref, revision)): Path<(String, i32)>,
+    req: Option<Json<RestoreRevisionRequest>>,
+) -> Result<Json<ApiResponse<Card>>, AppError> {
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    let (card, wip_warning) = handlers::revisions::restore_revision(&pool, resolved.id, revision, req).await?;
+    Ok(Json(
+        ApiResponse::success_with_message(card, format!("Revision {} restored", revision))
+            .with_warning(wip_warning),
+    ))
+}
+
+/// Edit a card's description together with others. Upgrades to a WebSocket
+/// carrying binary Automerge sync messages; the description is the text
+/// object under the document's `description` key. Changes are written back
+/// to the card's description periodically and when the last editor leaves.
+#[utoipa::path(
+    get,
+    path = "/api/cards/{id}/description/collab",
+    tag = "Cards",
+    params(
+        ("id" = String, Path, description = "Card ID or key, e.g. OPS-42"),
+        ("userId" = String, Query, description = "User editing, credited with the written-back description")
+    ),
+    responses(
+        (status = 101, description = "Switched to the collaboration WebSocket"),
+        (status = 404, description = "Card not found"),
+        (status = 400, description = "Missing userId")
+    )
+)]
+pub async fn collaborate_on_description(
+    State(pool): State<DbPool>,
+    Path(card_ref): Path<String>,
+    Query(query): Query<CollabQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    if query.user_id.trim().is_empty() {
+        return Err(AppError::ValidationError("userId is required".to_string()));
+    }
+    let resolved = handlers::card_keys::resolve_card_ref(&pool, &card_ref).await?;
+    let card = handlers::cards::get_card_by_id(&pool, resolved.id).await?;
+    Ok(ws
+        .max_message_size(MAX_SYNC_MESSAGE_BYTES)
+        .on_upgrade(move |socket| handlers::collab::collaborate(pool, card.id, query.user_id, socket)))
+}
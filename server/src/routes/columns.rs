@@ -11,13 +11,19 @@ use crate::errors::AppError;
 use crate::handlers;
 use crate::handlers::columns_bulk;
 use crate::handlers::columns_bulk::BulkColumnOrderUpdate;
-use crate::models::{ApiResponse, BoardColumn, CreateColumnRequest, UpdateColumnRequest};
+use crate::models::{
+    ApiResponse, BoardColumn, ColumnMoveReport, ColumnRollup, CopyColumnRequest, CreateColumnRequest,
+    MoveColumnRequest, UpdateColumnRequest,
+};
 
 pub fn router() -> Router<DbPool> {
     Router::new()
         .route("/", get(get_lists).post(create_list))
         .route("/bulk-order", axum::routing::patch(bulk_update_column_order))
         .route("/:id", get(get_list).put(update_list).delete(delete_list))
+        .route("/:id/rollup", get(get_list_rollup))
+        .route("/:id/copy", axum::routing::post(copy_list))
+        .route("/:id/move", axum::routing::post(move_list))
 }
 
 #[derive(Deserialize)]
@@ -151,3 +157,75 @@ pub async fn delete_list(
     handlers::columns::delete_column(&pool, id).await?;
     Ok(Json(ApiResponse::message_only("List deleted successfully".to_string())))
 }
+
+/// Get estimate and priority rollups for a list
+#[utoipa::path(
+    get,
+    path = "/api/lists/{id}/rollup",
+    tag = "Lists",
+    params(
+        ("id" = i32, Path, description = "List ID")
+    ),
+    responses(
+        (status = 200, description = "List rollup", body = ApiResponse<ColumnRollup>),
+        (status = 404, description = "List not found")
+    )
+)]
+pub async fn get_list_rollup(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<ColumnRollup>>, AppError> {
+    let rollup = handlers::rollups::get_column_rollup(&pool, id).await?;
+    Ok(Json(ApiResponse::success(rollup)))
+}
+
+/// Copy a list with all its cards into the same or another board
+#[utoipa::path(
+    post,
+    path = "/api/lists/{id}/copy",
+    tag = "Lists",
+    params(
+        ("id" = i32, Path, description = "List ID")
+    ),
+    request_body = CopyColumnRequest,
+    responses(
+        (status = 201, description = "List copied", body = ApiResponse<BoardColumn>),
+        (status = 404, description = "List or target board not found"),
+        (status = 400, description = "Invalid input"),
+        (status = 409, description = "The cards exceed the list's strict WIP limit")
+    )
+)]
+pub async fn copy_list(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<CopyColumnRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<BoardColumn>>), AppError> {
+    let col = handlers::copy::copy_column(&pool, id, req).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success_with_message(col, "List copied successfully".to_string())),
+    ))
+}
+
+/// Move a list with all its cards to an index on the same or another board
+#[utoipa::path(
+    post,
+    path = "/api/lists/{id}/move",
+    tag = "Lists",
+    params(
+        ("id" = i32, Path, description = "List ID")
+    ),
+    request_body = MoveColumnRequest,
+    responses(
+        (status = 200, description = "List moved; reports re-keyed cards and remapped lanes", body = ApiResponse<ColumnMoveReport>),
+        (status = 404, description = "List or target board not found")
+    )
+)]
+pub async fn move_list(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<MoveColumnRequest>,
+) -> Result<Json<ApiResponse<ColumnMoveReport>>, AppError> {
+    let report = handlers::columns_move::move_column(&pool, id, req).await?;
+    Ok(Json(ApiResponse::success_with_message(report, "List moved successfully".to_string())))
+}
@@ -0,0 +1,64 @@
+use axum::{
+    extract::{Path, State},
+    routing::put,
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{ApiResponse, CardComment, UpdateCommentRequest};
+
+pub fn router() -> Router<DbPool> {
+    Router::new().route("/:id", put(update_comment).delete(delete_comment))
+}
+
+/// Edit a comment
+#[utoipa::path(
+    put,
+    path = "/api/comments/{id}",
+    tag = "Comments",
+    params(
+        ("id" = i32, Path, description = "Comment ID")
+    ),
+    request_body = UpdateCommentRequest,
+    responses(
+        (status = 200, description = "Comment updated", body = ApiResponse<CardComment>),
+        (status = 404, description = "Comment not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn update_comment(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<UpdateCommentRequest>,
+) -> Result<Json<ApiResponse<CardComment>>, AppError> {
+    let comment = handlers::comments::update_comment(&pool, id, req).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        comment,
+        "Comment updated successfully".to_string(),
+    )))
+}
+
+/// Delete a comment
+#[utoipa::path(
+    delete,
+    path = "/api/comments/{id}",
+    tag = "Comments",
+    params(
+        ("id" = i32, Path, description = "Comment ID")
+    ),
+    responses(
+        (status = 200, description = "Comment deleted"),
+        (status = 404, description = "Comment not found")
+    )
+)]
+pub async fn delete_comment(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    handlers::comments::delete_comment(&pool, id).await?;
+    Ok(Json(ApiResponse::message_only(
+        "Comment deleted successfully".to_string(),
+    )))
+}
@@ -0,0 +1,161 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Html,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{
+    ApiResponse, EmailSettings, OutboxEmail, OutboxQuery, UnsubscribeQuery, UpdateEmailSettingsRequest,
+};
+
+pub fn router() -> Router<DbPool> {
+    Router::new()
+        .route("/settings", get(get_email_settings).put(update_email_settings))
+        .route("/unsubscribe", get(confirm_unsubscribe).post(unsubscribe))
+        .route("/outbox", get(get_outbox))
+        .route("/outbox/:id/retry", post(retry_email))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SettingsQuery {
+    #[serde(rename = "userId")]
+    user_id: String,
+}
+
+/// Get a user's email address and delivery mode
+#[utoipa::path(
+    get,
+    path = "/api/email/settings",
+    tag = "Email",
+    params(
+        ("userId" = String, Query, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Email settings", body = ApiResponse<EmailSettings>),
+        (status = 404, description = "The user has not set up email")
+    )
+)]
+pub async fn get_email_settings(
+    State(pool): State<DbPool>,
+    Query(query): Query<SettingsQuery>,
+) -> Result<Json<ApiResponse<EmailSettings>>, AppError> {
+    let settings = handlers::email::get_settings(&pool, &query.user_id).await?;
+    Ok(Json(ApiResponse::success(settings)))
+}
+
+/// Set a user's email address or delivery mode
+#[utoipa::path(
+    put,
+    path = "/api/email/settings",
+    tag = "Email",
+    request_body = UpdateEmailSettingsRequest,
+    responses(
+        (status = 200, description = "Email settings updated", body = ApiResponse<EmailSettings>),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn update_email_settings(
+    State(pool): State<DbPool>,
+    Json(req): Json<UpdateEmailSettingsRequest>,
+) -> Result<Json<ApiResponse<EmailSettings>>, AppError> {
+    let settings = handlers::email::update_settings(&pool, req).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        settings,
+        "Email settings updated successfully".to_string(),
+    )))
+}
+
+/// Page the unsubscribe link in a message opens. Asks for confirmation
+/// and changes nothing; its button posts to the same URL.
+#[utoipa::path(
+    get,
+    path = "/api/email/unsubscribe",
+    tag = "Email",
+    params(
+        ("token" = String, Query, description = "Signed token from the unsubscribe link")
+    ),
+    responses(
+        (status = 200, description = "Confirmation page", content_type = "text/html", body = String),
+        (status = 400, description = "Invalid token")
+    )
+)]
+pub async fn confirm_unsubscribe(
+    State(pool): State<DbPool>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<Html<String>, AppError> {
+    let page = handlers::email::unsubscribe_page(&pool, &query.token).await?;
+    Ok(Html(page))
+}
+
+/// Turn email off, from the confirmation page or a mail client's
+/// unsubscribe button (RFC 8058)
+#[utoipa::path(
+    post,
+    path = "/api/email/unsubscribe",
+    tag = "Email",
+    params(
+        ("token" = String, Query, description = "Signed token from the unsubscribe link")
+    ),
+    responses(
+        (status = 200, description = "Unsubscribed", body = ApiResponse<EmailSettings>),
+        (status = 400, description = "Invalid token")
+    )
+)]
+pub async fn unsubscribe(
+    State(pool): State<DbPool>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<Json<ApiResponse<EmailSettings>>, AppError> {
+    let settings = handlers::email::unsubscribe(&pool, &query.token).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        settings,
+        "You will no longer receive notification emails".to_string(),
+    )))
+}
+
+/// Get messages in the email outbox, newest first
+#[utoipa::path(
+    get,
+    path = "/api/email/outbox",
+    tag = "Email",
+    params(
+        ("status" = Option<String>, Query, description = "pending, sent or failed"),
+        ("userId" = Option<String>, Query, description = "Only messages to this user"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of messages (default 50, at most 500)")
+    ),
+    responses(
+        (status = 200, description = "List of messages", body = ApiResponse<Vec<OutboxEmail>>)
+    )
+)]
+pub async fn get_outbox(
+    State(pool): State<DbPool>,
+    Query(query): Query<OutboxQuery>,
+) -> Result<Json<ApiResponse<Vec<OutboxEmail>>>, AppError> {
+    let emails = handlers::email::get_outbox(&pool, &query).await?;
+    Ok(Json(ApiResponse::success(emails)))
+}
+
+/// Send an unsent message again at the next delivery run
+#[utoipa::path(
+    post,
+    path = "/api/email/outbox/{id}/retry",
+    tag = "Email",
+    params(
+        ("id" = i32, Path, description = "Outbox message ID")
+    ),
+    responses(
+        (status = 200, description = "Message queued again", body = ApiResponse<OutboxEmail>),
+        (status = 404, description = "Message not found or already sent")
+    )
+)]
+pub async fn retry_email(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<OutboxEmail>>, AppError> {
+    let email = handlers::email::retry_email(&pool, id).await?;
+    Ok(Json(ApiResponse::success(email)))
+}
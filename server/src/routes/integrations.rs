@@ -0,0 +1,55 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::HeaderMap,
+    routing::post,
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::handlers::git::PushCredentials;
+use crate::models::{ApiResponse, GitPushPayload, GitPushResult};
+
+pub fn router() -> Router<DbPool> {
+    Router::new().route("/git", post(git_push))
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+/// Receive a push event from GitHub, GitLab or Gitea. Commits mentioning a
+/// card key are linked to the card and commented on; "fixes OPS-42" on the
+/// default branch also moves the card to its board's done list.
+#[utoipa::path(
+    post,
+    path = "/api/integrations/git",
+    tag = "Integrations",
+    params(
+        ("X-Hub-Signature-256" = Option<String>, Header, description = "GitHub: sha256=<HMAC-SHA256 of the body>"),
+        ("X-Gitea-Signature" = Option<String>, Header, description = "Gitea: HMAC-SHA256 of the body"),
+        ("X-Gitlab-Token" = Option<String>, Header, description = "GitLab: the shared secret")
+    ),
+    request_body = GitPushPayload,
+    responses(
+        (status = 200, description = "Push processed", body = ApiResponse<GitPushResult>),
+        (status = 400, description = "Invalid payload"),
+        (status = 401, description = "Invalid or missing signature"),
+        (status = 404, description = "GIT_WEBHOOK_SECRET is not set")
+    )
+)]
+pub async fn git_push(
+    State(pool): State<DbPool>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ApiResponse<GitPushResult>>, AppError> {
+    let credentials = PushCredentials {
+        hub_signature: header(&headers, "x-hub-signature-256"),
+        gitea_signature: header(&headers, "x-gitea-signature").or_else(|| header(&headers, "x-gogs-signature")),
+        gitlab_token: header(&headers, "x-gitlab-token"),
+    };
+    let result = handlers::git::handle_push(&pool, &credentials, &body).await?;
+    Ok(Json(ApiResponse::success(result)))
+}
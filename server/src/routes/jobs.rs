@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{ApiResponse, ScheduledJob, UpdateScheduledJobRequest};
+
+pub fn router() -> Router<DbPool> {
+    Router::new()
+        .route("/", get(get_jobs))
+        .route("/:name", put(update_job))
+        .route("/:name/run", post(run_job))
+}
+
+/// Get all scheduled jobs with their last and next runs
+#[utoipa::path(
+    get,
+    path = "/api/jobs",
+    tag = "Jobs",
+    responses(
+        (status = 200, description = "List of jobs", body = ApiResponse<Vec<ScheduledJob>>)
+    )
+)]
+pub async fn get_jobs(
+    State(pool): State<DbPool>,
+) -> Result<Json<ApiResponse<Vec<ScheduledJob>>>, AppError> {
+    let jobs = handlers::jobs::get_all_jobs(&pool).await?;
+    Ok(Json(ApiResponse::success(jobs)))
+}
+
+/// Enable or disable a scheduled job
+#[utoipa::path(
+    put,
+    path = "/api/jobs/{name}",
+    tag = "Jobs",
+    params(
+        ("name" = String, Path, description = "Job name")
+    ),
+    request_body = UpdateScheduledJobRequest,
+    responses(
+        (status = 200, description = "Job updated", body = ApiResponse<ScheduledJob>),
+        (status = 404, description = "Job not found")
+    )
+)]
+pub async fn update_job(
+    State(pool): State<DbPool>,
+    Path(name): Path<String>,
+    Json(req): Json<UpdateScheduledJobRequest>,
+) -> Result<Json<ApiResponse<ScheduledJob>>, AppError> {
+    let job = handlers::jobs::update_job(&pool, &name, req).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        job,
+        "Job updated successfully".to_string(),
+    )))
+}
+
+/// Make a job due now instead of waiting for its schedule
+#[utoipa::path(
+    post,
+    path = "/api/jobs/{name}/run",
+    tag = "Jobs",
+    params(
+        ("name" = String, Path, description = "Job name")
+    ),
+    responses(
+        (status = 200, description = "Job queued", body = ApiResponse<ScheduledJob>),
+        (status = 404, description = "Job not found")
+    )
+)]
+pub async fn run_job(
+    State(pool): State<DbPool>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<ScheduledJob>>, AppError> {
+    let job = handlers::jobs::trigger_job(&pool, &name).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        job,
+        "Job queued".to_string(),
+    )))
+}
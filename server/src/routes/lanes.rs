@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Path, State},
+    routing::put,
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{ApiResponse, Swimlane, UpdateSwimlaneRequest};
+
+pub fn router() -> Router<DbPool> {
+    Router::new().route("/:id", put(update_lane).delete(delete_lane))
+}
+
+/// Rename a swimlane or move it to another index
+#[utoipa::path(
+    put,
+    path = "/api/lanes/{id}",
+    tag = "Lanes",
+    params(
+        ("id" = i32, Path, description = "Lane ID")
+    ),
+    request_body = UpdateSwimlaneRequest,
+    responses(
+        (status = 200, description = "Lane updated", body = ApiResponse<Swimlane>),
+        (status = 404, description = "Lane not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn update_lane(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<UpdateSwimlaneRequest>,
+) -> Result<Json<ApiResponse<Swimlane>>, AppError> {
+    let lane = handlers::lanes::update_lane(&pool, id, req).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        lane,
+        "Lane updated successfully".to_string(),
+    )))
+}
+
+/// Delete a swimlane, moving its cards to the default lane
+#[utoipa::path(
+    delete,
+    path = "/api/lanes/{id}",
+    tag = "Lanes",
+    params(
+        ("id" = i32, Path, description = "Lane ID")
+    ),
+    responses(
+        (status = 200, description = "Lane deleted"),
+        (status = 404, description = "Lane not found"),
+        (status = 400, description = "The default lane cannot be deleted")
+    )
+)]
+pub async fn delete_lane(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    handlers::lanes::delete_lane(&pool, id).await?;
+    Ok(Json(ApiResponse::message_only(
+        "Lane deleted successfully".to_string(),
+    )))
+}
@@ -1,3 +1,16 @@
+pub mod automations;
 pub mod boards;
 pub mod cards;
 pub mod columns;
+pub mod comments;
+pub mod email;
+pub mod integrations;
+pub mod jobs;
+pub mod lanes;
+pub mod notifications;
+pub mod reports;
+pub mod sync;
+pub mod templates;
+pub mod time_entries;
+pub mod users;
+pub mod webhooks;
@@ -0,0 +1,156 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{
+    ApiResponse, MarkAllReadRequest, Notification, NotificationPreference, NotificationQuery,
+    UpdateNotificationPreferencesRequest,
+};
+
+pub fn router() -> Router<DbPool> {
+    Router::new()
+        .route("/", get(get_notifications))
+        .route("/read-all", post(mark_all_read))
+        .route("/preferences", get(get_preferences).put(update_preferences))
+        .route("/:id/read", post(mark_read))
+        .route("/:id/unread", post(mark_unread))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct PreferencesQuery {
+    #[serde(rename = "userId")]
+    user_id: String,
+}
+
+/// Get a user's inbox, newest first
+#[utoipa::path(
+    get,
+    path = "/api/notifications",
+    tag = "Notifications",
+    params(
+        ("userId" = String, Query, description = "User whose inbox to read"),
+        ("unreadOnly" = Option<bool>, Query, description = "Only unread notifications"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of notifications (default 50, at most 200)")
+    ),
+    responses(
+        (status = 200, description = "List of notifications", body = ApiResponse<Vec<Notification>>)
+    )
+)]
+pub async fn get_notifications(
+    State(pool): State<DbPool>,
+    Query(query): Query<NotificationQuery>,
+) -> Result<Json<ApiResponse<Vec<Notification>>>, AppError> {
+    let notifications = handlers::notifications::get_notifications(&pool, &query).await?;
+    Ok(Json(ApiResponse::success(notifications)))
+}
+
+/// Mark a notification read
+#[utoipa::path(
+    post,
+    path = "/api/notifications/{id}/read",
+    tag = "Notifications",
+    params(
+        ("id" = i32, Path, description = "Notification ID")
+    ),
+    responses(
+        (status = 200, description = "Notification marked read", body = ApiResponse<Notification>),
+        (status = 404, description = "Notification not found")
+    )
+)]
+pub async fn mark_read(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Notification>>, AppError> {
+    let notification = handlers::notifications::set_read(&pool, id, true).await?;
+    Ok(Json(ApiResponse::success(notification)))
+}
+
+/// Mark a notification unread
+#[utoipa::path(
+    post,
+    path = "/api/notifications/{id}/unread",
+    tag = "Notifications",
+    params(
+        ("id" = i32, Path, description = "Notification ID")
+    ),
+    responses(
+        (status = 200, description = "Notification marked unread", body = ApiResponse<Notification>),
+        (status = 404, description = "Notification not found")
+    )
+)]
+pub async fn mark_unread(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<Notification>>, AppError> {
+    let notification = handlers::notifications::set_read(&pool, id, false).await?;
+    Ok(Json(ApiResponse::success(notification)))
+}
+
+/// Mark all of a user's notifications read
+#[utoipa::path(
+    post,
+    path = "/api/notifications/read-all",
+    tag = "Notifications",
+    request_body = MarkAllReadRequest,
+    responses(
+        (status = 200, description = "Notifications marked read")
+    )
+)]
+pub async fn mark_all_read(
+    State(pool): State<DbPool>,
+    Json(req): Json<MarkAllReadRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let count = handlers::notifications::mark_all_read(&pool, req).await?;
+    Ok(Json(ApiResponse::message_only(format!(
+        "Marked {} notifications read",
+        count
+    ))))
+}
+
+/// Get which notification kinds a user receives
+#[utoipa::path(
+    get,
+    path = "/api/notifications/preferences",
+    tag = "Notifications",
+    params(
+        ("userId" = String, Query, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Setting for every kind", body = ApiResponse<Vec<NotificationPreference>>)
+    )
+)]
+pub async fn get_preferences(
+    State(pool): State<DbPool>,
+    Query(query): Query<PreferencesQuery>,
+) -> Result<Json<ApiResponse<Vec<NotificationPreference>>>, AppError> {
+    let preferences = handlers::notifications::get_preferences(&pool, &query.user_id).await?;
+    Ok(Json(ApiResponse::success(preferences)))
+}
+
+/// Turn notification kinds on or off for a user
+#[utoipa::path(
+    put,
+    path = "/api/notifications/preferences",
+    tag = "Notifications",
+    request_body = UpdateNotificationPreferencesRequest,
+    responses(
+        (status = 200, description = "Setting for every kind", body = ApiResponse<Vec<NotificationPreference>>),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn update_preferences(
+    State(pool): State<DbPool>,
+    Json(req): Json<UpdateNotificationPreferencesRequest>,
+) -> Result<Json<ApiResponse<Vec<NotificationPreference>>>, AppError> {
+    let preferences = handlers::notifications::update_preferences(&pool, req).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        preferences,
+        "Preferences updated successfully".to_string(),
+    )))
+}
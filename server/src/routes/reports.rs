@@ -0,0 +1,56 @@
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{ApiResponse, ReportFormat, TimeReportQuery, TimeReportRow};
+
+pub fn router() -> Router<DbPool> {
+    Router::new().route("/time", get(get_time_report))
+}
+
+/// Aggregate tracked time by user, board, label or day
+#[utoipa::path(
+    get,
+    path = "/api/reports/time",
+    tag = "Time Tracking",
+    params(
+        ("groupBy" = Option<String>, Query, description = "user (default), board, label or day"),
+        ("format" = Option<String>, Query, description = "json (default) or csv"),
+        ("userId" = Option<String>, Query, description = "Only entries of this user"),
+        ("boardId" = Option<i32>, Query, description = "Only entries on cards of this board"),
+        ("label" = Option<String>, Query, description = "Only entries on cards with this label"),
+        ("from" = Option<String>, Query, description = "First day (YYYY-MM-DD, inclusive)"),
+        ("to" = Option<String>, Query, description = "Last day (YYYY-MM-DD, inclusive)")
+    ),
+    responses(
+        (status = 200, description = "Time report", body = ApiResponse<Vec<TimeReportRow>>),
+        (status = 400, description = "Invalid query")
+    )
+)]
+pub async fn get_time_report(
+    State(pool): State<DbPool>,
+    Query(query): Query<TimeReportQuery>,
+) -> Result<Response, AppError> {
+    let rows = handlers::time_entries::get_time_report(&pool, &query).await?;
+
+    let response = match query.format {
+        ReportFormat::Json => Json(ApiResponse::success(rows)).into_response(),
+        ReportFormat::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (header::CONTENT_DISPOSITION, "attachment; filename=\"time-report.csv\""),
+            ],
+            handlers::time_entries::time_report_to_csv(query.group_by, &rows),
+        )
+            .into_response(),
+    };
+
+    Ok(response)
+}
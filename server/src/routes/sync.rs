@@ -0,0 +1,57 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{ApiResponse, SyncOperationResult, SyncQuery, SyncRequest, SyncResponse};
+
+pub fn router() -> Router<DbPool> {
+    Router::new().route("/", get(get_changes).post(push_operations))
+}
+
+/// Pull the boards, lists and cards a user can see that were created,
+/// updated or deleted since a sync token
+#[utoipa::path(
+    get,
+    path = "/api/sync",
+    tag = "Sync",
+    params(
+        ("userId" = String, Query, description = "User ID"),
+        ("since" = Option<String>, Query, description = "Token from the previous sync; omit for a full sync")
+    ),
+    responses(
+        (status = 200, description = "Changes and the next token", body = ApiResponse<SyncResponse>),
+        (status = 400, description = "Invalid sync token")
+    )
+)]
+pub async fn get_changes(
+    State(pool): State<DbPool>,
+    Query(query): Query<SyncQuery>,
+) -> Result<Json<ApiResponse<SyncResponse>>, AppError> {
+    let changes = handlers::sync::get_changes(&pool, &query.user_id, query.since.as_deref()).await?;
+    Ok(Json(ApiResponse::success(changes)))
+}
+
+/// Push a batch of changes made offline. Each operation is applied on its
+/// own and reported as applied, conflicting or failed.
+#[utoipa::path(
+    post,
+    path = "/api/sync",
+    tag = "Sync",
+    request_body = SyncRequest,
+    responses(
+        (status = 200, description = "One result per operation, in order", body = ApiResponse<Vec<SyncOperationResult>>),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn push_operations(
+    State(pool): State<DbPool>,
+    Json(req): Json<SyncRequest>,
+) -> Result<Json<ApiResponse<Vec<SyncOperationResult>>>, AppError> {
+    let results = handlers::sync::apply_operations(&pool, req).await?;
+    Ok(Json(ApiResponse::success(results)))
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{ApiResponse, BoardTemplate};
+
+pub fn router() -> Router<DbPool> {
+    Router::new()
+        .route("/", get(get_templates))
+        .route("/:id", get(get_template).delete(delete_template))
+}
+
+/// Get all board templates
+#[utoipa::path(
+    get,
+    path = "/api/templates",
+    tag = "Templates",
+    responses(
+        (status = 200, description = "List of templates", body = ApiResponse<Vec<BoardTemplate>>)
+    )
+)]
+pub async fn get_templates(
+    State(pool): State<DbPool>,
+) -> Result<Json<ApiResponse<Vec<BoardTemplate>>>, AppError> {
+    let templates = handlers::templates::get_all_templates(&pool).await?;
+    Ok(Json(ApiResponse::success(templates)))
+}
+
+/// Get a single board template by ID
+#[utoipa::path(
+    get,
+    path = "/api/templates/{id}",
+    tag = "Templates",
+    params(
+        ("id" = i32, Path, description = "Template ID")
+    ),
+    responses(
+        (status = 200, description = "Template found", body = ApiResponse<BoardTemplate>),
+        (status = 404, description = "Template not found")
+    )
+)]
+pub async fn get_template(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<BoardTemplate>>, AppError> {
+    let template = handlers::templates::get_template_by_id(&pool, id).await?;
+    Ok(Json(ApiResponse::success(template)))
+}
+
+/// Delete a saved board template
+#[utoipa::path(
+    delete,
+    path = "/api/templates/{id}",
+    tag = "Templates",
+    params(
+        ("id" = i32, Path, description = "Template ID")
+    ),
+    responses(
+        (status = 200, description = "Template deleted"),
+        (status = 400, description = "Built-in templates cannot be deleted"),
+        (status = 404, description = "Template not found")
+    )
+)]
+pub async fn delete_template(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    handlers::templates::delete_template(&pool, id).await?;
+    Ok(Json(ApiResponse::message_only(
+        "Template deleted successfully".to_string(),
+    )))
+}
@@ -0,0 +1,37 @@
+use axum::{
+    extract::{Path, State},
+    routing::delete,
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::ApiResponse;
+
+pub fn router() -> Router<DbPool> {
+    Router::new().route("/:id", delete(delete_time_entry))
+}
+
+/// Delete a time entry
+#[utoipa::path(
+    delete,
+    path = "/api/time-entries/{id}",
+    tag = "Time Tracking",
+    params(
+        ("id" = i32, Path, description = "Time entry ID")
+    ),
+    responses(
+        (status = 200, description = "Time entry deleted"),
+        (status = 404, description = "Time entry not found")
+    )
+)]
+pub async fn delete_time_entry(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    handlers::time_entries::delete_time_entry(&pool, id).await?;
+    Ok(Json(ApiResponse::message_only(
+        "Time entry deleted successfully".to_string(),
+    )))
+}
@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{ApiResponse, UpdateUserProfileRequest, UserProfile};
+
+pub fn router() -> Router<DbPool> {
+    Router::new().route("/:id", get(get_profile).put(update_profile))
+}
+
+/// Get a user's display name
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}",
+    tag = "Users",
+    params(
+        ("id" = String, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "User profile", body = ApiResponse<UserProfile>)
+    )
+)]
+pub async fn get_profile(
+    State(pool): State<DbPool>,
+    Path(user_id): Path<String>,
+) -> Result<Json<ApiResponse<UserProfile>>, AppError> {
+    let profile = handlers::users::get_profile(&pool, &user_id).await?;
+    Ok(Json(ApiResponse::success(profile)))
+}
+
+/// Set the display name shown for a user, e.g. in resolved mentions
+#[utoipa::path(
+    put,
+    path = "/api/users/{id}",
+    tag = "Users",
+    params(
+        ("id" = String, Path, description = "User ID")
+    ),
+    request_body = UpdateUserProfileRequest,
+    responses(
+        (status = 200, description = "Profile updated", body = ApiResponse<UserProfile>),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn update_profile(
+    State(pool): State<DbPool>,
+    Path(user_id): Path<String>,
+    Json(req): Json<UpdateUserProfileRequest>,
+) -> Result<Json<ApiResponse<UserProfile>>, AppError> {
+    let profile = handlers::users::update_profile(&pool, &user_id, req).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        profile,
+        "Profile updated successfully".to_string(),
+    )))
+}
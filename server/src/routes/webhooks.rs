@@ -0,0 +1,120 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post, put},
+    Json, Router,
+};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::handlers;
+use crate::models::{ApiResponse, UpdateWebhookRequest, Webhook, WebhookDelivery, WebhookDeliveryQuery};
+
+pub fn router() -> Router<DbPool> {
+    Router::new()
+        .route("/:id", put(update_webhook).delete(delete_webhook))
+        .route("/:id/deliveries", get(get_deliveries))
+        .route("/:id/deliveries/:delivery_id/redeliver", post(redeliver))
+}
+
+/// Update a webhook, e.g. to change its events or re-enable it
+#[utoipa::path(
+    put,
+    path = "/api/webhooks/{id}",
+    tag = "Webhooks",
+    params(
+        ("id" = i32, Path, description = "Webhook ID")
+    ),
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook updated", body = ApiResponse<Webhook>),
+        (status = 404, description = "Webhook not found"),
+        (status = 400, description = "Invalid input")
+    )
+)]
+pub async fn update_webhook(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Json(req): Json<UpdateWebhookRequest>,
+) -> Result<Json<ApiResponse<Webhook>>, AppError> {
+    let webhook = handlers::webhooks::update_webhook(&pool, id, req).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        webhook,
+        "Webhook updated successfully".to_string(),
+    )))
+}
+
+/// Delete a webhook and its delivery history
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    tag = "Webhooks",
+    params(
+        ("id" = i32, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook deleted"),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn delete_webhook(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    handlers::webhooks::delete_webhook(&pool, id).await?;
+    Ok(Json(ApiResponse::message_only(
+        "Webhook deleted successfully".to_string(),
+    )))
+}
+
+/// Get the deliveries of a webhook, newest first
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/deliveries",
+    tag = "Webhooks",
+    params(
+        ("id" = i32, Path, description = "Webhook ID"),
+        ("status" = Option<String>, Query, description = "pending, delivered or failed"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of deliveries (default 50, at most 500)")
+    ),
+    responses(
+        (status = 200, description = "Delivery history", body = ApiResponse<Vec<WebhookDelivery>>),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn get_deliveries(
+    State(pool): State<DbPool>,
+    Path(id): Path<i32>,
+    Query(query): Query<WebhookDeliveryQuery>,
+) -> Result<Json<ApiResponse<Vec<WebhookDelivery>>>, AppError> {
+    let deliveries = handlers::webhooks::get_deliveries(&pool, id, &query).await?;
+    Ok(Json(ApiResponse::success(deliveries)))
+}
+
+/// Send a delivery's payload again, as a new delivery
+#[utoipa::path(
+    post,
+    path = "/api/webhooks/{id}/deliveries/{delivery_id}/redeliver",
+    tag = "Webhooks",
+    params(
+        ("id" = i32, Path, description = "Webhook ID"),
+        ("delivery_id" = i32, Path, description = "Delivery ID")
+    ),
+    responses(
+        (status = 202, description = "Delivery queued", body = ApiResponse<WebhookDelivery>),
+        (status = 404, description = "Delivery not found")
+    )
+)]
+pub async fn redeliver(
+    State(pool): State<DbPool>,
+    Path((id, delivery_id)): Path<(i32, i32)>,
+) -> Result<(StatusCode, Json<ApiResponse<WebhookDelivery>>), AppError> {
+    let delivery = handlers::webhooks::redeliver(&pool, id, delivery_id).await?;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ApiResponse::success_with_message(
+            delivery,
+            "Delivery queued".to_string(),
+        )),
+    ))
+}